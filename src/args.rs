@@ -0,0 +1,694 @@
+use crate::{
+    checksum,
+    compression::Compression,
+    split::{self, NameFormat},
+    Error,
+};
+use parse_size::parse_size;
+use std::{ffi::OsString, io, path::PathBuf};
+
+/// The largest buffer size that `--buffer-size` accepts.
+/// Parts that are written concurrently each have buffers of their own, so a typo should not be able to take up all memory.
+const MAX_BUFFER_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The options that only some ways of splitting work with, which [`Options::check_conflicts`] checks against each other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OptionKind {
+    Size,
+    Parts,
+    Halve,
+    Lines,
+    LinesPerPart,
+    Delimiter,
+    Text,
+    Jsonl,
+    Csv,
+    Pad,
+    Stripe,
+    Headers,
+    Filter,
+    Compress,
+    Encrypt,
+    Passphrase,
+    Base64,
+    ZipVolumes,
+    Parity,
+    Redundancy,
+    OutputDir,
+    NoFolder,
+    NameTemplate,
+    Resume,
+    Verify,
+    RemoveSource,
+    Sfv,
+    Sha256sums,
+    JoinScripts,
+    DryRun,
+    FormatGnu,
+    Fit,
+    Stdin,
+    Split,
+    Extract,
+}
+
+impl OptionKind {
+    /// Returns the option as it is given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            OptionKind::Size => "--size",
+            OptionKind::Parts => "--parts",
+            OptionKind::Halve => "--halve",
+            OptionKind::Lines => "--lines",
+            OptionKind::LinesPerPart => "--lines-per-part",
+            OptionKind::Delimiter => "--delimiter",
+            OptionKind::Text => "--text",
+            OptionKind::Jsonl => "--jsonl",
+            OptionKind::Csv => "--csv",
+            OptionKind::Pad => "--pad",
+            OptionKind::Stripe => "--stripe",
+            OptionKind::Headers => "--headers",
+            OptionKind::Filter => "--filter",
+            OptionKind::Compress => "--compress",
+            OptionKind::Encrypt => "--encrypt",
+            OptionKind::Passphrase => "--passphrase",
+            OptionKind::Base64 => "--base64",
+            OptionKind::ZipVolumes => "--zip-volumes",
+            OptionKind::Parity => "--parity",
+            OptionKind::Redundancy => "--redundancy",
+            OptionKind::OutputDir => "--output-dir",
+            OptionKind::NoFolder => "--no-folder",
+            OptionKind::NameTemplate => "--name-template",
+            OptionKind::Resume => "--resume",
+            OptionKind::Verify => "--verify",
+            OptionKind::RemoveSource => "--remove-source",
+            OptionKind::Sfv => "--sfv",
+            OptionKind::Sha256sums => "--sha256sums",
+            OptionKind::JoinScripts => "--join-scripts",
+            OptionKind::DryRun => "--dry-run",
+            OptionKind::FormatGnu => "--format gnu",
+            OptionKind::Fit => "--fit",
+            OptionKind::Stdin => "--stdin",
+            OptionKind::Split => "--split",
+            OptionKind::Extract => "--extract",
+        }
+    }
+}
+
+/// The options given on the command line.
+#[derive(Default)]
+pub struct Options {
+    /// The files or directories to operate on.
+    pub paths: Vec<PathBuf>,
+    /// The split size given with `--size`, or the name of a preset given with `--preset`. See [`crate::split::PRESETS`].
+    /// If this is not given, the user is asked for it.
+    pub split_size: Option<String>,
+    /// The number of parts to split into, given with `--parts`. This is the same as giving `/N` as the split size.
+    pub part_count: Option<usize>,
+    /// Whether to split with [`crate::split::split_parts`] instead of into equal-size chunks.
+    pub halve: bool,
+    /// Whether to only end parts after a newline, given with `--lines` or `--line-bytes`. See [`crate::split::line_parts`].
+    pub lines: bool,
+    /// The number of lines of every part, given with `--lines-per-part`. This replaces the split size.
+    /// See [`crate::split::line_count_parts`].
+    pub lines_per_part: Option<usize>,
+    /// The byte sequence that parts end after, given with `--delimiter` as text or as hexadecimal starting with `0x`.
+    /// See [`crate::split::delimiter_parts`].
+    pub delimiter: Option<Vec<u8>>,
+    /// Whether to fail instead of creating a part longer than the split size if no delimiter is found within it,
+    /// given with `--strict`.
+    pub strict: bool,
+    /// The length of the stripes the file is dealt out to the parts in, given with `--stripe`. It is parsed like the split size.
+    /// See [`crate::split::stripe_parts`].
+    pub stripe: Option<String>,
+    /// Whether to only end parts between UTF-8 characters, given with `--text`. See [`crate::split::text_parts`].
+    pub text: bool,
+    /// Whether to split a JSON Lines file into parts of whole records, given with `--jsonl`.
+    /// This works like `--lines`, or with `--lines-per-part`, but the records at the ends of the parts are also checked to be valid JSON.
+    pub jsonl: bool,
+    /// Whether to split a CSV file into parts of whole records that all start with its header, given with `--csv`.
+    /// See [`crate::split::csv_parts`].
+    pub csv: bool,
+    /// How many parts a split may create without asking first, given with `--max-parts`.
+    /// If this is not given, it is [`crate::split::DEFAULT_MAX_PARTS`].
+    pub max_parts: Option<usize>,
+    /// The byte to pad the last part with up to the split size, given with `--pad` or `--pad=BYTE`.
+    /// Without a value, it is 0.
+    pub pad: Option<u8>,
+    /// Whether every part should start with a header that says which part of which file it is, given with `--headers`.
+    /// See [`crate::part_header`].
+    pub headers: bool,
+    /// The name to name the parts and the split folder after when splitting stdin, given with `--prefix`.
+    /// It is also the name of the joined file.
+    pub prefix: Option<String>,
+    /// The folder to create the split folder in, given with `--out` or `--output-dir`.
+    /// If this is not given, the split folder is created next to the file.
+    pub output_dir: Option<PathBuf>,
+    /// The folder on a removable medium to split the file onto, given with `--fit`.
+    /// Every part fills the free space of the medium, which is swapped between the parts.
+    pub fit: Option<PathBuf>,
+    /// How the parts are numbered in their filenames, given with `--format`.
+    pub format: NameFormat,
+    /// The number of digits of the trailing numbers of the parts, or letters of their suffixes with `--format gnu`,
+    /// given with `--suffix-length`. If this is not given, it is as many as the number of parts needs.
+    pub suffix_length: Option<usize>,
+    /// The template the filenames of the parts are made from, given with `--name-template` or `--template`.
+    pub name_template: Option<String>,
+    /// The separator between the original filename and the trailing number of the parts, given with `--separator`.
+    /// If this is not given, it is [`crate::join::DEFAULT_SEPARATOR`].
+    pub separator: Option<String>,
+    /// Whether to put the parts directly into the output folder instead of a split folder, given with `--no-folder`.
+    pub no_folder: bool,
+    /// The command every part is piped through instead of being written, given with `--filter`.
+    /// When joining, every part is piped through it instead and what it prints is joined.
+    pub filter: Option<String>,
+    /// The algorithm to compress the parts with, given with `--compress` for gzip or `--compress=NAME`.
+    pub compress: Option<Compression>,
+    /// Whether to encrypt the parts with a passphrase, given with `--encrypt`. See [`crate::crypt`].
+    pub encrypt: bool,
+    /// The passphrase to encrypt or decrypt the parts with, given with `--passphrase`.
+    /// If this is not given, it is taken from [`crate::crypt::PASSPHRASE_VARIABLE`] or asked for.
+    pub passphrase: Option<String>,
+    /// Whether the parts have to be encrypted to be joined, given with `--decrypt`.
+    /// Encrypted parts are decrypted either way, but with this, parts that are not encrypted are refused instead of joined as they are.
+    pub decrypt: bool,
+    /// Whether to encode the parts as Base64 text, given with `--base64`. See [`crate::encoding`].
+    pub base64: bool,
+    /// Whether to write a parity file from which one missing part can be rebuilt, given with `--parity`. See [`crate::parity`].
+    pub parity: bool,
+    /// How many recovery parts to write, from which as many missing or corrupted parts can be rebuilt, given with `--redundancy`.
+    /// See [`crate::recovery`].
+    pub redundancy: Option<usize>,
+    /// Whether to write the file into a spanned ZIP archive instead of parts, given with `--zip-volumes`. See [`crate::zip`].
+    pub zip_volumes: bool,
+    /// Whether to read the parts back after splitting and compare them with the file, given with `--verify`.
+    /// Given with a split folder, the parts are only checked against its manifest instead of being joined.
+    pub verify: bool,
+    /// Whether to only list the parts in a split folder and which of them are missing, given with `--list`. See [`crate::join::list`].
+    pub list: bool,
+    /// The algorithm of the checksums in the manifest, given with `--checksum`.
+    pub checksum: checksum::Algorithm,
+    /// Whether to write an SFV file with the CRC32 checksums of the parts, given with `--sfv`.
+    pub sfv: bool,
+    /// Whether to write a `SHA256SUMS` file with the SHA-256 checksums of the parts, given with `--sha256sums`.
+    pub sha256sums: bool,
+    /// Whether to write scripts that join the parts without splitter, given with `--join-scripts`. See [`crate::join_script`].
+    pub join_scripts: bool,
+    /// Whether to remove the file after it was split successfully, given with `--remove-source`.
+    /// This implies `--verify`, so the file is only removed if the parts match it.
+    pub remove_source: bool,
+    /// The path of the joined file, given with `--output`.
+    /// If this is not given, it is `joined-<name>` in the current folder.
+    pub output: Option<PathBuf>,
+    /// Whether to remove the parts and the files describing them once they were joined successfully, given with `--cleanup`.
+    /// The split folder is removed as well if nothing else is left in it.
+    pub cleanup: bool,
+    /// Whether to write the joined file to stdout instead of a file, given with `--stdout`.
+    pub stdout: bool,
+    /// Whether to continue an interrupted split in its split folder, given with `--resume`.
+    pub resume: bool,
+    /// Whether to replace a previous split folder or joined file, given with `--force`.
+    pub force: bool,
+    /// Whether to split a file even if it looks like a part of a split, given with `--force-split`.
+    pub force_split: bool,
+    /// Whether to go on splitting a file that is modified while it is split, such as a growing log, given with `--allow-changes`.
+    /// Without it, splitting is aborted and the split folder removed, as the parts would not fit together.
+    pub allow_changes: bool,
+    /// The start and the length of the range of bytes to extract into a file of their own instead of splitting,
+    /// given with `--extract START LEN`. Both are parsed like split sizes.
+    pub extract: Option<(String, String)>,
+    /// Whether to split every given file on its own instead of joining them, given with `--split`.
+    pub split: bool,
+    /// Whether to split what is read from stdin instead of a file, given with `--stdin`.
+    pub stdin: bool,
+    /// Whether to only print which parts a split would create instead of creating them, given with `--dry-run`.
+    pub dry_run: bool,
+    /// Whether to check the parts against the SFV file of the split before joining, given with `--check-sfv`.
+    pub check_sfv: bool,
+    /// Whether to skip checking the parts against the checksums in the manifest and in `SHA256SUMS` when joining.
+    pub no_verify: bool,
+    /// Whether to skip making sure that there is enough free space before splitting or joining, given with `--no-space-check`.
+    pub no_space_check: bool,
+    /// The size of the buffer the content is read through when splitting, joining, checking and rebuilding parts,
+    /// given with `--buffer-size`.
+    /// If this is not given, it is [`crate::split::DEFAULT_BUFFER_SIZE`].
+    pub buffer_size: Option<usize>,
+    /// How many times a read of a part that failed with an error that may go away is retried, given with `--retries`.
+    /// If this is not given, it is [`crate::join::DEFAULT_RETRIES`].
+    pub retries: Option<usize>,
+    /// Whether to leave the permissions and the modification time of the joined file as they are
+    /// instead of restoring those of the file that was split, given with `--no-preserve`.
+    pub no_preserve: bool,
+    /// Whether to leave out the file length, progress and other informational output, given with `--quiet`.
+    /// Whether splitting or joining succeeded is then only told by the exit code and errors on stderr.
+    pub quiet: bool,
+    /// Whether to print what was done as a JSON object on stdout instead of a message, given with `--json`.
+    /// See [`crate::outcome::Outcome`]. This implies `--quiet`, so that the JSON object is all that is printed.
+    pub json: bool,
+}
+
+impl Options {
+    /// Parses the command-line arguments, not including the program name.
+    ///
+    /// Options that take a value accept it both as `--option value` and as `--option=value`.
+    pub fn parse<I: Iterator<Item = OsString>>(mut args: I) -> Result<Options, Error> {
+        let mut options = Options::default();
+        let mut size_given = false;
+        let mut preset_given = false;
+        let mut line_bytes_given = false;
+
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.to_str() {
+                Some(arg) if arg.starts_with("--") => match arg.split_once('=') {
+                    Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                    None => (arg.to_string(), None),
+                },
+                _ => {
+                    options.paths.push(PathBuf::from(arg));
+                    continue;
+                }
+            };
+
+            let mut value = || get_value(&mut args, &name, inline_value.clone());
+            let flag = || check_flag(&name, &inline_value);
+
+            match name.as_str() {
+                "--size" => {
+                    size_given = true;
+                    options.split_size = Some(value()?)
+                }
+                "--preset" => {
+                    preset_given = true;
+                    options.split_size = Some(parse_preset(&value()?)?)
+                }
+                "--parts" => options.part_count = Some(parse_number(&name, &value()?)?),
+                "--halve" => options.halve = flag()?,
+                "--lines" => options.lines = flag()?,
+                // Like `split -C` and `split --line-bytes` of GNU split.
+                "--line-bytes" => {
+                    line_bytes_given = true;
+                    options.lines = true;
+                    options.split_size = Some(value()?)
+                }
+                "--lines-per-part" => {
+                    options.lines_per_part = Some(parse_number(&name, &value()?)?)
+                }
+                "--delimiter" => options.delimiter = Some(parse_delimiter(&value()?)?),
+                "--strict" => options.strict = flag()?,
+                "--stripe" => options.stripe = Some(value()?),
+                "--text" => options.text = flag()?,
+                "--jsonl" => options.jsonl = flag()?,
+                "--csv" => options.csv = flag()?,
+                "--pad" => {
+                    options.pad = Some(match &inline_value {
+                        Some(byte) => parse_byte(&name, byte)?,
+                        None => 0,
+                    })
+                }
+                "--headers" => options.headers = flag()?,
+                "--max-parts" => options.max_parts = Some(parse_number(&name, &value()?)?),
+                "--prefix" => options.prefix = Some(parse_prefix(&value()?)?),
+                "--out" | "--output-dir" => options.output_dir = Some(PathBuf::from(value()?)),
+                "--fit" => options.fit = Some(PathBuf::from(value()?)),
+                "--output" => options.output = Some(PathBuf::from(value()?)),
+                "--format" => options.format = NameFormat::parse(&value()?)?,
+                "--suffix-length" => options.suffix_length = Some(parse_number(&name, &value()?)?),
+                "--name-template" | "--template" => options.name_template = Some(value()?),
+                "--separator" => options.separator = Some(parse_separator(&value()?)?),
+                "--no-folder" => options.no_folder = flag()?,
+                "--filter" => options.filter = Some(value()?),
+                "--compress" => {
+                    options.compress = Some(match &inline_value {
+                        Some(name) => Compression::parse(name)?,
+                        None => Compression::default(),
+                    })
+                }
+                "--encrypt" => options.encrypt = flag()?,
+                "--decrypt" => options.decrypt = flag()?,
+                "--passphrase" => options.passphrase = Some(value()?),
+                "--base64" => options.base64 = flag()?,
+                "--parity" => options.parity = flag()?,
+                "--redundancy" if !cfg!(feature = "redundancy") => {
+                    return Err(Error(
+                        "--redundancy is not available because splitter was built without the redundancy feature.".into(),
+                    ))
+                }
+                "--redundancy" => options.redundancy = Some(parse_number(&name, &value()?)?),
+                "--zip-volumes" => options.zip_volumes = flag()?,
+                "--verify" => options.verify = flag()?,
+                "--list" => options.list = flag()?,
+                "--checksum" => options.checksum = checksum::Algorithm::parse(&value()?)?,
+                "--sfv" => options.sfv = flag()?,
+                "--sha256sums" => options.sha256sums = flag()?,
+                "--join-scripts" => options.join_scripts = flag()?,
+                "--remove-source" => options.remove_source = flag()?,
+                "--cleanup" => options.cleanup = flag()?,
+                "--stdout" => options.stdout = flag()?,
+                "--resume" => options.resume = flag()?,
+                "--force" => options.force = flag()?,
+                "--force-split" => options.force_split = flag()?,
+                "--allow-changes" => options.allow_changes = flag()?,
+                "--split" => options.split = flag()?,
+                "--extract" => {
+                    let start = value()?;
+                    let len = get_value(&mut args, &name, None)?;
+                    options.extract = Some((start, len));
+                }
+                "--stdin" => options.stdin = flag()?,
+                "--dry-run" => options.dry_run = flag()?,
+                "--check-sfv" => options.check_sfv = flag()?,
+                "--no-verify" => options.no_verify = flag()?,
+                "--no-space-check" => options.no_space_check = flag()?,
+                "--retries" => options.retries = Some(parse_count(&name, &value()?)?),
+                "--buffer-size" => {
+                    options.buffer_size = Some(parse_buffer_size(&name, &value()?)?)
+                }
+                "--no-preserve" => options.no_preserve = flag()?,
+                "--quiet" => options.quiet = flag()?,
+                "--json" => options.json = flag()?,
+                _ => return Err(Error(format!("Unknown option: {}", name).into())),
+            }
+        }
+
+        if size_given && preset_given {
+            return Err(Error("--size and --preset cannot be used together.".into()));
+        }
+        if line_bytes_given && (size_given || preset_given) {
+            return Err(Error(
+                "--line-bytes cannot be used with --size or --preset because it gives the split size itself.".into(),
+            ));
+        }
+
+        // The JSON object has to be all that is printed on stdout.
+        if options.json {
+            if options.stdout {
+                return Err(Error(
+                    "--json cannot be used with --stdout because the joined file is written to stdout."
+                        .into(),
+                ));
+            }
+            options.quiet = true;
+        }
+
+        Ok(options)
+    }
+
+    /// Returns `stdout` for progress and other informational output, or nothing if that was left out with `--quiet`.
+    pub fn info_stdout<'a, 'b>(
+        &self,
+        stdout: &'a mut io::StdoutLock<'b>,
+    ) -> Option<&'a mut io::StdoutLock<'b>> {
+        (!self.quiet).then_some(stdout)
+    }
+
+    /// Returns the size of the buffer the content is read through when splitting, joining, checking and rebuilding parts.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size.unwrap_or(split::DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Returns how many times a read of a part that failed with an error that may go away is retried.
+    pub fn retries(&self) -> usize {
+        self.retries.unwrap_or(crate::join::DEFAULT_RETRIES)
+    }
+
+    /// Returns the separator between the original filename and the trailing number of the parts.
+    pub fn separator(&self) -> &str {
+        self.separator
+            .as_deref()
+            .unwrap_or(crate::join::DEFAULT_SEPARATOR)
+    }
+
+    /// Returns whether `option` was given.
+    pub fn is_given(&self, option: OptionKind) -> bool {
+        match option {
+            OptionKind::Size => self.split_size.is_some(),
+            OptionKind::Parts => self.part_count.is_some(),
+            OptionKind::Halve => self.halve,
+            OptionKind::Lines => self.lines,
+            OptionKind::LinesPerPart => self.lines_per_part.is_some(),
+            OptionKind::Delimiter => self.delimiter.is_some(),
+            OptionKind::Text => self.text,
+            OptionKind::Jsonl => self.jsonl,
+            OptionKind::Csv => self.csv,
+            OptionKind::Pad => self.pad.is_some(),
+            OptionKind::Stripe => self.stripe.is_some(),
+            OptionKind::Headers => self.headers,
+            OptionKind::Filter => self.filter.is_some(),
+            OptionKind::Compress => self.compress.is_some(),
+            OptionKind::Encrypt => self.encrypt,
+            OptionKind::Passphrase => self.passphrase.is_some(),
+            OptionKind::Base64 => self.base64,
+            OptionKind::ZipVolumes => self.zip_volumes,
+            OptionKind::Parity => self.parity,
+            OptionKind::Redundancy => self.redundancy.is_some(),
+            OptionKind::OutputDir => self.output_dir.is_some(),
+            OptionKind::NoFolder => self.no_folder,
+            OptionKind::NameTemplate => self.name_template.is_some(),
+            OptionKind::Resume => self.resume,
+            OptionKind::Verify => self.verify,
+            OptionKind::RemoveSource => self.remove_source,
+            OptionKind::Sfv => self.sfv,
+            OptionKind::Sha256sums => self.sha256sums,
+            OptionKind::JoinScripts => self.join_scripts,
+            OptionKind::DryRun => self.dry_run,
+            OptionKind::FormatGnu => self.format == NameFormat::Gnu,
+            OptionKind::Fit => self.fit.is_some(),
+            OptionKind::Stdin => self.stdin,
+            OptionKind::Split => self.split,
+            OptionKind::Extract => self.extract.is_some(),
+        }
+    }
+
+    /// Makes sure that none of the `conflicting` options were given along with `option`.
+    /// Otherwise, the error names the first of them that was given, and why they cannot be used together if a `reason` is given.
+    pub fn check_conflicts(
+        &self,
+        option: OptionKind,
+        conflicting: &[OptionKind],
+        reason: Option<&str>,
+    ) -> Result<(), Error> {
+        let conflict = conflicting
+            .iter()
+            .find(|conflicting| self.is_given(**conflicting));
+
+        match (conflict, reason) {
+            (None, _) => Ok(()),
+            (Some(conflict), None) => Err(Error(
+                format!("{} cannot be used with {}.", option.name(), conflict.name()).into(),
+            )),
+            (Some(conflict), Some(reason)) => Err(Error(
+                format!(
+                    "{} cannot be used with {} because {}.",
+                    option.name(),
+                    conflict.name(),
+                    reason
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+fn get_value<I: Iterator<Item = OsString>>(
+    args: &mut I,
+    name: &str,
+    inline_value: Option<String>,
+) -> Result<String, Error> {
+    if let Some(value) = inline_value {
+        return Ok(value);
+    }
+
+    match args.next() {
+        Some(value) => value
+            .into_string()
+            .map_err(|_| Error(format!("The value of {} is not valid UTF-8.", name).into())),
+        None => Err(Error(format!("{} requires a value.", name).into())),
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, Error> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(Error(
+            format!("The value of {} must be a positive number.", name).into(),
+        )),
+    }
+}
+
+/// Parses a number that, unlike with [`parse_number`], may be 0.
+fn parse_count(name: &str, value: &str) -> Result<usize, Error> {
+    value
+        .parse::<usize>()
+        .map_err(|_| Error(format!("The value of {} must be a number.", name).into()))
+}
+
+/// Parses a buffer size given like a split size, such as `1MiB`.
+fn parse_buffer_size(name: &str, value: &str) -> Result<usize, Error> {
+    match parse_size(value) {
+        Ok(size) if size > 0 && size <= MAX_BUFFER_SIZE => Ok(size as usize),
+        _ => Err(Error(
+            format!(
+                "The value of {} must be a size from 1 byte to 256 MiB, such as 1MiB.",
+                name
+            )
+            .into(),
+        )),
+    }
+}
+
+/// Parses a byte given in decimal or in hexadecimal starting with `0x`.
+fn parse_byte(name: &str, value: &str) -> Result<u8, Error> {
+    let byte = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse::<u8>(),
+    };
+
+    byte.map_err(|_| {
+        Error(
+            format!(
+                "The value of {} must be a byte from 0 to 255 or from 0x00 to 0xff.",
+                name
+            )
+            .into(),
+        )
+    })
+}
+
+/// Parses a delimiter, which is hexadecimal if it starts with `0x` and text otherwise.
+fn parse_delimiter(delimiter: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || {
+        Error(
+            format!(
+                "Invalid delimiter: {}. It must be text or an even number of hexadecimal digits starting with 0x.",
+                delimiter
+            )
+            .into(),
+        )
+    };
+
+    match delimiter
+        .strip_prefix("0x")
+        .or_else(|| delimiter.strip_prefix("0X"))
+    {
+        Some(hex) if !hex.is_empty() && hex.len() % 2 == 0 => (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).map_err(|_| invalid()))
+            .collect(),
+        Some(_) => Err(invalid()),
+        None if delimiter.is_empty() => Err(invalid()),
+        None => Ok(delimiter.as_bytes().to_vec()),
+    }
+}
+
+/// Makes sure that the parts can be told apart from their trailing numbers with the `separator`.
+fn parse_separator(separator: &str) -> Result<String, Error> {
+    if separator.is_empty() || separator.ends_with(|char: char| char.is_ascii_digit()) {
+        Err(Error(
+            "The separator must not be empty or end with a digit because the trailing numbers of the parts could not be told apart from it."
+                .into(),
+        ))
+    } else if separator.contains('/') || separator.contains('\\') {
+        Err(Error(
+            "The separator must not contain path separators.".into(),
+        ))
+    } else {
+        Ok(separator.to_string())
+    }
+}
+
+/// Makes sure that `name` is the name of a preset, which is then used as the split size.
+fn parse_preset(name: &str) -> Result<String, Error> {
+    match split::get_preset(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(Error(
+            format!(
+                "Unknown preset: {}. Presets are: {}.",
+                name,
+                split::preset_names()
+            )
+            .into(),
+        )),
+    }
+}
+
+/// Makes sure that the parts named after `prefix` stay in their split folder.
+fn parse_prefix(prefix: &str) -> Result<String, Error> {
+    if prefix.is_empty() || prefix == "." || prefix == ".." {
+        Err(Error("The prefix must be a filename.".into()))
+    } else if prefix.contains('/') || prefix.contains('\\') {
+        Err(Error("The prefix must not contain path separators.".into()))
+    } else {
+        Ok(prefix.to_string())
+    }
+}
+
+/// Makes sure that no value was given to an option that does not take one.
+fn check_flag(name: &str, inline_value: &Option<String>) -> Result<bool, Error> {
+    match inline_value {
+        Some(_) => Err(Error(format!("{} does not take a value.", name).into())),
+        None => Ok(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, Error> {
+        Options::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn buffer_size() {
+        assert_eq!(
+            parse(&[]).unwrap().buffer_size(),
+            split::DEFAULT_BUFFER_SIZE
+        );
+        assert_eq!(
+            parse(&["--buffer-size", "1MiB"]).unwrap().buffer_size(),
+            1024 * 1024
+        );
+        assert_eq!(parse(&["--buffer-size", "1"]).unwrap().buffer_size(), 1);
+        assert!(parse(&["--buffer-size", "0"]).is_err());
+        assert!(parse(&["--buffer-size", "257MiB"]).is_err());
+        assert!(parse(&["--buffer-size", "big"]).is_err());
+    }
+
+    #[test]
+    fn check_conflicts() {
+        let options = parse(&["--halve", "--csv", "--format", "gnu"]).unwrap();
+        assert!(options
+            .check_conflicts(
+                OptionKind::Fit,
+                &[OptionKind::Parts, OptionKind::Text],
+                None
+            )
+            .is_ok());
+        assert_eq!(
+            options
+                .check_conflicts(
+                    OptionKind::Fit,
+                    &[OptionKind::Parts, OptionKind::Csv, OptionKind::Halve],
+                    None
+                )
+                .err()
+                .unwrap()
+                .0,
+            "--fit cannot be used with --csv."
+        );
+        assert_eq!(
+            options
+                .check_conflicts(
+                    OptionKind::Stdin,
+                    &[OptionKind::FormatGnu],
+                    Some("it has no length")
+                )
+                .err()
+                .unwrap()
+                .0,
+            "--stdin cannot be used with --format gnu because it has no length."
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Buffers for data that is handed to another thread, which sends them back once it is done with them,
+/// so that the same few buffers are reused instead of allocating one for every chunk.
+///
+/// How many buffers there are is bounded by how many chunks can be queued up for the thread at once.
+pub struct BufferPool {
+    recycled: Receiver<Vec<u8>>,
+    recycler: Sender<Vec<u8>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        let (recycler, recycled) = mpsc::channel();
+        BufferPool { recycled, recycler }
+    }
+
+    /// Returns what the thread sends the buffers back with once it is done with them.
+    pub fn recycler(&self) -> Sender<Vec<u8>> {
+        self.recycler.clone()
+    }
+
+    /// Returns a copy of `data` in a buffer that was sent back, or in a new one if none is available yet.
+    pub fn copy(&self, data: &[u8]) -> Vec<u8> {
+        let mut buffer = self.recycled.try_recv().unwrap_or_default();
+        buffer.clear();
+        buffer.extend_from_slice(data);
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_buffers_sent_back() {
+        let pool = BufferPool::new();
+        let buffer = pool.copy(&[1; 100]);
+        let allocation = buffer.as_ptr();
+        pool.recycler().send(buffer).unwrap();
+
+        let buffer = pool.copy(&[2; 10]);
+        assert_eq!(buffer, [2; 10]);
+        assert_eq!(buffer.as_ptr(), allocation);
+    }
+}
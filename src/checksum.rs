@@ -0,0 +1,130 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/// An algorithm the manifest records the checksums of the parts and the file with, chosen with `--checksum`.
+///
+/// To add an algorithm, add it to [`Algorithm::ALL`], give it names and a [`Hasher`].
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Crc32,
+    Md5,
+    /// This is the default, and the algorithm of manifests that do not name one,
+    /// which were made before the algorithm could be chosen.
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl Algorithm {
+    /// All supported algorithms.
+    pub const ALL: &'static [Algorithm] = &[
+        Algorithm::Crc32,
+        Algorithm::Md5,
+        Algorithm::Sha256,
+        Algorithm::Blake3,
+    ];
+
+    /// Returns the name the algorithm is given with on the command line and in the manifest.
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "crc32",
+            Algorithm::Md5 => "md5",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Returns the name the algorithm is shown with in messages.
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "CRC32",
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// Returns the algorithm called `name`, ignoring case.
+    pub fn parse(name: &str) -> Result<Algorithm, Error> {
+        Algorithm::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                Error(
+                    format!(
+                        "Unknown checksum algorithm: {}. Supported algorithms are: {}.",
+                        name,
+                        Algorithm::ALL
+                            .iter()
+                            .map(|algorithm| algorithm.name())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )
+                    .into(),
+                )
+            })
+    }
+
+    /// Returns a new hasher that computes a checksum with this algorithm.
+    pub fn hasher(self) -> Box<dyn Hasher> {
+        match self {
+            Algorithm::Crc32 => Box::new(crc32fast::Hasher::new()),
+            Algorithm::Md5 => Box::new(md5::Md5::new()),
+            Algorithm::Sha256 => Box::new(sha2::Sha256::new()),
+            Algorithm::Blake3 => Box::new(blake3::Hasher::new()),
+        }
+    }
+}
+
+/// Computes a checksum of everything passed to [`Hasher::update`].
+///
+/// Hashers are [`Send`] so that hashing can happen on another thread.
+pub trait Hasher: Send {
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the checksum in hexadecimal.
+    fn finalize(self: Box<Self>) -> String;
+}
+
+impl Hasher for crc32fast::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        crc32fast::Hasher::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> String {
+        format!("{:08x}", crc32fast::Hasher::finalize(*self))
+    }
+}
+
+impl Hasher for md5::Md5 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> String {
+        crate::to_hex(&Digest::finalize(*self))
+    }
+}
+
+impl Hasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> String {
+        crate::to_hex(&Digest::finalize(*self))
+    }
+}
+
+impl Hasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> String {
+        blake3::Hasher::finalize(&self).to_hex().to_string()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::Error;
+use flate2::{read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+/// An algorithm the parts are compressed with, chosen with `--compress` or `--compress=NAME`.
+///
+/// To add an algorithm, add it to [`Compression::ALL`] and give it a name, an extension, a magic number,
+/// an [`Encoder`] and a decoder.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// This is the default, and the algorithm of manifests that were made before it could be chosen.
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// All supported algorithms.
+    pub const ALL: &'static [Compression] = &[Compression::Gzip, Compression::Zstd];
+
+    /// Returns the name the algorithm is given with on the command line and in the manifest.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the extension that is added to the filenames of parts compressed with this algorithm.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Returns the bytes that data compressed with this algorithm starts with.
+    fn magic(self) -> &'static [u8] {
+        match self {
+            Compression::Gzip => &[0x1f, 0x8b],
+            Compression::Zstd => &[0x28, 0xb5, 0x2f, 0xfd],
+        }
+    }
+
+    /// Returns the algorithm called `name`, ignoring case.
+    ///
+    /// ```
+    /// use splitter::compression::Compression;
+    ///
+    /// assert!(Compression::parse("zstd")? == Compression::Zstd);
+    /// assert!(Compression::parse("GZIP")? == Compression::Gzip);
+    /// assert!(Compression::parse("zip").is_err());
+    /// # Ok::<(), splitter::Error>(())
+    /// ```
+    pub fn parse(name: &str) -> Result<Compression, Error> {
+        Compression::ALL
+            .iter()
+            .copied()
+            .find(|compression| compression.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                Error(
+                    format!(
+                        "Unknown compression: {}. Supported compressions are: {}.",
+                        name,
+                        Compression::ALL
+                            .iter()
+                            .map(|compression| compression.name())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )
+                    .into(),
+                )
+            })
+    }
+
+    /// Returns the algorithm whose [`Compression::extension`] `file_name` ends with, if any.
+    ///
+    /// ```
+    /// use splitter::compression::Compression;
+    ///
+    /// assert!(Compression::from_file_name(b"video.mp4.001.zst") == Some(Compression::Zstd));
+    /// assert!(Compression::from_file_name(b"video.mp4.001.gz") == Some(Compression::Gzip));
+    /// assert!(Compression::from_file_name(b"video.mp4.001").is_none());
+    /// ```
+    pub fn from_file_name(file_name: &[u8]) -> Option<Compression> {
+        Compression::ALL
+            .iter()
+            .copied()
+            .find(|compression| file_name.ends_with(compression.extension().as_bytes()))
+    }
+
+    /// Returns the algorithm that `bytes`, the start of some data, look like they were compressed with, if any.
+    pub(crate) fn from_magic(bytes: &[u8]) -> Option<Compression> {
+        Compression::ALL
+            .iter()
+            .copied()
+            .find(|compression| bytes.starts_with(compression.magic()))
+    }
+
+    /// Returns an encoder that compresses what is written to it into `writer`.
+    pub(crate) fn encoder<W: Write>(self, writer: W) -> io::Result<Encoder<W>> {
+        Ok(match self {
+            Compression::Gzip => {
+                Encoder::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Compression::Zstd => {
+                Encoder::Zstd(zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?)
+            }
+        })
+    }
+
+    /// Returns a reader that decompresses what is read from `reader`.
+    pub(crate) fn decoder<'a, R: Read + 'a>(self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(GzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// Compresses what is written to it with one of the algorithms.
+/// The compressed data is only complete once [`Encoder::finish`] was called.
+pub(crate) enum Encoder<W: Write> {
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Writes the end of the compressed data and returns the writer it was written to.
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+    }
+
+    /// Returns the writer the compressed data is written to.
+    pub(crate) fn get_ref(&self) -> &W {
+        match self {
+            Encoder::Gzip(encoder) => encoder.get_ref(),
+            Encoder::Zstd(encoder) => encoder.get_ref(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Encrypting the parts with a passphrase, given with `--encrypt`.
+//!
+//! The key is derived from the passphrase with Argon2id and a random salt.
+//! The content of a part is encrypted with ChaCha20-Poly1305 in chunks of [`CHUNK_LEN`] bytes,
+//! so that a part never has to fit into memory. An encrypted part is made of, in this order:
+//!
+//! - the [`MAGIC`] bytes,
+//! - the [`VERSION`] of the format as 1 byte,
+//! - the salt the key was derived with as 16 bytes,
+//! - the random start of the nonces of the part as 7 bytes,
+//! - the encrypted chunks, each followed by its 16-byte tag.
+//!
+//! The nonce of a chunk is the start of the nonces, followed by the index of the chunk as 4 big-endian bytes
+//! and a byte that is 1 for the last chunk and 0 for the others, like in the STREAM construction.
+//! That way, chunks cannot be reordered or left out without it being noticed, and neither can a part be cut off.
+//! The last chunk is always shorter than [`CHUNK_LEN`], even if that means that it is empty.
+//!
+//! The salt and the start of the nonces of every part are also recorded in the manifest, which the headers of the parts are checked against
+//! so that parts that were swapped or mixed in from another split are noticed before anything is decrypted.
+//! The headers alone are still enough to decrypt a part without the manifest.
+//!
+//! Neither the passphrase nor the key are stored anywhere.
+
+use crate::{manifest, part_header::read_up_to, to_hex, Error};
+use argon2::Argon2;
+use chacha20poly1305::{aead::AeadInPlace, ChaCha20Poly1305, KeyInit, Nonce, Tag};
+use std::{
+    env,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// The bytes every encrypted part starts with.
+pub const MAGIC: &[u8; 8] = b"SPLITENC";
+
+/// The version of the format of encrypted parts.
+pub const VERSION: u8 = 1;
+
+/// The environment variable the passphrase can be given in instead of typing it in, such as in scripts.
+pub const PASSPHRASE_VARIABLE: &str = "SPLITTER_PASSPHRASE";
+
+/// The length of the content of every chunk but the last one.
+pub const CHUNK_LEN: usize = 64 * 1024;
+
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+
+/// Asks for the passphrase on the terminal without showing it, twice if it is `new` so that typos are caught.
+/// If it was `given` with `--passphrase` or is given in the [`PASSPHRASE_VARIABLE`], that is used instead.
+pub fn ask_passphrase(new: bool, given: Option<&str>) -> Result<String, Error> {
+    let passphrase = if let Some(passphrase) = given {
+        passphrase.to_string()
+    } else if let Ok(passphrase) = env::var(PASSPHRASE_VARIABLE) {
+        passphrase
+    } else {
+        let passphrase = prompt("Passphrase: ")?;
+        if new && prompt("Repeat the passphrase: ")? != passphrase {
+            return Err(Error("The passphrases do not match.".into()));
+        }
+        passphrase
+    };
+
+    if passphrase.is_empty() {
+        Err(Error("The passphrase must not be empty.".into()))
+    } else {
+        Ok(passphrase)
+    }
+}
+
+fn prompt(text: &str) -> Result<String, Error> {
+    rpassword::prompt_password(text).map_err(|err| {
+        Error(
+            format!(
+                "Failed to read the passphrase. {} Please enter it in a terminal, give it with --passphrase or in the {} environment variable.",
+                crate::describe_io_error(&err),
+                PASSPHRASE_VARIABLE
+            )
+            .into(),
+        )
+    })
+}
+
+/// Fills `bytes` with random bytes from the operating system.
+fn random(bytes: &mut [u8]) -> Result<(), Error> {
+    getrandom::getrandom(bytes)
+        .map_err(|err| Error(format!("Failed to generate random bytes. {}.", err).into()))
+}
+
+/// Parses `hex`, such as a salt or a nonce stored in the manifest, into `N` bytes.
+fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut bytes = [0_u8; N];
+    for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// A key derived from a passphrase, along with the salt it was derived with.
+#[derive(Clone)]
+pub struct Key {
+    salt: [u8; SALT_LEN],
+    cipher: ChaCha20Poly1305,
+}
+
+impl Key {
+    /// Derives a key from `passphrase` with a new random salt, to encrypt the parts of a split with.
+    pub fn generate(passphrase: &str) -> Result<Key, Error> {
+        let mut salt = [0_u8; SALT_LEN];
+        random(&mut salt)?;
+        Key::derive(passphrase, salt)
+    }
+
+    /// Derives the key from `passphrase` that the parts of a split were encrypted with,
+    /// whose `salt` is recorded in its manifest as returned by [`Key::salt`].
+    pub fn for_salt(passphrase: &str, salt: &str) -> Result<Key, Error> {
+        let salt = from_hex(salt).ok_or_else(|| {
+            Error(format!("The salt in the {} is invalid.", manifest::FILE_NAME).into())
+        })?;
+        Key::derive(passphrase, salt)
+    }
+
+    /// Returns the salt the key was derived with as hexadecimal, which is how it is recorded in the manifest.
+    pub fn salt(&self) -> String {
+        to_hex(&self.salt)
+    }
+
+    /// Derives the key from `passphrase` that parts with `salt` were encrypted with.
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Key, Error> {
+        let mut key = [0_u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|err| {
+                Error(format!("Failed to derive a key from the passphrase. {}.", err).into())
+            })?;
+
+        Ok(Key {
+            salt,
+            cipher: ChaCha20Poly1305::new(&key.into()),
+        })
+    }
+
+    /// Derives the key that the part read by `reader` was encrypted with from the passphrase returned by `passphrase`,
+    /// if the part is encrypted at all. Only the start of the part is read.
+    pub fn for_part<R: Read, F: FnOnce() -> Result<String, Error>>(
+        reader: &mut R,
+        passphrase: F,
+    ) -> Result<Option<Key>, Error> {
+        match read_header(reader)? {
+            Some(header) => Key::derive(&passphrase()?, header.salt).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// What an encrypted part starts with, after the [`MAGIC`] bytes and the [`VERSION`].
+struct Header {
+    salt: [u8; SALT_LEN],
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+}
+
+/// Reads the salt and the start of the nonces from the start of the part read by `reader` as hexadecimal,
+/// to be compared with what the manifest records, if the part is encrypted at all.
+pub fn read_salt_and_nonce<R: Read>(reader: &mut R) -> Result<Option<(String, String)>, Error> {
+    Ok(read_header(reader)?.map(|header| (to_hex(&header.salt), to_hex(&header.nonce_prefix))))
+}
+
+/// Reads the header from the start of `reader`, if it starts with the [`MAGIC`] bytes.
+fn read_header<R: Read>(reader: &mut R) -> Result<Option<Header>, Error> {
+    let mut magic = [0_u8; MAGIC.len()];
+    if read_up_to(reader, &mut magic)? < magic.len() || &magic != MAGIC {
+        return Ok(None);
+    }
+
+    let mut version = [0_u8; 1];
+    let mut salt = [0_u8; SALT_LEN];
+    let mut nonce_prefix = [0_u8; NONCE_PREFIX_LEN];
+    reader
+        .read_exact(&mut version)
+        .and_then(|()| reader.read_exact(&mut salt))
+        .and_then(|()| reader.read_exact(&mut nonce_prefix))
+        .map_err(|_| Error("The encryption header is cut off.".into()))?;
+
+    if version[0] != VERSION {
+        return Err(Error(
+            format!(
+                "The part was encrypted with version {} of the format, which is not supported. Please update splitter.",
+                version[0]
+            )
+            .into(),
+        ));
+    }
+
+    Ok(Some(Header { salt, nonce_prefix }))
+}
+
+/// Returns the nonce of the chunk at `index` of a part whose nonces start with `nonce_prefix`.
+fn nonce(nonce_prefix: &[u8; NONCE_PREFIX_LEN], index: u32, last: bool) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(nonce_prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_PREFIX_LEN + 4].copy_from_slice(&index.to_be_bytes());
+    nonce[NONCE_PREFIX_LEN + 4] = last as u8;
+    nonce
+}
+
+/// Encrypts everything written to it and passes it on to `inner`.
+///
+/// [`EncryptWriter::finish`] has to be called at the end, as the last chunk is only written then.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    index: u32,
+    chunk: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Writes the header of the part to `inner` with a new random start of the nonces.
+    pub fn new(mut inner: W, key: &Key) -> Result<Self, Error> {
+        let mut nonce_prefix = [0_u8; NONCE_PREFIX_LEN];
+        random(&mut nonce_prefix)?;
+
+        inner
+            .write_all(MAGIC)
+            .and_then(|()| inner.write_all(&[VERSION]))
+            .and_then(|()| inner.write_all(&key.salt))
+            .and_then(|()| inner.write_all(&nonce_prefix))
+            .map_err(Error::io("Failed to write output."))?;
+
+        Ok(EncryptWriter {
+            inner,
+            cipher: key.cipher.clone(),
+            nonce_prefix,
+            index: 0,
+            chunk: Vec::with_capacity(CHUNK_LEN),
+        })
+    }
+
+    /// Returns the random start of the nonces of the part as hexadecimal, which is how it is recorded in the manifest.
+    pub fn nonce(&self) -> String {
+        to_hex(&self.nonce_prefix)
+    }
+
+    fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = nonce(&self.nonce_prefix, self.index, last);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce, b"", &mut self.chunk)
+            .map_err(|_| io::Error::other("The part is too long to be encrypted"))?;
+        self.inner.write_all(&self.chunk)?;
+        self.inner.write_all(&tag)?;
+
+        self.chunk.clear();
+        self.index = self
+            .index
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other("The part is too long to be encrypted"))?;
+        Ok(())
+    }
+
+    /// Writes the last chunk and returns `inner`.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.write_chunk(true)
+            .map_err(Error::io("Failed to write output."))?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(CHUNK_LEN - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..len]);
+
+        // Full chunks are written right away, so the last chunk that is written by `finish` is always shorter.
+        if self.chunk.len() == CHUNK_LEN {
+            self.write_chunk(false)?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts what is read from `inner`.
+///
+/// Nothing is returned from a chunk unless all of it was decrypted and found to be intact.
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    index: u32,
+    chunk: Vec<u8>,
+    position: usize,
+    finished: bool,
+    path: Box<Path>,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Reads the header of the part at `path` from `inner`, which has to have been encrypted with `key`.
+    pub fn new(mut inner: R, key: &Key, path: &Path) -> Result<Self, Error> {
+        let fail = |message: &str| {
+            Error(format!("Failed to decrypt {}. {}", path.to_string_lossy(), message).into())
+        };
+
+        let header = read_header(&mut inner)
+            .map_err(|Error(message)| fail(&message))?
+            .ok_or_else(|| fail("It is not encrypted."))?;
+        if header.salt != key.salt {
+            return Err(fail(
+                "It was encrypted separately from the other parts. Make sure they all belong to the same split.",
+            ));
+        }
+
+        Ok(DecryptReader {
+            inner,
+            cipher: key.cipher.clone(),
+            nonce_prefix: header.nonce_prefix,
+            index: 0,
+            chunk: Vec::with_capacity(CHUNK_LEN + TAG_LEN),
+            position: 0,
+            finished: false,
+            path: path.into(),
+        })
+    }
+
+    fn read_chunk(&mut self) -> io::Result<()> {
+        self.chunk.resize(CHUNK_LEN + TAG_LEN, 0);
+        let len = read_up_to(&mut self.inner, &mut self.chunk).map_err(io::Error::other)?;
+        // Only the last chunk is shorter than a full one.
+        let last = len < self.chunk.len();
+        if len < TAG_LEN {
+            return Err(corrupted(&self.path));
+        }
+
+        let (content, tag) = self.chunk[..len].split_at_mut(len - TAG_LEN);
+        let nonce = nonce(&self.nonce_prefix, self.index, last);
+        self.cipher
+            .decrypt_in_place_detached(&nonce, b"", content, Tag::from_slice(tag))
+            .map_err(|_| corrupted(&self.path))?;
+
+        self.chunk.truncate(len - TAG_LEN);
+        self.position = 0;
+        self.finished = last;
+        self.index = self
+            .index
+            .checked_add(1)
+            .ok_or_else(|| corrupted(&self.path))?;
+        Ok(())
+    }
+}
+
+/// The error for a chunk of the encrypted part at `path` that could not be decrypted.
+fn corrupted(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Failed to decrypt {}. The passphrase is wrong, or the part is corrupted or cut off",
+            path.to_string_lossy()
+        ),
+    )
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.position);
+        buf[..len].copy_from_slice(&self.chunk[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        manifest::Manifest,
+        test_util::{self, TempDir},
+    };
+    use std::fs;
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(from_hex::<2>("00ff"), Some([0, 255]));
+        assert_eq!(from_hex::<2>("00f"), None);
+        assert_eq!(from_hex::<2>("00fg"), None);
+        assert_eq!(from_hex::<1>("+f"), None);
+    }
+
+    #[test]
+    fn records_salt_and_nonces_in_manifest() {
+        let dir = TempDir::new();
+        let data = test_util::data(10_000);
+        let path = dir.write("file.bin", &data);
+        test_util::split(
+            &path,
+            &["--size", "3000", "--encrypt", "--passphrase", "secret"],
+        )
+        .unwrap();
+
+        let split_folder = dir.path().join("file.bin-split");
+        let manifest = Manifest::read(&split_folder).unwrap().unwrap();
+        let salt = manifest.salt.as_deref().unwrap();
+        for part in &manifest.parts {
+            let mut file = fs::File::open(split_folder.join(&part.file_name)).unwrap();
+            let (part_salt, nonce) = read_salt_and_nonce(&mut file).unwrap().unwrap();
+            assert_eq!(part_salt, salt);
+            assert_eq!(part.nonce.as_deref(), Some(nonce.as_str()));
+        }
+
+        let output = dir.path().join("joined");
+        let output_arg = output.to_string_lossy().into_owned();
+        let parts = test_util::files_in(&split_folder);
+        test_util::join(
+            parts.clone(),
+            &[
+                "--passphrase",
+                "secret",
+                "--decrypt",
+                "--output",
+                &output_arg,
+            ],
+        )
+        .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), data);
+
+        fs::remove_file(&output).unwrap();
+        let err = test_util::join(parts, &["--passphrase", "wrong", "--output", &output_arg])
+            .err()
+            .unwrap();
+        assert!(err.0.contains("file.bin-split-1"), "{}", err.0);
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn refuses_swapped_parts() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(10_000));
+        test_util::split(
+            &path,
+            &["--size", "3000", "--encrypt", "--passphrase", "secret"],
+        )
+        .unwrap();
+
+        let split_folder = dir.path().join("file.bin-split");
+        let (first, second) = (
+            split_folder.join("file.bin-split-1"),
+            split_folder.join("file.bin-split-2"),
+        );
+        let swap = split_folder.join("swap");
+        fs::rename(&first, &swap).unwrap();
+        fs::rename(&second, &first).unwrap();
+        fs::rename(&swap, &second).unwrap();
+
+        let output = dir.path().join("joined");
+        let output_arg = output.to_string_lossy().into_owned();
+        let err = test_util::join(
+            test_util::files_in(&split_folder),
+            &["--passphrase", "secret", "--output", &output_arg],
+        )
+        .err()
+        .unwrap();
+        assert!(
+            err.0
+                .contains("was not encrypted as the manifest.json records"),
+            "{}",
+            err.0
+        );
+    }
+
+    #[test]
+    fn passphrase_requires_encrypt() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(10_000));
+        assert!(test_util::split(&path, &["--size", "3000", "--passphrase", "secret"]).is_err());
+        assert!(!dir.path().join("file.bin-split").exists());
+    }
+}
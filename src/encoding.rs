@@ -0,0 +1,212 @@
+//! Encodes parts as text so that they can be sent where only text is safe, such as in the body of an email.
+//!
+//! Parts split with `--base64` are stored as RFC 4648 Base64 wrapped at [`LINE_LEN`] columns, like MIME attachments.
+//! This is the outermost layer of a part, so they are encoded after they are compressed and encrypted.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// How many characters every line of encoded text has, except for the last one.
+pub const LINE_LEN: usize = 76;
+
+/// How many bytes are encoded into one line.
+const LINE_BYTES: usize = LINE_LEN / 4 * 3;
+
+/// How the parts are encoded as text, if they were split with `--base64`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Base64,
+}
+
+impl Encoding {
+    /// Returns the extension that is added to the filenames of encoded parts, after any [`crate::compression::Compression::extension`].
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Base64 => ".b64",
+        }
+    }
+
+    /// Returns the encoding whose [`Encoding::extension`] `file_name` ends with, if any.
+    ///
+    /// ```
+    /// use splitter::encoding::Encoding;
+    ///
+    /// assert!(Encoding::from_file_name(b"video.mp4-split-1.gz.b64") == Some(Encoding::Base64));
+    /// assert!(Encoding::from_file_name(b"video.mp4-split-1").is_none());
+    /// ```
+    pub fn from_file_name(file_name: &[u8]) -> Option<Encoding> {
+        file_name
+            .ends_with(Encoding::Base64.extension().as_bytes())
+            .then_some(Encoding::Base64)
+    }
+}
+
+/// Encodes what is written to it as Base64 lines into the inner writer.
+/// The encoded text is only complete once [`Base64Writer::finish`] was called.
+pub(crate) struct Base64Writer<W: Write> {
+    inner: W,
+    /// The bytes that do not fill a line yet.
+    pending: Vec<u8>,
+    /// The encoded lines, kept so that they do not have to be allocated for every write.
+    lines: Vec<u8>,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Base64Writer {
+            inner,
+            pending: Vec::with_capacity(LINE_BYTES),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Encodes `bytes` as one line and appends it to `lines`.
+    fn encode_line(lines: &mut Vec<u8>, bytes: &[u8]) {
+        let start = lines.len();
+        lines.resize(start + LINE_LEN, 0);
+        let len = STANDARD
+            .encode_slice(bytes, &mut lines[start..])
+            .expect("a line holds the encoding of at most its number of bytes");
+        lines.truncate(start + len);
+        lines.push(b'\n');
+    }
+
+    /// Writes the last line, which is padded if its bytes do not fill a group of three,
+    /// and returns the writer it was written to.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            self.lines.clear();
+            Self::encode_line(&mut self.lines, &self.pending);
+            self.inner.write_all(&self.lines)?;
+        }
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len();
+        self.lines.clear();
+
+        if !self.pending.is_empty() {
+            let taken = (LINE_BYTES - self.pending.len()).min(buf.len());
+            self.pending.extend_from_slice(&buf[..taken]);
+            buf = &buf[taken..];
+            if self.pending.len() < LINE_BYTES {
+                return Ok(len);
+            }
+            Self::encode_line(&mut self.lines, &self.pending);
+            self.pending.clear();
+        }
+
+        let mut lines = buf.chunks_exact(LINE_BYTES);
+        for line in &mut lines {
+            Self::encode_line(&mut self.lines, line);
+        }
+        self.pending.extend_from_slice(lines.remainder());
+
+        self.inner.write_all(&self.lines)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decodes Base64 text read from the inner reader.
+/// Line breaks and other whitespace are skipped, so the lines can have any length.
+pub(crate) struct Base64Reader<R: Read> {
+    inner: R,
+    /// The characters that were read but not decoded yet because they do not fill a group of four.
+    text: Vec<u8>,
+    decoded: Vec<u8>,
+    position: usize,
+    /// Whether padding was decoded, which may only come at the end of the text.
+    padded: bool,
+    /// The path of the part that is read, for errors.
+    path: PathBuf,
+}
+
+impl<R: Read> Base64Reader<R> {
+    pub(crate) fn new(inner: R, path: &Path) -> Self {
+        Base64Reader {
+            inner,
+            text: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+            padded: false,
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Reads and decodes the next chunk of text. Returns `false` once there is nothing left to decode.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0_u8; 16 * 1024];
+        loop {
+            let read_len = self.inner.read(&mut chunk)?;
+            self.text.extend(
+                chunk[..read_len]
+                    .iter()
+                    .filter(|byte| !byte.is_ascii_whitespace()),
+            );
+
+            // Only whole groups of four characters can be decoded, until the end of the text is reached.
+            let decodable_len = match read_len {
+                0 => self.text.len(),
+                _ => self.text.len() / 4 * 4,
+            };
+            if decodable_len == 0 {
+                if read_len == 0 {
+                    return Ok(false);
+                }
+                continue;
+            }
+
+            let text = &self.text[..decodable_len];
+            if self.padded {
+                return Err(invalid(&self.path));
+            }
+            self.padded = text.ends_with(b"=");
+
+            self.decoded.clear();
+            self.position = 0;
+            STANDARD
+                .decode_vec(text, &mut self.decoded)
+                .map_err(|_| invalid(&self.path))?;
+            self.text.drain(..decodable_len);
+            return Ok(true);
+        }
+    }
+}
+
+/// The error for a part at `path` that is not valid Base64.
+fn invalid(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Failed to decode {}. It is not valid Base64, or it is corrupted",
+            path.to_string_lossy()
+        ),
+    )
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+
+        let len = buf.len().min(self.decoded.len() - self.position);
+        buf[..len].copy_from_slice(&self.decoded[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
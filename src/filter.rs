@@ -0,0 +1,154 @@
+//! Piping parts through external commands given with `--filter`, such as `gpg -c -o $FILE` or `gzip > $FILE`.
+//!
+//! The command is run in the shell with the `FILE` environment variable set to the path of the part.
+
+use crate::Error;
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+/// The environment variable the path of the part is given to the command in, like with GNU split.
+pub const FILE_VARIABLE: &str = "FILE";
+
+/// Starts `command` in the shell for the part at `path` with the given `stdin` and `stdout`.
+fn spawn(command: &str, path: &Path, stdin: Stdio, stdout: Stdio) -> Result<Child, Error> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    shell
+        .arg(command)
+        .env(FILE_VARIABLE, path)
+        .stdin(stdin)
+        .stdout(stdout)
+        .spawn()
+        .map_err(Error::io(format!("Failed to run the filter {}.", command)))
+}
+
+/// Makes sure that the `child` running `command` for the part at `path` exited successfully.
+fn wait(child: &mut Child, command: &str, path: &Path) -> Result<(), Error> {
+    let status = child
+        .wait()
+        .map_err(Error::io(format!("Failed to run the filter {}.", command)))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error(
+            format!(
+                "The filter {} failed for {} with {}.",
+                command,
+                path.to_string_lossy(),
+                status
+            )
+            .into(),
+        ))
+    }
+}
+
+/// Pipes everything written to it into a command that writes the part.
+pub struct FilterWriter<'a> {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    command: &'a str,
+    path: &'a Path,
+}
+
+impl<'a> FilterWriter<'a> {
+    /// Starts `command` to write the part at `path`. What the command prints is passed on to stdout.
+    pub fn spawn(command: &'a str, path: &'a Path) -> Result<Self, Error> {
+        let mut child = spawn(command, path, Stdio::piped(), Stdio::inherit())?;
+        let stdin = child.stdin.take();
+
+        Ok(FilterWriter {
+            child,
+            stdin,
+            command,
+            path,
+        })
+    }
+
+    /// Closes the stdin of the command and waits for it to finish.
+    /// This fails if the command did not exit successfully, as the part may not have been written then.
+    pub fn finish(mut self) -> Result<(), Error> {
+        drop(self.stdin.take());
+        wait(&mut self.child, self.command, self.path)
+    }
+}
+
+impl Write for FilterWriter<'_> {
+    /// If the command stopped reading early, this fails with why the command failed, if it did.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = match &mut self.stdin {
+            Some(stdin) => stdin.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        };
+
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                drop(self.stdin.take());
+                wait(&mut self.child, self.command, self.path)
+                    .map_err(|Error(message)| io::Error::other(message.trim_end_matches('.')))?;
+                Err(err)
+            }
+            result => result,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads what a command prints when the part is piped into it.
+pub struct FilterReader {
+    child: Child,
+    stdout: ChildStdout,
+    command: String,
+    path: Box<Path>,
+}
+
+impl FilterReader {
+    /// Starts `command` with the part at `path` piped into it.
+    pub fn spawn(command: &str, path: &Path) -> Result<Self, Error> {
+        let part = fs::File::open(path).map_err(Error::io(format!(
+            "Failed to open {}.",
+            path.to_string_lossy()
+        )))?;
+        let mut child = spawn(command, path, Stdio::from(part), Stdio::piped())?;
+        // The stdout of the command was piped, so it is there.
+        let stdout = child.stdout.take().unwrap();
+
+        Ok(FilterReader {
+            child,
+            stdout,
+            command: command.to_string(),
+            path: path.into(),
+        })
+    }
+}
+
+impl Read for FilterReader {
+    /// Once the command has printed everything, this fails if the command did not exit successfully,
+    /// as what it printed may be incomplete then.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            wait(&mut self.child, &self.command, &self.path)
+                .map_err(|Error(message)| io::Error::other(message.trim_end_matches('.')))?;
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,96 @@
+//! How much space is left on a filesystem, which the standard library cannot tell, for `--fit` and the space check.
+
+use crate::Error;
+use std::{io, path::Path};
+
+/// Makes sure that `required` bytes can be written to the folder `dir`, which does not have to exist yet,
+/// so that splitting or joining does not fail once most of it was written.
+/// If the free space cannot be found out, nothing is checked.
+pub fn check(dir: &Path, required: u64) -> Result<(), Error> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    // A folder that is still going to be created is on the filesystem of the closest folder that already exists.
+    let existing_dir = dir
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| Path::new("."));
+    let available = match available(existing_dir) {
+        Ok(available) => available,
+        Err(_) => return Ok(()),
+    };
+
+    if required > available {
+        Err(Error(
+            format!(
+                "There is not enough space in {}. {} bytes are needed, but only {} bytes are free. If that is wrong, use --no-space-check.",
+                dir.to_string_lossy(),
+                required,
+                available
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns how many bytes can still be written to the filesystem that `path` is on.
+#[cfg(unix)]
+// The types of the fields of `statvfs` differ between platforms, so they are not `u64` everywhere.
+#[allow(clippy::useless_conversion)]
+pub fn available(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is a valid C string and `stat` is only read once `statvfs` filled it in.
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+
+    // The blocks reserved for root are not counted, as they cannot be written to by everyone.
+    Ok(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+/// Returns how many bytes can still be written to the filesystem that `path` is on.
+#[cfg(windows)]
+pub fn available(path: &Path) -> io::Result<u64> {
+    use std::{os::windows::ffi::OsStrExt, ptr};
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+    let mut available = 0;
+    // SAFETY: `path` is null-terminated and the totals that are not needed may be null.
+    if unsafe {
+        GetDiskFreeSpaceExW(
+            path.as_ptr(),
+            &mut available,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    } == 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(available)
+}
+
+/// Returns how many bytes can still be written to the filesystem that `path` is on.
+#[cfg(not(any(unix, windows)))]
+pub fn available(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the free space cannot be found out on this platform",
+    ))
+}
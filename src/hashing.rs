@@ -0,0 +1,251 @@
+use crate::{buffer_pool::BufferPool, checksum, Error};
+use sha2::{Digest, Sha256};
+use std::{
+    sync::mpsc::{self, Receiver, Sender, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+/// How many chunks can be queued up for the worker before the sender has to wait.
+/// This bounds the memory used for chunks that were read but not hashed yet.
+const QUEUE_LEN: usize = 16;
+
+enum Message {
+    /// Starts hashing the following data as a new part.
+    /// If `stored` is true, its SHA-256 checksum is also computed for the SHA256SUMS file.
+    Part {
+        stored: bool,
+    },
+    Data(Vec<u8>),
+    /// Data that only belongs to the current part and not to the file, such as a repeated CSV header.
+    PartData(Vec<u8>),
+}
+
+/// The checksums computed by a [`HashingWorker`].
+pub struct Checksums {
+    /// The checksum of every part, in the order they were started.
+    pub parts: Vec<String>,
+    /// The SHA-256 checksum of every part that was started with `stored`.
+    pub stored_sha256s: Vec<Option<String>>,
+    /// The checksum of all data together.
+    pub file: String,
+}
+
+/// Computes the checksums of the parts and the file.
+///
+/// If it is created as `threaded`, this happens on a worker thread so that hashing overlaps with reading and writing
+/// instead of holding them up.
+pub struct HashingWorker(Mode);
+
+enum Mode {
+    Threaded {
+        sender: SyncSender<Message>,
+        handle: JoinHandle<Checksums>,
+        /// The chunks are copied into these, as the data passed to [`HashingWorker::update`] is only borrowed.
+        buffers: BufferPool,
+    },
+    Inline(Hashers),
+}
+
+impl HashingWorker {
+    pub fn new(algorithm: checksum::Algorithm, threaded: bool) -> Self {
+        if threaded {
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_LEN);
+            let buffers = BufferPool::new();
+            let recycler = buffers.recycler();
+            let handle = thread::spawn(move || work(receiver, recycler, algorithm));
+
+            HashingWorker(Mode::Threaded {
+                sender,
+                handle,
+                buffers,
+            })
+        } else {
+            HashingWorker(Mode::Inline(Hashers::new(algorithm)))
+        }
+    }
+
+    /// Starts a new part. The data passed to [`HashingWorker::update`] after this belongs to it.
+    pub fn start_part(&mut self, stored: bool) {
+        match &mut self.0 {
+            // If the worker is gone, that is reported by `finish`.
+            Mode::Threaded { sender, .. } => {
+                let _ = sender.send(Message::Part { stored });
+            }
+            Mode::Inline(hashers) => hashers.start_part(stored),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.0 {
+            Mode::Threaded {
+                sender, buffers, ..
+            } => {
+                let _ = sender.send(Message::Data(buffers.copy(data)));
+            }
+            Mode::Inline(hashers) => hashers.update(data),
+        }
+    }
+
+    /// Like [`HashingWorker::update`], but `data` is only part of the current part and not of the file.
+    pub fn update_part(&mut self, data: &[u8]) {
+        match &mut self.0 {
+            Mode::Threaded {
+                sender, buffers, ..
+            } => {
+                let _ = sender.send(Message::PartData(buffers.copy(data)));
+            }
+            Mode::Inline(hashers) => hashers.update_part(data),
+        }
+    }
+
+    /// Waits until everything was hashed and returns the checksums.
+    pub fn finish(self) -> Result<Checksums, Error> {
+        match self.0 {
+            Mode::Threaded { sender, handle, .. } => {
+                drop(sender);
+
+                handle
+                    .join()
+                    .map_err(|_| Error("Failed to compute the checksums.".into()))
+            }
+            Mode::Inline(hashers) => Ok(hashers.finish()),
+        }
+    }
+}
+
+/// Hashes what is received until the [`HashingWorker`] is finished, and sends the buffers back to `recycler` once they were hashed.
+fn work(
+    receiver: Receiver<Message>,
+    recycler: Sender<Vec<u8>>,
+    algorithm: checksum::Algorithm,
+) -> Checksums {
+    let mut hashers = Hashers::new(algorithm);
+
+    for message in receiver {
+        let data = match message {
+            Message::Part { stored } => {
+                hashers.start_part(stored);
+                continue;
+            }
+            Message::Data(data) => {
+                hashers.update(&data);
+                data
+            }
+            Message::PartData(data) => {
+                hashers.update_part(&data);
+                data
+            }
+        };
+        let _ = recycler.send(data);
+    }
+
+    hashers.finish()
+}
+
+/// The hashers of the file and of the current part.
+struct Hashers {
+    algorithm: checksum::Algorithm,
+    file: Box<dyn checksum::Hasher>,
+    /// The hasher of the checksum of the current part and the one of its SHA-256 checksum if that is needed.
+    part: Option<(Box<dyn checksum::Hasher>, Option<Sha256>)>,
+    checksums: Checksums,
+}
+
+impl Hashers {
+    fn new(algorithm: checksum::Algorithm) -> Self {
+        Hashers {
+            algorithm,
+            file: algorithm.hasher(),
+            part: None,
+            checksums: Checksums {
+                parts: Vec::new(),
+                stored_sha256s: Vec::new(),
+                file: String::new(),
+            },
+        }
+    }
+
+    fn start_part(&mut self, stored: bool) {
+        self.finish_part();
+        self.part = Some((self.algorithm.hasher(), stored.then(Sha256::new)));
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.update_part(data);
+        self.file.update(data);
+    }
+
+    fn update_part(&mut self, data: &[u8]) {
+        let (part_hasher, stored_hasher) = self
+            .part
+            .as_mut()
+            .expect("data was hashed before a part was started");
+
+        part_hasher.update(data);
+        if let Some(stored_hasher) = stored_hasher {
+            stored_hasher.update(data);
+        }
+    }
+
+    fn finish_part(&mut self) {
+        if let Some((part_hasher, stored_hasher)) = self.part.take() {
+            self.checksums.parts.push(part_hasher.finalize());
+            self.checksums
+                .stored_sha256s
+                .push(stored_hasher.map(|stored_hasher| crate::to_hex(&stored_hasher.finalize())));
+        }
+    }
+
+    fn finish(mut self) -> Checksums {
+        self.finish_part();
+        self.checksums.file = self.file.finalize();
+        self.checksums
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TempDir};
+    use std::{
+        fs,
+        io::{Read, Write},
+        time::Instant,
+    };
+
+    /// Compares how long hashing a file of 1 GB takes on the worker thread and inline. Run it with
+    /// `cargo test --release hashing_benchmark -- --ignored --nocapture` to see the times.
+    #[test]
+    #[ignore]
+    fn hashing_benchmark() {
+        let dir = TempDir::new();
+        let path = dir.path().join("file");
+        let chunk = test_util::data(1 << 20);
+        let mut file = fs::File::create(&path).unwrap();
+        for _ in 0..1024 {
+            file.write_all(&chunk).unwrap();
+        }
+        drop(file);
+
+        let mut checksums = Vec::new();
+        for threaded in [true, false] {
+            let start = Instant::now();
+            let mut hashing = HashingWorker::new(checksum::Algorithm::default(), threaded);
+            hashing.start_part(false);
+            let mut file = fs::File::open(&path).unwrap();
+            let mut buffer = vec![0; crate::split::DEFAULT_BUFFER_SIZE];
+            loop {
+                let len = file.read(&mut buffer).unwrap();
+                if len == 0 {
+                    break;
+                }
+                hashing.update(&buffer[..len]);
+            }
+            checksums.push(hashing.finish().unwrap().file);
+
+            let mode = if threaded { "threaded" } else { "inline" };
+            println!("Hashing {}: {:?}", mode, start.elapsed());
+        }
+        assert_eq!(checksums[0], checksums[1]);
+    }
+}
@@ -0,0 +1,58 @@
+use splitter::Error;
+use std::process::Command;
+
+/// Shows a dialog with the description `text` that asks for a line of text.
+/// Returns `None` if the dialog was cancelled or nothing was entered.
+///
+/// `rfd` has no such dialog so this runs whatever program the platform has for it.
+pub fn ask(text: &str) -> Result<Option<String>, Error> {
+    for mut command in get_commands(text) {
+        match command.output() {
+            Ok(output) if output.status.success() => {
+                let input = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+                return Ok(if input.is_empty() { None } else { Some(input) });
+            }
+            Ok(_) => return Ok(None),
+            // The program is not installed, so the next one is tried.
+            Err(_) => continue,
+        }
+    }
+
+    Err(Error(
+        "No input dialog is available. Please run splitter in a terminal or give the split size with --size.".into(),
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn get_commands(text: &str) -> Vec<Command> {
+    let mut powershell = Command::new("powershell");
+    powershell.args(["-NoProfile", "-Command"]).arg(format!(
+        "Add-Type -AssemblyName Microsoft.VisualBasic; [Microsoft.VisualBasic.Interaction]::InputBox('{}', 'splitter')",
+        text.replace('\'', "''")
+    ));
+
+    vec![powershell]
+}
+
+#[cfg(target_os = "macos")]
+fn get_commands(text: &str) -> Vec<Command> {
+    let mut osascript = Command::new("osascript");
+    osascript.arg("-e").arg(format!(
+        "text returned of (display dialog \"{}\" default answer \"\" with title \"splitter\")",
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    ));
+
+    vec![osascript]
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn get_commands(text: &str) -> Vec<Command> {
+    let mut zenity = Command::new("zenity");
+    zenity.args(["--entry", "--title=splitter", "--text", text]);
+
+    let mut kdialog = Command::new("kdialog");
+    kdialog.args(["--title", "splitter", "--inputbox", text]);
+
+    vec![zenity, kdialog]
+}
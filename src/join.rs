@@ -1,115 +1,2365 @@
-use crate::Error;
+use crate::{
+    args::Options,
+    checksum,
+    compression::Compression,
+    crypt::{self, DecryptReader, Key},
+    encoding::{Base64Reader, Encoding},
+    filter::FilterReader,
+    free_space, join_script,
+    manifest::{self, Manifest},
+    outcome::{Operation, Outcome},
+    parity,
+    part_header::{self, PartHeader},
+    progress::Progress,
+    recovery,
+    retry::RetryReader,
+    sfv, sha256sums,
+    split::{self, NameFormat},
+    Error,
+};
+use sha2::{Digest, Sha256};
 use std::{
-    borrow::Cow,
+    ffi::{OsStr, OsString},
     fs,
     io::{self, Read, Write},
     path::{Path, PathBuf},
-    usize,
 };
 
+/// Removes the [`Compression::extension`] from `file_name` if it has one.
+fn strip_compressed_extension(file_name: &[u8]) -> &[u8] {
+    match Compression::from_file_name(file_name) {
+        Some(compression) => &file_name[..file_name.len() - compression.extension().len()],
+        None => file_name,
+    }
+}
+
+/// Removes the [`Encoding::extension`] from `file_name` if it has one.
+fn strip_encoded_extension(file_name: &[u8]) -> &[u8] {
+    match Encoding::from_file_name(file_name) {
+        Some(encoding) => &file_name[..file_name.len() - encoding.extension().len()],
+        None => file_name,
+    }
+}
+
+/// Returns the filename of the part at `path` without the [`Encoding::extension`] and the [`Compression::extension`].
+/// The filename is worked with as bytes so that parts of files whose names are not UTF-8 can be joined too.
+fn get_part_file_name(path: &Path) -> Option<&[u8]> {
+    path.file_name().map(|file_name| {
+        strip_compressed_extension(strip_encoded_extension(file_name.as_encoded_bytes()))
+    })
+}
+
+/// The separator between the original filename and the trailing number in the filenames of the parts,
+/// unless another one is given with `--separator`.
+pub const DEFAULT_SEPARATOR: &str = "-split-";
+
+/// What the filenames of the parts of a split made with `--stripe` have between the separator and the trailing number,
+/// so that they are not mistaken for parts that hold consecutive byte ranges of the file.
+pub const STRIPE_MARKER: &str = "stripe";
+
+/// How many times a read of a part that failed with an error that may go away is retried,
+/// unless another number is given with `--retries`.
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// Splits `file_name` into the part before its trailing number and the trailing number itself.
+fn split_trailing_number(file_name: &[u8]) -> (&[u8], &[u8]) {
+    let number_len = file_name
+        .iter()
+        .rev()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    file_name.split_at(file_name.len() - number_len)
+}
+
+/// Returns the number at the end of the filename of a part.
+/// Leading zeros are allowed, so both `foo-split-7` and `foo-split-07` are part 7.
 fn get_trailing_number(path: &Path) -> Result<usize, Error> {
-    if let Some(path) = path.to_str() {
-        if let Some((_, trailing_number)) = path.rsplit_once('-') {
-            Ok(trailing_number
-                .parse::<usize>()
-                .map_err(|_| Error("invalid trailing number".into()))?)
-        } else {
-            Err(Error("no trailing number found".into()))
-        }
-    } else {
-        Err(Error("path is not UTF-8".into()))
+    let file_name = get_part_file_name(path).ok_or_else(|| Error("no filename found".into()))?;
+    let (_, trailing_number) = split_trailing_number(file_name);
+    if trailing_number.is_empty() {
+        return Err(Error("no trailing number found".into()));
     }
+
+    // The trailing number only consists of ASCII digits, so it is UTF-8.
+    std::str::from_utf8(trailing_number)
+        .ok()
+        .and_then(|trailing_number| trailing_number.parse::<usize>().ok())
+        .ok_or_else(|| Error("invalid trailing number".into()))
 }
 
-/// Splits off the "split" suffix from filenames.
+/// Returns whether `path` is named like a part, which is `<name><separator><number>`, `<name>-<number>` or `<name>.<number>`,
+/// optionally followed by a [`Compression::extension`].
 ///
 /// # Examples
 ///
 /// ```
-/// assert_eq!(split_file_name("Cargo.toml-split-0"), "Cargo.toml");
+/// # use splitter::join::{is_part, DEFAULT_SEPARATOR};
+/// # use std::path::Path;
+/// assert!(is_part(Path::new("Cargo.toml-split-1"), DEFAULT_SEPARATOR));
+/// assert!(is_part(Path::new("Cargo.toml-split-1.gz"), DEFAULT_SEPARATOR));
+/// assert!(is_part(Path::new("Cargo.toml.part001"), ".part"));
+/// assert!(is_part(Path::new("Cargo.toml.001"), DEFAULT_SEPARATOR));
+/// assert!(!is_part(Path::new("manifest.json"), DEFAULT_SEPARATOR));
 /// ```
-fn split_file_name(filename: &str) -> Option<&str> {
-    let mut split = filename.rsplitn(3, '-');
-    split.next()?;
-    split.next()?;
-    split.next()
+pub fn is_part(path: &Path, separator: &str) -> bool {
+    get_part_file_name(path)
+        .is_some_and(|file_name| split_file_name(file_name, separator).is_some())
+}
+
+/// Returns whether `path` is named like a part that splitter wrote, which is `<name><separator><number>`,
+/// or `<name><separator>stripe-<number>` for `--stripe`.
+/// Unlike [`is_part`], this does not accept other separators, so that files like `report-2024` are not taken for parts.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::join::{is_named_part, DEFAULT_SEPARATOR};
+/// # use std::path::Path;
+/// assert!(is_named_part(Path::new("backup.tar-split-3"), DEFAULT_SEPARATOR));
+/// assert!(is_named_part(Path::new("backup.tar-split-03.gz"), DEFAULT_SEPARATOR));
+/// assert!(is_named_part(Path::new("backup.tar-split-stripe-2"), DEFAULT_SEPARATOR));
+/// assert!(!is_named_part(Path::new("report-2024"), DEFAULT_SEPARATOR));
+/// assert!(!is_named_part(Path::new("backup.tar"), DEFAULT_SEPARATOR));
+/// ```
+pub fn is_named_part(path: &Path, separator: &str) -> bool {
+    is_striped_part(path, separator)
+        || get_part_file_name(path).is_some_and(|file_name| {
+            let (name, number) = split_trailing_number(file_name);
+            !number.is_empty()
+                && name
+                    .strip_suffix(separator.as_bytes())
+                    .is_some_and(|name| !name.is_empty())
+        })
+}
+
+/// Returns whether `path` is named like a part of a split made with `--stripe`, which is `<name><separator>stripe-<number>`.
+fn is_striped_part(path: &Path, separator: &str) -> bool {
+    get_part_file_name(path).is_some_and(|file_name| {
+        let (name, number) = split_trailing_number(file_name);
+        !number.is_empty() && name.ends_with(format!("{}{}-", separator, STRIPE_MARKER).as_bytes())
+    })
+}
+
+/// What the filenames of parts named with `--format gnu` start with, like the ones of GNU split.
+pub const GNU_PREFIX: &str = "x";
+
+/// Returns the number of the part with the alphabetic `suffix`, starting at 1.
+/// This is the reverse of [`crate::split::gnu_suffix`].
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::join::parse_gnu_suffix;
+/// assert_eq!(parse_gnu_suffix(b"aa"), Some(1));
+/// assert_eq!(parse_gnu_suffix(b"az"), Some(26));
+/// assert_eq!(parse_gnu_suffix(b"ba"), Some(27));
+/// assert_eq!(parse_gnu_suffix(b"zz"), Some(676));
+/// assert_eq!(parse_gnu_suffix(b"baa"), Some(677));
+/// assert_eq!(parse_gnu_suffix(b"a1"), None);
+/// assert_eq!(parse_gnu_suffix(b""), None);
+/// ```
+pub fn parse_gnu_suffix(suffix: &[u8]) -> Option<usize> {
+    if suffix.is_empty() {
+        return None;
+    }
+
+    suffix
+        .iter()
+        .try_fold(0_usize, |number, letter| {
+            if letter.is_ascii_lowercase() {
+                number
+                    .checked_mul(26)?
+                    .checked_add((letter - b'a') as usize)
+            } else {
+                None
+            }
+        })
+        .map(|number| number + 1)
+}
+
+/// Returns the alphabetic suffix of the part at `path` if it is named like the parts of GNU split,
+/// which is [`GNU_PREFIX`] followed by lowercase letters, optionally followed by a [`Compression::extension`].
+fn get_gnu_suffix(path: &Path) -> Option<&[u8]> {
+    get_part_file_name(path)
+        .and_then(|file_name| file_name.strip_prefix(GNU_PREFIX.as_bytes()))
+        .filter(|suffix| parse_gnu_suffix(suffix).is_some())
+}
+
+/// Returns whether `path` is named like the parts of GNU split, such as `xaa`, `xab` and so on.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::join::is_gnu_part;
+/// # use std::path::Path;
+/// assert!(is_gnu_part(Path::new("xaa")));
+/// assert!(is_gnu_part(Path::new("xbaa.gz")));
+/// assert!(!is_gnu_part(Path::new("x")));
+/// assert!(!is_gnu_part(Path::new("manifest.json")));
+/// ```
+pub fn is_gnu_part(path: &Path) -> bool {
+    get_gnu_suffix(path).is_some()
+}
+
+/// Splits off the `separator` and the trailing number from filenames of parts.
+/// Parts of older splits, which are named `<name>-<number>`, and parts named `<name>.<number>` like `bigfile.bin.001`,
+/// as made with `--format 001` or by HJSplit, are also recognized.
+/// Everything before it is kept, including any dashes.
+fn split_file_name<'a>(filename: &'a [u8], separator: &str) -> Option<&'a [u8]> {
+    let (name, number) = split_trailing_number(filename);
+    if number.is_empty() {
+        return None;
+    }
+
+    let name = name
+        .strip_suffix(separator.as_bytes())
+        .or_else(|| name.strip_suffix(b"-"))
+        .or_else(|| name.strip_suffix(b"."))?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+struct NumberedPart {
+    path: PathBuf,
+    /// The trailing number of the part, or its index if it has a part header.
+    number: usize,
+}
+
+/// Opens the part at `path` to read it. Opening and reading it is retried up to `retries` times if it fails with an error that may go away.
+///
+/// Parts are only opened while they are read rather than all at once,
+/// so that splits with more parts than a process may have open files can be joined too.
+fn open_part(path: &Path, retries: usize) -> Result<RetryReader, Error> {
+    RetryReader::open(path, retries).map_err(Error::io(format!(
+        "Failed to open {}.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Returns the length of the file at `path`, if it exists.
+fn get_len(path: &Path) -> Result<Option<u64>, Error> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some(metadata.len())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::io(format!(
+            "Failed to read {}.",
+            path.to_string_lossy()
+        ))(err)),
+    }
+}
+
+/// Returns the paths of the parts listed in the `manifest` of the split folder `dir` in order, once they were found.
+/// The parts of the `manifest` are sorted the same way.
+///
+/// If the split has a parity file, one missing part is rebuilt from it, reading `buffer_size` bytes at a time,
+/// and what was rebuilt is returned as well.
+fn find_parts_from_manifest(
+    dir: &Path,
+    manifest: &mut Manifest,
+    buffer_size: usize,
+) -> Result<(Vec<PathBuf>, Option<String>), Error> {
+    manifest.parts.sort_unstable_by_key(|part| part.index);
+    let parts = &manifest.parts;
+
+    if parts.len() != manifest.part_count
+        || parts
+            .iter()
+            .enumerate()
+            .any(|(index, part)| index + 1 != part.index)
+    {
+        return Err(Error(
+            format!(
+                "The {} has missing or duplicate parts.",
+                manifest::FILE_NAME
+            )
+            .into(),
+        ));
+    }
+
+    let mut missing = Vec::<usize>::new();
+    for (index, part) in parts.iter().enumerate() {
+        if get_len(&dir.join(&part.file_name))?.is_none() {
+            missing.push(index);
+        }
+    }
+
+    let rebuilt_part = match (missing.as_slice(), &manifest.parity) {
+        ([], _) => None,
+        ([index], Some(parity)) => {
+            parity::rebuild(dir, manifest, parity, *index, buffer_size)?;
+            Some(format!(
+                "Part {} was missing and was rebuilt from the parity file.",
+                parts[*index].index
+            ))
+        }
+        ([index, ..], None) => {
+            return Err(Error(
+                format!("Part {} is missing.", parts[*index].file_name).into(),
+            ))
+        }
+        (_, Some(_)) => {
+            return Err(Error(
+                format!(
+                    "Parts {} are missing. The parity file can only rebuild one missing part.",
+                    missing
+                        .iter()
+                        .map(|index| parts[*index].file_name.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )
+                .into(),
+            ))
+        }
+    };
+
+    let mut paths = Vec::<PathBuf>::with_capacity(parts.len());
+
+    for part in parts {
+        let path = dir.join(&part.file_name);
+        let len = get_len(&path)?
+            .ok_or_else(|| Error(format!("Part {} is missing.", part.file_name).into()))?;
+
+        // The length of a compressed, encrypted, encoded or filtered part is only known once it is read.
+        if manifest.compression.is_none()
+            && manifest.encryption.is_none()
+            && manifest.encoding.is_none()
+            && manifest.filter.is_none()
+            && len != part.len
+        {
+            return Err(Error(
+                format!("Part {} does not have the expected length.", part.file_name).into(),
+            ));
+        }
+
+        paths.push(path);
+    }
+
+    Ok((paths, rebuilt_part))
+}
+
+/// Returns how the parts at `path_bufs` are compressed, going by their filenames.
+fn get_compression(path_bufs: &[PathBuf]) -> Result<Option<Compression>, Error> {
+    let mut compressions = path_bufs.iter().map(|path| {
+        path.file_name().and_then(|file_name| {
+            Compression::from_file_name(strip_encoded_extension(file_name.as_encoded_bytes()))
+        })
+    });
+    let compression = compressions.next().flatten();
+
+    if compressions.all(|other| other == compression) {
+        Ok(compression)
+    } else {
+        Err(Error(
+            "Only some of the parts are compressed, or they are compressed differently. Make sure they all belong to the same split."
+                .into(),
+        ))
+    }
+}
+
+/// Returns how the parts at `path_bufs` are encoded, going by their filenames.
+fn get_encoding(path_bufs: &[PathBuf]) -> Result<Option<Encoding>, Error> {
+    let mut encodings = path_bufs.iter().map(|path| {
+        path.file_name()
+            .and_then(|file_name| Encoding::from_file_name(file_name.as_encoded_bytes()))
+    });
+    let encoding = encodings.next().flatten();
+
+    if encodings.all(|other| other == encoding) {
+        Ok(encoding)
+    } else {
+        Err(Error(
+            "Only some of the parts are encoded as Base64. Make sure they all belong to the same split."
+                .into(),
+        ))
+    }
+}
+
+/// Opens the part at `path` to read its content, which is decompressed on the fly if it has a `compression`.
+/// Before that, it is piped through the `filter` command, decoded if it has an `encoding`
+/// and decrypted with the `key` if they are given. Reads of the part are retried up to `retries` times.
+pub(crate) fn open_part_reader(
+    path: &Path,
+    encoding: Option<Encoding>,
+    compression: Option<Compression>,
+    filter: Option<&str>,
+    key: Option<&Key>,
+    retries: usize,
+) -> Result<Box<dyn Read>, Error> {
+    let reader: Box<dyn Read> = match filter {
+        Some(command) => Box::new(FilterReader::spawn(command, path)?),
+        None => Box::new(open_part(path, retries)?),
+    };
+    let reader: Box<dyn Read> = match encoding {
+        Some(Encoding::Base64) => Box::new(Base64Reader::new(reader, path)),
+        None => reader,
+    };
+    let reader: Box<dyn Read> = match key {
+        Some(key) => Box::new(DecryptReader::new(reader, key, path)?),
+        None => reader,
+    };
+
+    match compression {
+        Some(compression) => compression.decoder(reader).map_err(Error::io(format!(
+            "Failed to decompress {}.",
+            path.to_string_lossy()
+        ))),
+        None => Ok(reader),
+    }
+}
+
+/// Makes sure that the parts of a split made with `--filter`, as recorded in the `manifest`,
+/// are joined with a `filter` as well, as they could not be read otherwise.
+fn check_filter(manifest: &Manifest, filter: Option<&str>) -> Result<(), Error> {
+    match (&manifest.filter, filter) {
+        (Some(split_filter), None) => Err(Error(
+            format!(
+                "The parts were written through the filter {}. Please join them with a --filter that reverses it.",
+                split_filter
+            )
+            .into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Asks for the passphrase if the parts at `paths` are encrypted and derives the key to decrypt them with from it.
+/// If it was given with `--passphrase`, it is not asked for.
+/// Whether they are is told by the `manifest` or, if it does not record the salt, by the first part.
+/// The parts are read through the `filter` command if one is given and decoded if they have an `encoding`.
+/// The key is tried on the first part right away, so that a wrong passphrase is reported before anything is written.
+fn get_key(
+    paths: &[PathBuf],
+    encoding: Option<Encoding>,
+    manifest: Option<&Manifest>,
+    filter: Option<&str>,
+    passphrase: Option<&str>,
+    retries: usize,
+) -> Result<Option<Key>, Error> {
+    let first_path = match paths.first() {
+        Some(first_path) => first_path,
+        None => return Ok(None),
+    };
+
+    let key = match (
+        manifest,
+        manifest.and_then(|manifest| manifest.salt.as_deref()),
+    ) {
+        (Some(manifest), Some(salt)) => {
+            check_encryption_headers(paths, manifest, salt, encoding, filter, retries)?;
+            Some(Key::for_salt(
+                &crypt::ask_passphrase(false, passphrase)?,
+                salt,
+            )?)
+        }
+        // Manifests of older versions do not record the salt, so it is read from the first part.
+        _ => Key::for_part(
+            &mut open_part_reader(first_path, encoding, None, filter, None, retries)?,
+            || crypt::ask_passphrase(false, passphrase),
+        )?,
+    };
+    let key = match (key, manifest.and_then(|manifest| manifest.encryption)) {
+        (Some(key), _) => key,
+        (None, Some(_)) => {
+            return Err(Error(
+                format!(
+                    "{} is not encrypted, although the {} says that the parts are.",
+                    first_path.to_string_lossy(),
+                    manifest::FILE_NAME
+                )
+                .into(),
+            ))
+        }
+        (None, None) => return Ok(None),
+    };
+
+    // Decrypting the first chunk is enough to tell whether the passphrase is right.
+    let reader = open_part_reader(first_path, encoding, None, filter, Some(&key), retries)?;
+    io::copy(&mut reader.take(1), &mut io::sink())?;
+
+    Ok(Some(key))
+}
+
+/// Makes sure that the encrypted parts at `paths` start with the `salt` and the nonces that the `manifest` records for them,
+/// so that parts that were swapped, renamed or mixed in from another split are noticed before anything is decrypted.
+/// The parts are read through the `filter` command if one is given and decoded if they have an `encoding`.
+fn check_encryption_headers(
+    paths: &[PathBuf],
+    manifest: &Manifest,
+    salt: &str,
+    encoding: Option<Encoding>,
+    filter: Option<&str>,
+    retries: usize,
+) -> Result<(), Error> {
+    for path in paths {
+        let header = crypt::read_salt_and_nonce(&mut open_part_reader(
+            path, encoding, None, filter, None, retries,
+        )?)?;
+        let (part_salt, nonce) = header.ok_or_else(|| {
+            Error(
+                format!(
+                    "{} is not encrypted, although the {} says that the parts are.",
+                    path.to_string_lossy(),
+                    manifest::FILE_NAME
+                )
+                .into(),
+            )
+        })?;
+
+        let recorded_nonce = manifest
+            .parts
+            .iter()
+            .find(|part| path.file_name() == Some(OsStr::new(&part.file_name)))
+            .and_then(|part| part.nonce.as_deref());
+        if part_salt != salt || recorded_nonce.is_some_and(|recorded_nonce| recorded_nonce != nonce)
+        {
+            return Err(Error(
+                format!(
+                    "{} was not encrypted as the {} records. Make sure all parts belong to the same split and were not renamed.",
+                    path.to_string_lossy(),
+                    manifest::FILE_NAME
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `reader` to its end and passes every chunk that is read to `on_chunk`.
+pub(crate) fn read_chunks<R: Read, F: FnMut(&[u8]) -> Result<(), Error>>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    mut on_chunk: F,
+) -> Result<(), Error> {
+    loop {
+        let read = match reader.read(buffer) {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => break Err(err.into()),
+        };
+        on_chunk(&buffer[..read])?;
+    }
+}
+
+/// Returns how many bytes the part at `index`, starting at 0, starts with that are not part of the file,
+/// which are its part header and the copy of the CSV header.
+/// The first part has the only CSV header that is part of the file, so that one is not counted for it.
+fn header_len(part_header_len: Option<u64>, csv_header_len: Option<u64>, index: usize) -> u64 {
+    let csv_header_len = match csv_header_len {
+        Some(csv_header_len) if index > 0 => csv_header_len,
+        _ => 0,
+    };
+    part_header_len.unwrap_or(0) + csv_header_len
+}
+
+/// Returns what is left of `chunk` once the rest of the `header_len` bytes of the headers of a part are skipped,
+/// and counts them off `header_len`.
+fn skip_header<'a>(chunk: &'a [u8], header_len: &mut u64) -> &'a [u8] {
+    let skipped = (*header_len).min(chunk.len() as u64);
+    *header_len -= skipped;
+    &chunk[skipped as usize..]
+}
+
+/// Returns what is left of `chunk` once anything after the `unpadded_len` bytes that are left of the file is cut off,
+/// which is padding, and counts them off `unpadded_len`.
+fn cut_padding<'a>(chunk: &'a [u8], unpadded_len: &mut u64) -> &'a [u8] {
+    let len = (*unpadded_len).min(chunk.len() as u64);
+    *unpadded_len -= len;
+    &chunk[..len as usize]
+}
+
+/// Returns how many bytes of the parts of the split in the `manifest` belong to the file, not counting repeated CSV headers,
+/// if the last part is padded. Otherwise, there is no limit.
+fn get_unpadded_len(manifest: &Manifest) -> u64 {
+    match manifest.padding {
+        Some(_) => manifest.file_len,
+        None => u64::MAX,
+    }
 }
 
-struct File {
-    file: fs::File,
-    trailing_number: usize,
+/// Checks the parts at `paths` against the checksums in the `manifest`.
+/// `paths` has to be in the order of the parts of the `manifest`.
+/// They are piped through the `filter` command and decrypted with the `key` first if they are given.
+fn verify_parts(
+    paths: &[PathBuf],
+    manifest: &Manifest,
+    filter: Option<&str>,
+    key: Option<&Key>,
+    retries: usize,
+    buffer_size: usize,
+    progress: &mut Progress,
+) -> Result<(), Error> {
+    let algorithm = manifest.checksum_algorithm;
+    let mut file_hasher = algorithm.hasher();
+    let mut buffer = vec![0_u8; buffer_size];
+    let mut unpadded_len = get_unpadded_len(manifest);
+
+    for (index, (path, part)) in paths.iter().zip(&manifest.parts).enumerate() {
+        let mut part_hasher = algorithm.hasher();
+        let mut part_len = 0;
+        let mut header_len = header_len(manifest.part_header_len, manifest.csv_header_len, index);
+
+        let mut reader = open_part_reader(
+            path,
+            manifest.encoding,
+            manifest.compression,
+            filter,
+            key,
+            retries,
+        )?;
+        read_chunks(&mut reader, &mut buffer, |chunk| {
+            part_hasher.update(chunk);
+            file_hasher.update(cut_padding(
+                skip_header(chunk, &mut header_len),
+                &mut unpadded_len,
+            ));
+            part_len += chunk.len() as u64;
+            progress.advance(chunk.len() as u64);
+            Ok(())
+        })?;
+        drop(reader);
+
+        if part_len != part.len {
+            return Err(Error(
+                format!("Part {} does not have the expected length.", part.file_name).into(),
+            ));
+        }
+
+        if let Some(checksum) = &part.checksum {
+            let actual_checksum = part_hasher.finalize();
+            if *checksum != actual_checksum {
+                return Err(Error(
+                    format!(
+                        "Part {} is corrupted. Its checksum does not match.\n\nExpected {name}: {}\nActual {name}: {}",
+                        part.file_name,
+                        checksum,
+                        actual_checksum,
+                        name = algorithm.display_name()
+                    )
+                    .into(),
+                ));
+            }
+        }
+    }
+
+    progress.finish();
+
+    // Striped parts only add up to the file once they are dealt back, which is checked when they are joined.
+    if let Some(checksum) = manifest
+        .checksum
+        .as_ref()
+        .filter(|_| manifest.stripe_size.is_none())
+    {
+        let actual_checksum = file_hasher.finalize();
+        if *checksum != actual_checksum {
+            return Err(Error(
+                format!(
+                    "The parts do not add up to the original file. Its checksum does not match.\n\nExpected {name}: {}\nActual {name}: {}",
+                    checksum,
+                    actual_checksum,
+                    name = algorithm.display_name()
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the files in `dir` against the CRC32 checksums listed in the SFV file `sfv_path`.
+/// Progress is reported on `stdout` if it is given.
+fn check_sfv(
+    stdout: Option<&mut io::StdoutLock>,
+    sfv_path: &Path,
+    dir: &Path,
+    retries: usize,
+    buffer_size: usize,
+) -> Result<(), Error> {
+    if !sfv_path.is_file() {
+        return Err(Error(
+            format!(
+                "There is no SFV file to check the parts against. It should be at {}.",
+                sfv_path.to_string_lossy()
+            )
+            .into(),
+        ));
+    }
+
+    let mut parts = Vec::new();
+    let mut total_len = 0;
+    for (file_name, crc32) in sfv::read(sfv_path)? {
+        let path = dir.join(&file_name);
+        total_len += get_len(&path)?
+            .ok_or_else(|| Error(format!("Part {} is missing.", file_name).into()))?;
+        parts.push((file_name, crc32, path));
+    }
+
+    let mut progress = Progress::new(stdout, "Checking", total_len);
+    let mut buffer = vec![0_u8; buffer_size];
+
+    for (file_name, crc32, path) in parts {
+        let mut hasher = crc32fast::Hasher::new();
+        read_chunks(&mut open_part(&path, retries)?, &mut buffer, |chunk| {
+            hasher.update(chunk);
+            progress.advance(chunk.len() as u64);
+            Ok(())
+        })?;
+
+        let actual_crc32 = hasher.finalize();
+        if actual_crc32 != crc32 {
+            return Err(Error(
+                format!(
+                    "Part {} is corrupted. Its CRC32 does not match.\n\nExpected CRC32: {:08x}\nActual CRC32: {:08x}",
+                    file_name, crc32, actual_crc32
+                )
+                .into(),
+            ));
+        }
+    }
+
+    progress.finish();
+
+    Ok(())
 }
 
-pub fn join(path_bufs: Vec<PathBuf>) -> Result<Cow<'static, str>, Error> {
-    let first_path = &path_bufs[0];
-    let file_name = if first_path.is_file() {
-        let file_name = crate::get_file_name(first_path)?;
+/// Checks the files in `dir` against the SHA-256 checksums listed in the file `sha256sums_path`.
+/// Unlike [`check_sfv`], all files are checked before failing so that every bad part is reported.
+/// Progress is reported on `stdout` if it is given.
+fn check_sha256sums(
+    stdout: Option<&mut io::StdoutLock>,
+    sha256sums_path: &Path,
+    dir: &Path,
+    retries: usize,
+    buffer_size: usize,
+) -> Result<(), Error> {
+    let mut failures = Vec::<String>::new();
+    let mut parts = Vec::new();
+    let mut total_len = 0;
+    for (file_name, sha256) in sha256sums::read(sha256sums_path)? {
+        let path = dir.join(&file_name);
+        let len = get_len(&path)?;
+        total_len += len.unwrap_or(0);
+        parts.push((file_name, sha256, len.map(|_| path)));
+    }
+
+    let mut progress = Progress::new(stdout, "Checking", total_len);
+    let mut buffer = vec![0_u8; buffer_size];
+
+    for (file_name, sha256, path) in parts {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                failures.push(format!("{}: missing", file_name));
+                continue;
+            }
+        };
+
+        let mut hasher = Sha256::new();
+        read_chunks(&mut open_part(&path, retries)?, &mut buffer, |chunk| {
+            hasher.update(chunk);
+            progress.advance(chunk.len() as u64);
+            Ok(())
+        })?;
+
+        if crate::to_hex(&hasher.finalize()) != sha256 {
+            failures.push(format!("{}: FAILED", file_name));
+        }
+    }
 
-        split_file_name(file_name)
-            .ok_or_else(|| Error(format!("Invalid filename: {}", file_name).into()))
+    progress.finish();
+
+    if failures.is_empty() {
+        Ok(())
     } else {
         Err(Error(
-            format!("{} is not a file", first_path.to_string_lossy()).into(),
+            format!(
+                "Some parts do not match the checksums in {}:\n\n{}",
+                sha256sums::FILE_NAME,
+                failures.join("\n")
+            )
+            .into(),
         ))
-    }?;
+    }
+}
+
+/// Checks the parts in the split folder `dir` against its manifest without joining them
+/// and reports progress on `stdout` if it is given.
+/// All parts are checked before failing so that every bad part is reported.
+/// They are piped through the `filter` command first if one is given, and decrypted if they are encrypted,
+/// with the `passphrase` if one is given or otherwise one that is asked for.
+/// Reads of the parts that fail with an error that may go away are retried up to `retries` times.
+/// The parts are read `buffer_size` bytes at a time.
+pub fn verify(
+    stdout: Option<&mut io::StdoutLock>,
+    dir: &Path,
+    filter: Option<&str>,
+    passphrase: Option<&str>,
+    retries: usize,
+    buffer_size: usize,
+) -> Result<Outcome, Error> {
+    let mut manifest = Manifest::read(dir)?.ok_or_else(|| {
+        Error(
+            format!(
+                "There is no {} in {} to check the parts against.",
+                manifest::FILE_NAME,
+                dir.to_string_lossy()
+            )
+            .into(),
+        )
+    })?;
+    manifest.parts.sort_unstable_by_key(|part| part.index);
+    check_filter(&manifest, filter)?;
 
-    let mut files = Vec::<File>::new();
+    let algorithm = manifest.checksum_algorithm;
+    let compression = manifest.compression;
+    let mut failures = Vec::<String>::new();
+    let mut paths = Vec::with_capacity(manifest.parts.len());
     let mut total_len = 0;
+    for part in &manifest.parts {
+        let path = dir.join(&part.file_name);
+        if get_len(&path)?.is_some() {
+            total_len += part.len;
+            paths.push(Some(path));
+        } else {
+            paths.push(None);
+        }
+    }
 
-    for path in &path_bufs {
-        let fs_file = fs::File::open(path)?;
-        let trailing_number = get_trailing_number(&path)?;
+    let existing_paths = paths.iter().flatten().cloned().collect::<Vec<PathBuf>>();
+    let key = get_key(
+        &existing_paths,
+        manifest.encoding,
+        Some(&manifest),
+        filter,
+        passphrase,
+        retries,
+    )?;
 
-        total_len += fs_file.metadata()?.len();
+    let mut progress = Progress::new(stdout, "Verifying", total_len);
+    let mut buffer = vec![0_u8; buffer_size];
+    let mut file_hasher = algorithm.hasher();
+    let mut unpadded_len = get_unpadded_len(&manifest);
 
-        let file = File {
-            file: fs_file,
-            trailing_number,
+    for (index, (part, path)) in manifest.parts.iter().zip(paths).enumerate() {
+        let mut reader = match path {
+            Some(path) => open_part_reader(
+                &path,
+                manifest.encoding,
+                compression,
+                filter,
+                key.as_ref(),
+                retries,
+            )?,
+            None => {
+                failures.push(format!("{}: missing", part.file_name));
+                continue;
+            }
         };
 
-        files.push(file)
+        let mut part_hasher = algorithm.hasher();
+        let mut part_len = 0;
+        let mut header_len = header_len(manifest.part_header_len, manifest.csv_header_len, index);
+        let result = read_chunks(&mut reader, &mut buffer, |chunk| {
+            part_hasher.update(chunk);
+            file_hasher.update(cut_padding(
+                skip_header(chunk, &mut header_len),
+                &mut unpadded_len,
+            ));
+            part_len += chunk.len() as u64;
+            progress.advance(chunk.len() as u64);
+            Ok(())
+        });
+
+        let actual_checksum = part_hasher.finalize();
+        // A compressed or encrypted part that is corrupted may not even be decompressed or decrypted.
+        if result.is_err() {
+            failures.push(format!("{}: corrupted", part.file_name));
+        } else if part_len != part.len {
+            failures.push(format!("{}: wrong length", part.file_name));
+        } else if part
+            .checksum
+            .as_ref()
+            .is_some_and(|checksum| *checksum != actual_checksum)
+        {
+            failures.push(format!("{}: corrupted", part.file_name));
+        }
+    }
+
+    progress.finish();
+
+    // If every part matches, the file can only differ if the parts are listed in the wrong order.
+    // Striped parts only add up to the file once they are dealt back, so that is not checked for them.
+    if failures.is_empty() && manifest.stripe_size.is_none() {
+        if let Some(checksum) = &manifest.checksum {
+            if *checksum != file_hasher.finalize() {
+                failures.push(format!(
+                    "The parts do not add up to {}. Its {} checksum does not match.",
+                    manifest.original_file_name(),
+                    algorithm.display_name()
+                ));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        let message = format!(
+            "All {} parts of {} are intact.",
+            manifest.parts.len(),
+            manifest.original_file_name()
+        );
+        Ok(Outcome {
+            operation: Operation::Verify,
+            input: Some(dir.to_path_buf()),
+            output: None,
+            part_count: Some(manifest.parts.len()),
+            file_len: manifest.file_len,
+            part_lens: Some(manifest.parts.iter().map(|part| part.len).collect()),
+            dry_run: false,
+            message: message.into(),
+        })
+    } else {
+        // Joining rebuilds as many parts as there are recovery parts on its own,
+        // or a single missing part from the parity file, as long as nothing else is wrong.
+        let note = if let Some(recovery) = &manifest.recovery {
+            format!(
+                "\n\nUp to {} missing or corrupted parts can be rebuilt from the recovery parts by joining the parts.",
+                recovery.recovery_shards
+            )
+        } else if manifest.parity.is_some()
+            && failures.len() == 1
+            && failures[0].ends_with(": missing")
+        {
+            "\n\nThe missing part can be rebuilt from the parity file by joining the parts."
+                .to_string()
+        } else {
+            String::new()
+        };
+        Err(Error(
+            format!(
+                "Some parts of {} are missing or corrupted:\n\n{}{}",
+                manifest.original_file_name(),
+                failures.join("\n"),
+                note
+            )
+            .into(),
+        ))
+    }
+}
+
+/// Lists the parts of the split in the split folder `dir` without reading them, for when joining them fails.
+/// The report says what the original file is called, how long it is, how many parts there are,
+/// whether each of them is there and how long it is, and which parts are missing.
+///
+/// The parts are taken from the manifest if there is one. Otherwise, they are found by their filenames,
+/// and parts after the one with the highest trailing number cannot be known to be missing.
+pub fn list(dir: &Path, options: &Options) -> Result<Outcome, Error> {
+    // The parts and their lengths, or `None` for missing ones, and the original filename and length.
+    let (parts, file_name, file_len) = match Manifest::read(dir)? {
+        Some(mut manifest) => {
+            manifest.parts.sort_unstable_by_key(|part| part.index);
+            let mut parts = Vec::<(String, Option<u64>, Option<u64>)>::new();
+            for part in &manifest.parts {
+                let len = get_len(&dir.join(&part.file_name))?;
+                parts.push((part.file_name.clone(), len, Some(part.len)));
+            }
+            let file_name = manifest.original_file_name().to_string();
+            (parts, file_name, manifest.file_len)
+        }
+        None => {
+            let mut numbered_parts = Vec::<NumberedPart>::new();
+            for entry in dir.read_dir()? {
+                let path = entry?.path();
+                let number = match options.format {
+                    NameFormat::Numbered | NameFormat::Extension
+                        if path.is_file() && is_part(&path, options.separator()) =>
+                    {
+                        get_trailing_number(&path)?
+                    }
+                    NameFormat::Gnu if path.is_file() => {
+                        match get_gnu_suffix(&path).and_then(parse_gnu_suffix) {
+                            Some(number) => number,
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+                numbered_parts.push(NumberedPart { path, number });
+            }
+            if numbered_parts.is_empty() {
+                return Err(Error(
+                    format!(
+                        "There are no parts in {} and no {}.",
+                        dir.to_string_lossy(),
+                        manifest::FILE_NAME
+                    )
+                    .into(),
+                ));
+            }
+            numbered_parts.sort_unstable_by_key(|part| part.number);
+
+            let paths = numbered_parts
+                .iter()
+                .map(|part| part.path.clone())
+                .collect::<Vec<PathBuf>>();
+            let file_name = get_split_file_name(None, &paths, options)?;
+
+            // Without a manifest, the parts that are there are the only ones that are known of.
+            // Numbers between them that no part has are gaps.
+            let last_number = numbered_parts.last().map_or(0, |part| part.number);
+            let mut parts = Vec::<(String, Option<u64>, Option<u64>)>::new();
+            let mut file_len = 0;
+            for number in 1..=last_number {
+                let found = numbered_parts
+                    .iter()
+                    .filter(|part| part.number == number)
+                    .collect::<Vec<&NumberedPart>>();
+                if found.is_empty() {
+                    parts.push((format!("Part {}", number), None, None));
+                }
+                for part in found {
+                    let len = get_len(&part.path)?;
+                    file_len += len.unwrap_or(0);
+                    parts.push((
+                        part.path.file_name().map_or_else(String::new, |file_name| {
+                            file_name.to_string_lossy().into_owned()
+                        }),
+                        len,
+                        None,
+                    ));
+                }
+            }
+            (parts, file_name, file_len)
+        }
+    };
+
+    let mut lines = Vec::<String>::with_capacity(parts.len());
+    let mut missing = Vec::<&str>::new();
+    for (file_name, len, expected_len) in &parts {
+        lines.push(match (len, expected_len) {
+            (None, _) => {
+                missing.push(file_name);
+                format!("{}: missing", file_name)
+            }
+            (Some(len), Some(expected_len)) if len != expected_len => format!(
+                "{}: {} bytes, but it should be {} bytes",
+                file_name, len, expected_len
+            ),
+            (Some(len), _) => format!("{}: {} bytes", file_name, len),
+        });
+    }
+
+    let missing = match missing.len() {
+        0 => "No parts are missing.".to_string(),
+        1 => format!("1 part is missing: {}", missing[0]),
+        len => format!("{} parts are missing: {}", len, missing.join(", ")),
+    };
+    let message = format!(
+        "{}: {} bytes in {} parts\n\n{}\n\n{}",
+        file_name,
+        file_len,
+        parts.len(),
+        lines.join("\n"),
+        missing
+    );
+
+    Ok(Outcome {
+        operation: Operation::List,
+        input: Some(dir.to_path_buf()),
+        output: None,
+        part_count: Some(parts.len()),
+        file_len,
+        part_lens: None,
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Reconstructs the original filename from the filename of the part at `path`,
+/// whose trailing number follows `separator`. Like the filename, it does not have to be UTF-8.
+fn get_original_file_name_bytes<'a>(path: &'a Path, separator: &str) -> Result<&'a [u8], Error> {
+    if path.is_file() {
+        get_part_file_name(path)
+            .and_then(|file_name| split_file_name(file_name, separator))
+            .ok_or_else(|| Error(format!("Invalid filename: {}", path.to_string_lossy()).into()))
+    } else {
+        Err(Error(
+            format!("{} is not a file", path.to_string_lossy()).into(),
+        ))
+    }
+}
+
+/// Like [`get_original_file_name_bytes`], but the parts that are not UTF-8 are replaced with `�`.
+fn get_original_file_name(path: &Path, separator: &str) -> Result<String, Error> {
+    get_original_file_name_bytes(path, separator)
+        .map(|file_name| String::from_utf8_lossy(file_name).into_owned())
+}
+
+/// Returns the name of the file that was split, which is taken from the `manifest` if there is one
+/// and from the filename of the first of the parts at `path_bufs` otherwise.
+/// Parts named like the ones of GNU split do not have it in their filenames, so it is the [`GNU_PREFIX`] for them.
+fn get_split_file_name(
+    manifest: Option<&Manifest>,
+    path_bufs: &[PathBuf],
+    options: &Options,
+) -> Result<String, Error> {
+    match (manifest, options.format) {
+        (Some(manifest), _) => Ok(manifest.file_name.clone()),
+        (None, NameFormat::Numbered | NameFormat::Extension) => match path_bufs.first() {
+            Some(path) => get_original_file_name(path, options.separator()),
+            None => Err(Error("No parts to join.".into())),
+        },
+        (None, NameFormat::Gnu) => Ok(GNU_PREFIX.to_string()),
+    }
+}
+
+/// Sorts the parts in `path_bufs` by their alphabetic suffixes, like the ones of GNU split, and makes sure none are missing.
+fn sort_gnu_parts(path_bufs: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::<NumberedPart>::with_capacity(path_bufs.len());
+    let mut suffix_len = None;
+
+    for path in path_bufs {
+        let suffix = get_gnu_suffix(path)
+            .ok_or_else(|| Error(format!("Invalid filename: {}", path.to_string_lossy()).into()))?;
+
+        // The suffixes of a split all have the same length, so `xaa` and `xaaa` cannot be from the same split.
+        if *suffix_len.get_or_insert(suffix.len()) != suffix.len() {
+            return Err(Error(
+                "The suffixes of the parts have different lengths. Make sure they all belong to the same split."
+                    .into(),
+            ));
+        }
+
+        files.push(NumberedPart {
+            path: path.clone(),
+            // The suffix was checked to be valid when it was found.
+            number: parse_gnu_suffix(suffix).unwrap(),
+        });
     }
 
-    // We make no assumptions about the order of `files` and sort it by trailing number.
-    // We can use an unstable sort because our input is guaranteed to have no duplicates.
-    files.sort_unstable_by(|file1, file2| file1.trailing_number.cmp(&file2.trailing_number));
+    files.sort_unstable_by_key(|file| file.number);
 
     for (index, file) in files.iter().enumerate() {
-        if index + 1 != file.trailing_number {
+        if index + 1 != file.number {
+            return Err(Error(
+                format!(
+                    "Part {}{} is missing. Make sure you provided all split files.",
+                    GNU_PREFIX,
+                    split::gnu_suffix(index + 1, suffix_len.unwrap_or(2))
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(files.into_iter().map(|file| file.path).collect())
+}
+
+/// Sorts the parts in `path_bufs` by their trailing numbers, which follow `separator`, and makes sure none are missing.
+fn sort_parts_from_file_names(
+    path_bufs: &[PathBuf],
+    separator: &str,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::<NumberedPart>::with_capacity(path_bufs.len());
+
+    for path in path_bufs {
+        // This names the file if it is not a part at all.
+        get_original_file_name_bytes(path, separator)?;
+        let trailing_number = get_trailing_number(path)?;
+
+        files.push(NumberedPart {
+            path: path.clone(),
+            number: trailing_number,
+        })
+    }
+
+    // We make no assumptions about the order of `files`, which is up to the file dialog or the filesystem, and sort it by trailing number.
+    files.sort_unstable_by_key(|file| file.number);
+
+    // Parts of different files could have matching trailing numbers, so they are told apart by their names.
+    // Those are compared to the name of the part with the lowest number rather than to whichever file came first.
+    let (first, rest) = files
+        .split_first()
+        .ok_or_else(|| Error("No parts to join.".into()))?;
+    let original_file_name = get_original_file_name_bytes(&first.path, separator)?;
+    if let Some(file) = rest.iter().find(|file| {
+        get_original_file_name_bytes(&file.path, separator).ok() != Some(original_file_name)
+    }) {
+        return Err(Error(
+            format!(
+                "{} is not a part of {}. Make sure all parts belong to the same split.",
+                file.path.to_string_lossy(),
+                String::from_utf8_lossy(original_file_name)
+            )
+            .into(),
+        ));
+    }
+
+    // Padded and unpadded names such as `foo-split-1` and `foo-split-01` have the same trailing number.
+    if let Some(files) = files
+        .windows(2)
+        .find(|files| files[0].number == files[1].number)
+    {
+        return Err(Error(
+            format!(
+                "There is more than one part with the trailing number {}.",
+                files[0].number
+            )
+            .into(),
+        ));
+    }
+
+    for (index, file) in files.iter().enumerate() {
+        if index + 1 != file.number {
             return Err(Error(
                 "Trailing number mismatch. Make sure you provided all split files.".into(),
             ));
         }
     }
 
+    Ok(files.into_iter().map(|file| file.path).collect())
+}
+
+/// Reads the part headers of the parts at `path_bufs`, in the same order.
+/// Either all of them have one or none of them do, in which case there are none.
+fn read_part_headers(path_bufs: &[PathBuf]) -> Result<Vec<PartHeader>, Error> {
+    let mut part_headers = Vec::with_capacity(path_bufs.len());
+    for path in path_bufs {
+        part_headers.push(part_header::read_file(path)?);
+    }
+
+    if part_headers.iter().all(Option::is_none) {
+        return Ok(Vec::new());
+    }
+
+    match path_bufs
+        .iter()
+        .zip(&part_headers)
+        .find(|(_, part_header)| part_header.is_none())
+    {
+        Some((path, _)) => Err(Error(
+            format!(
+                "{} has no part header, unlike the other parts. Make sure all parts belong to the same split.",
+                path.to_string_lossy()
+            )
+            .into(),
+        )),
+        None => Ok(part_headers.into_iter().flatten().collect()),
+    }
+}
+
+/// Sorts the parts in `path_bufs` by the indices in their `part_headers` and makes sure none are missing.
+///
+/// Their filenames do not have to have trailing numbers, so parts whose filenames were changed can still be joined.
+/// If they do have trailing numbers following `separator`, those have to match the indices though,
+/// because otherwise it is not clear which of them is right.
+fn sort_parts_from_headers(
+    path_bufs: &[PathBuf],
+    part_headers: &[PartHeader],
+    separator: &str,
+) -> Result<Vec<PathBuf>, Error> {
+    let first_header = &part_headers[0];
+    let mut parts = Vec::<NumberedPart>::with_capacity(path_bufs.len());
+
+    for (path, part_header) in path_bufs.iter().zip(part_headers) {
+        if part_header.file_name != first_header.file_name
+            || part_header.file_len != first_header.file_len
+            || part_header.part_count != first_header.part_count
+        {
+            return Err(Error(
+                format!(
+                    "{} is not a part of {}. Make sure all parts belong to the same split.",
+                    path.to_string_lossy(),
+                    first_header.file_name
+                )
+                .into(),
+            ));
+        }
+
+        if is_part(path, separator) {
+            let trailing_number = get_trailing_number(path)?;
+            if trailing_number as u64 != part_header.index {
+                return Err(Error(
+                    format!(
+                        "{} is part {} according to its part header, but its trailing number is {}. Please rename it.",
+                        path.to_string_lossy(),
+                        part_header.index,
+                        trailing_number
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        parts.push(NumberedPart {
+            path: path.clone(),
+            number: part_header.index as usize,
+        });
+    }
+
+    parts.sort_unstable_by_key(|part| part.number);
+
+    if let Some(parts) = parts
+        .windows(2)
+        .find(|parts| parts[0].number == parts[1].number)
+    {
+        return Err(Error(
+            format!(
+                "There is more than one part {} of {}.",
+                parts[0].number, first_header.file_name
+            )
+            .into(),
+        ));
+    }
+
+    if let Some(missing_index) = (1..=first_header.part_count as usize)
+        .zip(
+            parts
+                .iter()
+                .map(|part| part.number)
+                .chain(std::iter::repeat(0)),
+        )
+        .find(|(index, number)| index != number)
+        .map(|(index, _)| index)
+    {
+        return Err(Error(
+            format!(
+                "Part {} of {} is missing.",
+                missing_index, first_header.file_name
+            )
+            .into(),
+        ));
+    }
+
+    Ok(parts.into_iter().map(|part| part.path).collect())
+}
+
+/// Joins the parts at `path_bufs` and reports progress on `stdout`.
+///
+/// The parts are concatenated in the order of their indices in the manifest or, without one, of their trailing numbers.
+/// This is the order they were written in by [`crate::split::split`], which writes consecutive byte ranges of the file.
+/// Parts of a split made with `--stripe` are instead read one stripe at a time, in the order the stripes were dealt out in.
+///
+/// The parts can be given in any order, such as the one of a file dialog, and the manifest and the checksum files can be given along with them.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::{args::Options, join::join};
+/// # use std::{fs, io};
+/// let dir = std::env::temp_dir().join("splitter-join-example");
+/// # let _ = fs::remove_dir_all(&dir);
+/// fs::create_dir_all(&dir).unwrap();
+/// for (number, content) in [(1, "Hello, "), (2, "World"), (3, "!")] {
+///     fs::write(dir.join(format!("hello.txt-split-{}", number)), content).unwrap();
+/// }
+/// let stdout = io::stdout();
+///
+/// let options = Options {
+///     output: Some(dir.join("joined.txt")),
+///     quiet: true,
+///     ..Options::default()
+/// };
+/// let parts = [3, 1, 2].iter().map(|number| dir.join(format!("hello.txt-split-{}", number)));
+/// join(&mut stdout.lock(), parts.collect(), &options).unwrap();
+/// assert_eq!(fs::read_to_string(dir.join("joined.txt")).unwrap(), "Hello, World!");
+///
+/// let options = Options {
+///     output: Some(dir.join("first.txt")),
+///     quiet: true,
+///     ..Options::default()
+/// };
+/// join(&mut stdout.lock(), vec![dir.join("hello.txt-split-1")], &options).unwrap();
+/// assert_eq!(fs::read_to_string(dir.join("first.txt")).unwrap(), "Hello, ");
+///
+/// let err = join(&mut stdout.lock(), Vec::new(), &options).err().unwrap();
+/// assert_eq!(err.0, "No files to join.");
+/// # fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn join(
+    stdout: &mut io::StdoutLock,
+    path_bufs: Vec<PathBuf>,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    if options.stdout {
+        let mut parts = find_parts(None, path_bufs, options)?;
+        let len = join_to_stdout(stdout, &mut parts)?;
+        let cleanup = if options.cleanup {
+            format!("\n\n{}", clean_up(&parts, None)?)
+        } else {
+            String::new()
+        };
+        let message = format!(
+            "Successful join. {} bytes were written to stdout.{}{}{}",
+            len,
+            describe_rebuilt_part(&parts),
+            describe_possible_padding(&parts),
+            cleanup
+        );
+        return Ok(Outcome {
+            operation: Operation::Join,
+            input: Some(get_split_folder(&parts)),
+            output: None,
+            part_count: Some(parts.paths.len()),
+            file_len: len,
+            part_lens: None,
+            dry_run: false,
+            message: message.into(),
+        });
+    }
+
+    let mut parts = find_parts(options.info_stdout(stdout), path_bufs, options)?;
+    let report = join_parts(
+        options.info_stdout(stdout),
+        &mut parts,
+        options.output.as_deref(),
+        options,
+    )?;
+    let cleanup = if options.cleanup {
+        format!("\n\n{}", clean_up(&parts, Some(&report.output))?)
+    } else {
+        String::new()
+    };
+
+    let message = format!(
+        "Successful join. Joined file: {}{}{}{}",
+        report.output.to_string_lossy(),
+        describe_rebuilt_part(&parts),
+        describe_possible_padding(&parts),
+        cleanup
+    );
+    Ok(Outcome {
+        operation: Operation::Join,
+        input: Some(get_split_folder(&parts)),
+        output: Some(report.output),
+        part_count: Some(parts.paths.len()),
+        file_len: report.len,
+        part_lens: None,
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Returns the split folder the `parts` are in.
+fn get_split_folder(parts: &FoundParts) -> PathBuf {
+    parts.paths[0]
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf()
+}
+
+/// Tells which of the `parts` were rebuilt from the parity file or the recovery parts, if any were.
+fn describe_rebuilt_part(parts: &FoundParts) -> String {
+    match &parts.rebuilt {
+        Some(rebuilt) => format!("\n\n{}", rebuilt),
+        None => String::new(),
+    }
+}
+
+/// Returns a warning if the last of the `parts` may have been padded with `--pad`, as the padding was not removed then.
+fn describe_possible_padding(parts: &FoundParts) -> &'static str {
+    if parts.possibly_padded {
+        "\n\nWarning: the parts are all the same length, so the last one may have been padded with --pad. Without a manifest, the padding cannot be told apart from the file and was joined as well."
+    } else {
+        ""
+    }
+}
+
+/// Removes the `parts` once they were joined with `--cleanup`, along with the manifest, the checksum files and the join scripts describing them,
+/// and their split folder if nothing else is left in it. The joined file at `output` is kept, even if it is in the split folder.
+/// Returns what was removed.
+fn clean_up(parts: &FoundParts, output: Option<&Path>) -> Result<String, Error> {
+    let split_folder = parts.paths[0].parent().unwrap_or_else(|| Path::new(""));
+    let output = output.and_then(|output| fs::canonicalize(output).ok());
+
+    let describing_files = [
+        manifest::FILE_NAME.to_string(),
+        sha256sums::FILE_NAME.to_string(),
+        sfv::file_name(&split::get_base_name(&parts.file_name)),
+        join_script::SH_FILE_NAME.to_string(),
+        join_script::CMD_FILE_NAME.to_string(),
+    ]
+    .iter()
+    .chain(&parts.recovery_file_names)
+    .map(|file_name| split_folder.join(file_name))
+    .filter(|path| path.is_file())
+    .collect::<Vec<PathBuf>>();
+
+    for path in parts.paths.iter().chain(&describing_files) {
+        if output.is_some() && fs::canonicalize(path).ok() == output {
+            continue;
+        }
+
+        fs::remove_file(path).map_err(Error::io(format!(
+            "The parts were joined, but {} could not be removed.",
+            path.to_string_lossy()
+        )))?;
+    }
+
+    // Removing a folder only works if it is empty, so a split folder that still contains anything else is kept.
+    if !split_folder.as_os_str().is_empty() && fs::remove_dir(split_folder).is_ok() {
+        Ok(format!(
+            "The {} parts and the split folder {} were removed.",
+            parts.paths.len(),
+            split_folder.to_string_lossy()
+        ))
+    } else {
+        Ok(format!("The {} parts were removed.", parts.paths.len()))
+    }
+}
+
+/// Joins the parts at `parts` into the file `output`.
+///
+/// Unlike [`join`], this does not print anything.
+///
+/// # Examples
+///
+/// Parts named like `file.ext.001`, such as the ones made by HJSplit, are joined in the order of their numbers:
+///
+/// ```
+/// # use std::fs;
+/// let dir = std::env::temp_dir().join(format!("splitter-hjsplit-{}", std::process::id()));
+/// fs::create_dir_all(&dir)?;
+/// for (extension, content) in [("003", "!"), ("001", "Hello, "), ("002", "world")] {
+///     fs::write(dir.join(format!("greeting.txt.{}", extension)), content)?;
+/// }
+///
+/// let parts = ["greeting.txt.002", "greeting.txt.003", "greeting.txt.001"].map(|part| dir.join(part));
+/// let report = splitter::join_files(&parts, &dir.join("greeting.txt"))?;
+///
+/// assert_eq!(fs::read_to_string(&report.output)?, "Hello, world!");
+/// # fs::remove_dir_all(&dir)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn join_files(parts: &[PathBuf], output: &Path) -> Result<JoinReport, Error> {
+    if parts.is_empty() {
+        return Err(Error("No parts were given.".into()));
+    }
+
+    let options = Options::default();
+    let mut parts = find_parts(None, parts.to_vec(), &options)?;
+    join_parts(None, &mut parts, Some(output), &options)
+}
+
+/// What a join created.
+pub struct JoinReport {
+    /// The joined file.
+    pub output: PathBuf,
+    /// The length of the joined file.
+    pub len: u64,
+}
+
+/// The parts of a split, found and checked so that they can be joined.
+struct FoundParts {
+    /// The full name of the file that was split. The parts may be named after a shortened one, see [`split::shorten_file_name`].
+    file_name: String,
+    /// The full name of the file that was split as it was, which differs from `file_name` if it is not UTF-8.
+    /// See [`Manifest::original_os_file_name`].
+    os_file_name: OsString,
+    /// The paths of the parts in order. They are only opened once they are joined.
+    paths: Vec<PathBuf>,
+    encoding: Option<Encoding>,
+    compression: Option<Compression>,
+    /// The length of the parts as they are stored.
+    total_len: u64,
+    /// The length the joined file should have, if it is known.
+    expected_len: Option<u64>,
+    /// The checksum the joined file should have, if it is known.
+    expected_checksum: Option<(checksum::Algorithm, String)>,
+    /// The length of the CSV header that is removed from every part but the first one, if the file was split with `--csv`.
+    csv_header_len: Option<u64>,
+    /// The length of the part header that is removed from every part, if the file was split with `--headers`.
+    part_header_len: Option<u64>,
+    /// The length of the stripes the file was dealt out to the parts in, if it was split with `--stripe`.
+    stripe_size: Option<u64>,
+    /// The command every part is piped through before it is joined, given with `--filter`.
+    filter: Option<String>,
+    /// The key the parts are decrypted with, if they are encrypted.
+    key: Option<Key>,
+    /// How many times a read of a part that failed with an error that may go away is retried.
+    retries: usize,
+    /// The size of the buffer the parts are copied through into the joined file, given with `--buffer-size`.
+    buffer_size: usize,
+    /// The filenames of the parity file or the recovery parts in the split folder, if the split has any.
+    recovery_file_names: Vec<String>,
+    /// What was rebuilt from the parity file or the recovery parts, if anything was.
+    rebuilt: Option<String>,
+    /// When the file that was split was last modified, in seconds since the Unix epoch, if it is known.
+    file_modified_at: Option<u64>,
+    /// The permissions of the file that was split as Unix mode bits, if they are known.
+    file_mode: Option<u32>,
+    /// How many bytes of the parts belong to the joined file, not counting repeated CSV headers.
+    /// This is only less than all of them if the last part is padded.
+    unpadded_len: u64,
+    /// Whether the last part may be padded without a manifest to say so, in which case the padding is joined as well.
+    possibly_padded: bool,
+}
+
+/// Returns the filenames of the parity file and the recovery parts of the split described by the `manifest`, if it has any.
+fn recovery_file_names(manifest: Option<&Manifest>) -> Vec<String> {
+    let manifest = match manifest {
+        Some(manifest) => manifest,
+        None => return Vec::new(),
+    };
+    let parity = manifest
+        .parity
+        .iter()
+        .map(|parity| parity.file_name.clone());
+    let recovery = manifest.recovery.iter().flat_map(|recovery| {
+        recovery.shards[recovery.data_shards.min(recovery.shards.len())..]
+            .iter()
+            .map(|shard| shard.file_name.clone())
+    });
+    parity.chain(recovery).collect()
+}
+
+/// Puts the parts in `path_bufs` in order and checks them as far as that is possible before joining them.
+/// Progress is reported on `stdout` if it is given.
+fn find_parts(
+    mut stdout: Option<&mut io::StdoutLock>,
+    mut path_bufs: Vec<PathBuf>,
+    options: &Options,
+) -> Result<FoundParts, Error> {
+    // A folder without any parts in it gives no files at all.
+    let Some(first_path) = path_bufs.first() else {
+        return Err(Error("No files to join.".into()));
+    };
+
+    // If the split folder has a manifest, it is the authority on which parts there are and what their order is.
+    // Otherwise we fall back to the filenames of the parts.
+    let split_folder = first_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let split_folder = split_folder.as_path();
+
+    // The manifest, the checksums and the join scripts are not parts themselves,
+    // but they may well be selected together with the parts in the file dialog.
+    path_bufs.retain(|path| {
+        path.file_name() != Some(OsStr::new(manifest::FILE_NAME))
+            && path.file_name() != Some(OsStr::new(sha256sums::FILE_NAME))
+            && path.extension() != Some(OsStr::new("sfv"))
+            && !join_script::is_join_script(path)
+    });
+    let mut manifest = Manifest::read(split_folder)?;
+    let recovery_file_names = recovery_file_names(manifest.as_ref());
+    path_bufs.retain(|path| {
+        !recovery_file_names
+            .iter()
+            .any(|file_name| path.file_name() == Some(OsStr::new(file_name)))
+    });
+    // With a manifest, the parts are found from it, so selecting just the manifest is enough.
+    if path_bufs.is_empty() && manifest.is_none() {
+        return Err(Error(
+            "No parts to join. Make sure you selected the parts and not only the files describing them.".into(),
+        ));
+    }
+
+    // Missing or corrupted parts are rebuilt from the recovery parts before anything else checks them.
+    let rebuilt_from_recovery = match &mut manifest {
+        Some(manifest) => {
+            manifest.parts.sort_unstable_by_key(|part| part.index);
+            match &manifest.recovery {
+                Some(recovery) => recovery::repair(
+                    split_folder,
+                    manifest,
+                    recovery,
+                    !options.no_verify,
+                    options.buffer_size(),
+                )?,
+                None => None,
+            }
+        }
+        None => None,
+    };
+
+    if options.check_sfv {
+        let file_name = get_split_file_name(manifest.as_ref(), &path_bufs, options)?;
+        let sfv_path = split_folder.join(sfv::file_name(&file_name));
+        check_sfv(
+            stdout.as_deref_mut(),
+            &sfv_path,
+            split_folder,
+            options.retries(),
+            options.buffer_size(),
+        )?;
+    }
+
+    let sha256sums_path = split_folder.join(sha256sums::FILE_NAME);
+    if !options.no_verify && sha256sums_path.is_file() {
+        check_sha256sums(
+            stdout.as_deref_mut(),
+            &sha256sums_path,
+            split_folder,
+            options.retries(),
+            options.buffer_size(),
+        )?;
+    }
+
+    // Without a manifest, parts with part headers are told apart by those rather than by their filenames,
+    // which may have been changed.
+    let part_headers = match &manifest {
+        Some(_) => Vec::new(),
+        None => read_part_headers(&path_bufs)?,
+    };
+    let part_header = part_headers.first().cloned();
+
+    let (encoding, compression, paths, key, rebuilt) = match &mut manifest {
+        Some(manifest) => {
+            // Anything that looks like a part but is not listed could be a part of another split that got mixed in.
+            if let Some(path) = path_bufs.iter().find(|path| {
+                !manifest
+                    .parts
+                    .iter()
+                    .any(|part| path.file_name() == Some(OsStr::new(&part.file_name)))
+            }) {
+                return Err(Error(
+                    format!(
+                        "{} is not listed in the {}. Make sure all parts belong to the same split.",
+                        path.to_string_lossy(),
+                        manifest::FILE_NAME
+                    )
+                    .into(),
+                ));
+            }
+
+            check_filter(manifest, options.filter.as_deref())?;
+            let (paths, rebuilt_from_parity) =
+                find_parts_from_manifest(split_folder, manifest, options.buffer_size())?;
+            let key = get_key(
+                &paths,
+                manifest.encoding,
+                Some(manifest),
+                options.filter.as_deref(),
+                options.passphrase.as_deref(),
+                options.retries(),
+            )?;
+            if !options.no_verify {
+                let total_len = manifest.parts.iter().map(|part| part.len).sum();
+                let mut progress = Progress::new(stdout, "Verifying", total_len);
+                verify_parts(
+                    &paths,
+                    manifest,
+                    options.filter.as_deref(),
+                    key.as_ref(),
+                    options.retries(),
+                    options.buffer_size(),
+                    &mut progress,
+                )?;
+            }
+            (
+                manifest.encoding,
+                manifest.compression,
+                paths,
+                key,
+                rebuilt_from_recovery.or(rebuilt_from_parity),
+            )
+        }
+        None => {
+            // Putting striped parts one after another would scramble the file.
+            if let Some(path) = path_bufs
+                .iter()
+                .find(|path| is_striped_part(path, options.separator()))
+            {
+                return Err(Error(
+                    format!(
+                        "{} is a part of a split made with --stripe, which can only be joined with its {}.",
+                        path.to_string_lossy(),
+                        manifest::FILE_NAME
+                    )
+                    .into(),
+                ));
+            }
+
+            let encoding = get_encoding(&path_bufs)?;
+            let compression = get_compression(&path_bufs)?;
+            let paths = match &part_header {
+                Some(_) => sort_parts_from_headers(&path_bufs, &part_headers, options.separator())?,
+                None => match options.format {
+                    NameFormat::Numbered | NameFormat::Extension => {
+                        sort_parts_from_file_names(&path_bufs, options.separator())?
+                    }
+                    NameFormat::Gnu => sort_gnu_parts(&path_bufs)?,
+                },
+            };
+            let key = get_key(
+                &paths,
+                encoding,
+                None,
+                options.filter.as_deref(),
+                options.passphrase.as_deref(),
+                options.retries(),
+            )?;
+            (encoding, compression, paths, key, None)
+        }
+    };
+
+    // In scripts that expect to decrypt, unencrypted parts are more likely the wrong ones than a mistake that does no harm.
+    if options.decrypt && key.is_none() {
+        return Err(Error(
+            "The parts are not encrypted, so they cannot be decrypted. Leave out --decrypt to join them as they are.".into(),
+        ));
+    }
+
+    // The joined file gets the full name, even if the parts are named after a shortened one.
+    let file_name = match (&manifest, &part_header) {
+        (Some(manifest), _) => manifest.original_file_name().to_string(),
+        (None, Some(part_header)) => part_header.file_name.clone(),
+        (None, None) => get_split_file_name(None, &paths, options)?,
+    };
+    let os_file_name = match &manifest {
+        Some(manifest) => manifest.original_os_file_name(),
+        None => OsString::from(&file_name),
+    };
+
+    let mut part_lens = Vec::with_capacity(paths.len());
+    for path in &paths {
+        part_lens.push(
+            get_len(path)?.ok_or_else(|| {
+                Error(format!("Part {} is missing.", path.to_string_lossy()).into())
+            })?,
+        );
+    }
+    let total_len = part_lens.iter().sum();
+    // Parts split with `--pad` are all the same length, but only the manifest says whether the last one was padded.
+    let possibly_padded = manifest.is_none()
+        && part_lens.len() > 1
+        && part_lens.iter().all(|len| *len == part_lens[0]);
+
+    // Without a manifest, all we know is that the output should be as long as the parts together,
+    // and for compressed, encrypted, encoded or filtered parts not even that.
+    let expected_len = match (&manifest, &part_header) {
+        (Some(manifest), _) => Some(manifest.file_len),
+        (None, Some(part_header)) => Some(part_header.file_len),
+        (None, None)
+            if encoding.is_none()
+                && compression.is_none()
+                && key.is_none()
+                && options.filter.is_none() =>
+        {
+            Some(total_len)
+        }
+        (None, None) => None,
+    };
+    let csv_header_len = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.csv_header_len);
+    let part_header_len = match &manifest {
+        Some(manifest) => manifest.part_header_len,
+        None => part_header
+            .as_ref()
+            .map(|part_header| part_header.encoded_len()),
+    };
+    let stripe_size = manifest.as_ref().and_then(|manifest| manifest.stripe_size);
+    // Without a manifest, it is not known whether the last part is padded, so it is joined as it is.
+    let unpadded_len = manifest.as_ref().map_or(u64::MAX, get_unpadded_len);
+    let file_modified_at = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.file_modified_at);
+    let file_mode = manifest.as_ref().and_then(|manifest| manifest.file_mode);
+    let expected_checksum = manifest.and_then(|manifest| {
+        let algorithm = manifest.checksum_algorithm;
+        manifest.checksum.map(|checksum| (algorithm, checksum))
+    });
+
+    Ok(FoundParts {
+        file_name,
+        os_file_name,
+        paths,
+        encoding,
+        compression,
+        total_len,
+        expected_len,
+        expected_checksum,
+        csv_header_len,
+        part_header_len,
+        stripe_size,
+        filter: options.filter.clone(),
+        key,
+        retries: options.retries(),
+        buffer_size: options.buffer_size(),
+        recovery_file_names,
+        rebuilt,
+        file_modified_at,
+        file_mode,
+        unpadded_len,
+        possibly_padded,
+    })
+}
+
+/// Joins the `parts` into `output`, or into `joined-<name>` if it is not given.
+/// Progress is reported on `stdout` if it is given.
+fn join_parts(
+    stdout: Option<&mut io::StdoutLock>,
+    parts: &mut FoundParts,
+    output: Option<&Path>,
+    options: &Options,
+) -> Result<JoinReport, Error> {
+    let mut joined_file_name = OsString::from("joined-");
+    joined_file_name.push(&parts.os_file_name);
+    let output_path = match output {
+        Some(output) => output.to_path_buf(),
+        None if joined_file_name.len() <= split::MAX_FILE_NAME_LEN => {
+            PathBuf::from(joined_file_name)
+        }
+        None => PathBuf::from(
+            split::shorten_file_name(
+                &(String::from("joined-") + &parts.file_name),
+                split::MAX_FILE_NAME_LEN,
+            )
+            .into_owned(),
+        ),
+    };
+
+    if !options.no_space_check {
+        free_space::check(
+            output_path.parent().unwrap_or_else(|| Path::new("")),
+            parts.expected_len.unwrap_or(parts.total_len),
+        )?;
+    }
+
     let mut open_options = fs::OpenOptions::new();
-    open_options.write(true).create_new(true);
-    let output_file_name = String::from("joined-") + file_name;
+    if options.force {
+        open_options.write(true).create(true).truncate(true);
+    } else {
+        open_options.write(true).create_new(true);
+    }
     let mut output = open_options
-        .open(&output_file_name)
+        .open(&output_path)
         .map_err(|err| match err.kind() {
             io::ErrorKind::AlreadyExists => Error(
                 format!(
-                    "Failed to create output file. {} already exists.",
-                    output_file_name
+                    "Failed to create output file. {} already exists. Please remove it or use --force.",
+                    output_path.to_string_lossy()
                 )
                 .into(),
             ),
-            _ => Error("Failed to create output file.".into()),
+            _ => Error::io("Failed to create output file.")(err),
+        })?;
+
+    let mut progress = Progress::new(
+        stdout,
+        "Joining",
+        parts.expected_len.unwrap_or(parts.total_len),
+    );
+
+    let result = write_joined_file(&mut output, parts, &mut progress).and_then(|len| {
+        // Writes that only fail once the data actually reaches the disk, such as when it is full, are reported here.
+        output
+            .sync_all()
+            .map_err(Error::io("Failed to write output."))?;
+        Ok(len)
+    });
+
+    match result {
+        Ok(len) => {
+            if !options.no_preserve {
+                restore_metadata(&output, &output_path, parts)?;
+            }
+            Ok(JoinReport {
+                output: output_path,
+                len,
+            })
+        }
+        Err(Error(message)) => {
+            // A joined file that is known to be incomplete or corrupt must not be mistaken for the original file.
+            drop(output);
+            let message = match fs::remove_file(&output_path) {
+                Ok(()) => format!("{}\n\nThe joined file was removed.", message),
+                Err(_) => format!(
+                    "{}\n\nThe joined file {} is broken and could not be removed.",
+                    message,
+                    output_path.to_string_lossy()
+                ),
+            };
+            Err(Error(message.into()))
+        }
+    }
+}
+
+/// Gives the joined file `output` at `output_path` the modification time and the permissions
+/// that the file that was split had, as far as they are known from the manifest of the `parts`.
+fn restore_metadata(
+    output: &fs::File,
+    output_path: &Path,
+    parts: &FoundParts,
+) -> Result<(), Error> {
+    if let Some(modified_at) = parts.file_modified_at.and_then(manifest::from_timestamp) {
+        output.set_modified(modified_at).map_err(Error::io(format!(
+            "Failed to restore the modification time of {}.",
+            output_path.to_string_lossy()
+        )))?;
+    }
+
+    // The permissions come last, as they may make the file read-only.
+    if let Some(mode) = parts.file_mode {
+        let mut permissions = output
+            .metadata()
+            .map_err(Error::io("Failed to read output."))?
+            .permissions();
+        manifest::set_mode(&mut permissions, mode);
+        fs::set_permissions(output_path, permissions).map_err(Error::io(format!(
+            "Failed to restore the permissions of {}.",
+            output_path.to_string_lossy()
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// Joins the `parts` and writes the joined file to `stdout` instead of a file, so that it can be piped.
+/// Nothing else is written to `stdout`.
+fn join_to_stdout(stdout: &mut io::StdoutLock, parts: &mut FoundParts) -> Result<u64, Error> {
+    let mut progress = Progress::new(None, "Joining", 0);
+
+    write_joined_file(stdout, parts, &mut progress).and_then(|len| {
+        stdout
+            .flush()
+            .map_err(Error::io("Failed to write output."))?;
+        Ok(len)
+    })
+}
+
+/// Writes the content of the `parts` to `output` in order and returns its length.
+/// `output` is checked against the expected length and checksum of the `parts` if they are known.
+fn write_joined_file<W: Write>(
+    output: &mut W,
+    parts: &mut FoundParts,
+    progress: &mut Progress,
+) -> Result<u64, Error> {
+    let mut hasher = parts
+        .expected_checksum
+        .as_ref()
+        .map(|(algorithm, _)| algorithm.hasher());
+    let mut buffer = vec![0_u8; parts.buffer_size];
+    let mut output_len = 0;
+    let mut unpadded_len = parts.unpadded_len;
+
+    let mut write_chunk = |chunk: &[u8]| {
+        output
+            .write_all(chunk)
+            .map_err(Error::io("Failed to write output."))?;
+        if let Some(hasher) = &mut hasher {
+            hasher.update(chunk);
+        }
+        output_len += chunk.len() as u64;
+        progress.advance(chunk.len() as u64);
+        Ok(())
+    };
+
+    match parts.stripe_size {
+        Some(stripe_size) => read_stripes(parts, stripe_size, &mut buffer, &mut write_chunk)?,
+        // Each part is streamed into the output in order so that only a small buffer is ever held in memory.
+        None => {
+            for (index, path) in parts.paths.iter().enumerate() {
+                let mut header_len = header_len(parts.part_header_len, parts.csv_header_len, index);
+
+                read_chunks(
+                    &mut open_part_reader(
+                        path,
+                        parts.encoding,
+                        parts.compression,
+                        parts.filter.as_deref(),
+                        parts.key.as_ref(),
+                        parts.retries,
+                    )?,
+                    &mut buffer,
+                    |chunk| {
+                        write_chunk(cut_padding(
+                            skip_header(chunk, &mut header_len),
+                            &mut unpadded_len,
+                        ))
+                    },
+                )?;
+            }
+        }
+    }
+
+    progress.finish();
+
+    if let Some(expected_len) = parts.expected_len {
+        if output_len != expected_len {
+            return Err(Error(
+                format!(
+                    "The joined file is {} bytes long but it should be {} bytes long.",
+                    output_len, expected_len
+                )
+                .into(),
+            ));
+        }
+    }
+
+    // Unlike the checksums of the parts, this also catches parts that were joined in the wrong order.
+    if let (Some((algorithm, expected_checksum)), Some(hasher)) = (&parts.expected_checksum, hasher)
+    {
+        let actual_checksum = hasher.finalize();
+        if *expected_checksum != actual_checksum {
+            return Err(Error(
+                format!(
+                    "The joined file does not match the original file. Its checksum does not match.\n\nExpected {name}: {}\nActual {name}: {}",
+                    expected_checksum,
+                    actual_checksum,
+                    name = algorithm.display_name()
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(output_len)
+}
+
+/// Reads the stripes of the `parts` of a split made with `--stripe` back in the order they were dealt out to the parts in,
+/// one stripe of `stripe_size` bytes from every part in turn, and passes them to `on_chunk` in chunks of at most the length of `buffer`.
+/// All parts are open at the same time, so that parts on different drives are read from at the same time.
+fn read_stripes<F: FnMut(&[u8]) -> Result<(), Error>>(
+    parts: &FoundParts,
+    stripe_size: u64,
+    buffer: &mut [u8],
+    mut on_chunk: F,
+) -> Result<(), Error> {
+    let mut readers = Vec::with_capacity(parts.paths.len());
+    for path in &parts.paths {
+        readers.push(open_part_reader(
+            path,
+            parts.encoding,
+            parts.compression,
+            parts.filter.as_deref(),
+            parts.key.as_ref(),
+            parts.retries,
+        )?);
+    }
+
+    let mut remaining = parts.expected_len.unwrap_or(parts.total_len);
+    let mut index = 0;
+    while remaining > 0 {
+        let len = remaining.min(stripe_size);
+        let mut stripe_len = 0;
+        read_chunks(&mut (&mut readers[index]).take(len), buffer, |chunk| {
+            stripe_len += chunk.len() as u64;
+            on_chunk(chunk)
         })?;
+        if stripe_len < len {
+            return Err(Error(
+                format!(
+                    "Part {} ends in the middle of a stripe.",
+                    parts.paths[index].to_string_lossy()
+                )
+                .into(),
+            ));
+        }
+        remaining -= len;
+        index = (index + 1) % readers.len();
+    }
+
+    Ok(())
+}
 
-    let mut buf = Vec::<u8>::with_capacity(total_len as usize);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TempDir};
 
-    // NOTE: This could be more efficient
-    for file in &mut files {
-        file.file.read_to_end(&mut buf)?;
+    #[test]
+    fn refuses_corrupted_part() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+        let manifest = Manifest::read(&split_folder).unwrap().unwrap();
+
+        let part = &manifest.parts[1];
+        let part_path = split_folder.join(&part.file_name);
+        let mut contents = fs::read(&part_path).unwrap();
+        contents[123] ^= 1;
+        fs::write(&part_path, &contents).unwrap();
+
+        let output = dir.path().join("joined");
+        let err = test_util::join(
+            test_util::files_in(&split_folder),
+            &["--output", output.to_str().unwrap()],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.0,
+            format!(
+                "Part {} is corrupted. Its checksum does not match.\n\nExpected SHA-256: {}\nActual SHA-256: {}",
+                part.file_name,
+                part.checksum.as_ref().unwrap(),
+                crate::to_hex(&Sha256::digest(&contents))
+            )
+        );
+    }
+
+    #[test]
+    fn splits_off_trailing_numbers() {
+        let split = |file_name: &'static [u8], separator| split_file_name(file_name, separator);
+        assert_eq!(
+            split(b"Cargo.toml-split-1", "-split-"),
+            Some(&b"Cargo.toml"[..])
+        );
+        assert_eq!(
+            split(b"my-file.bin-split-3", "-split-"),
+            Some(&b"my-file.bin"[..])
+        );
+        assert_eq!(split(b"a-b-c.tar-3", "-split-"), Some(&b"a-b-c.tar"[..]));
+        assert_eq!(
+            split(b"my-file.bin.part003", ".part"),
+            Some(&b"my-file.bin"[..])
+        );
+        assert_eq!(
+            split(b"my-file.bin.001", "-split-"),
+            Some(&b"my-file.bin"[..])
+        );
+        assert_eq!(
+            split(b"\xff.bin-split-1", "-split-"),
+            Some(&b"\xff.bin"[..])
+        );
+        assert_eq!(split(b"file.bin", "-split-"), None);
+        assert_eq!(split(b"-split-1", "-split-"), None);
+        assert_eq!(split(b"file.bin_1", "-split-"), None);
     }
-    // This panics:
-    // assert_eq!(buf.capacity(), buf.len());
 
-    output
-        .write_all(&buf)
-        .map_err(|_| Error("Failed to write output".into()))?;
+    /// Splits `len` bytes into parts of `split_size` bytes without a manifest, so that the parts are joined by their names,
+    /// and returns the parts.
+    fn split_without_manifest(dir: &TempDir, len: usize, split_size: &str) -> Vec<PathBuf> {
+        let path = dir.write("file.bin", &test_util::data(len));
+        let split_folder = test_util::split(&path, &["--size", split_size])
+            .unwrap()
+            .output
+            .unwrap();
+        fs::remove_file(split_folder.join(manifest::FILE_NAME)).unwrap();
+        test_util::files_in(&split_folder)
+    }
+
+    #[test]
+    fn joins_shuffled_parts() {
+        let dir = TempDir::new();
+        let mut parts = split_without_manifest(&dir, 1000, "100");
+        parts.reverse();
+        parts.rotate_left(3);
+
+        test_util::round_trip_parts(parts, &test_util::data(1000));
+    }
 
-    Ok(format!("Successful join. Joined file: {}", output_file_name).into())
+    #[test]
+    fn joins_single_part() {
+        let dir = TempDir::new();
+        let parts = split_without_manifest(&dir, 100, "100");
+        assert_eq!(parts.len(), 1);
+
+        test_util::round_trip_parts(parts, &test_util::data(100));
+
+        let err = test_util::join(Vec::new(), &[]).err().unwrap();
+        assert_eq!(err.0, "No files to join.");
+    }
+
+    #[test]
+    fn refuses_wrong_length() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+
+        // The parts are intact, but the file was supposedly longer.
+        let manifest_path = split_folder.join(manifest::FILE_NAME);
+        let json = fs::read_to_string(&manifest_path).unwrap().replacen(
+            r#""file_len": 1000"#,
+            r#""file_len": 1001"#,
+            1,
+        );
+        fs::write(&manifest_path, json).unwrap();
+
+        let output = dir.path().join("joined");
+        let err = test_util::join(
+            test_util::files_in(&split_folder),
+            &["--output", output.to_str().unwrap()],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.0,
+            "The joined file is 1000 bytes long but it should be 1001 bytes long.\n\nThe joined file was removed."
+        );
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn removes_padding_with_manifest() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300", "--pad"])
+            .unwrap()
+            .output
+            .unwrap();
+
+        let outcome = test_util::round_trip(&split_folder, &test_util::data(1000));
+        assert!(!outcome.message.contains("Warning"), "{}", outcome.message);
+    }
+
+    #[test]
+    fn warns_about_padding_without_manifest() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300", "--pad"])
+            .unwrap()
+            .output
+            .unwrap();
+        fs::remove_file(split_folder.join(manifest::FILE_NAME)).unwrap();
+
+        let mut expected = test_util::data(1000);
+        expected.resize(1200, 0);
+        let outcome = test_util::round_trip(&split_folder, &expected);
+        assert!(
+            outcome.message.ends_with(
+                "the padding cannot be told apart from the file and was joined as well."
+            ),
+            "{}",
+            outcome.message
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_file_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new();
+        let mut split_folders = Vec::new();
+        for file_name in [b"\xff.bin", b"\xfe.bin"] {
+            let path = dir.path().join(OsStr::from_bytes(file_name));
+            fs::write(&path, test_util::data(1000)).unwrap();
+            split_folders.push(
+                test_util::split(&path, &["--size", "300"])
+                    .unwrap()
+                    .output
+                    .unwrap(),
+            );
+        }
+
+        // The names only differ in the byte that is not UTF-8, so the parts must not be named alike.
+        assert_eq!(split_folders[0], dir.path().join("%FF.bin-split"));
+        assert_eq!(split_folders[1], dir.path().join("%FE.bin-split"));
+
+        let parts = find_parts(
+            None,
+            test_util::files_in(&split_folders[0]),
+            &test_util::options(&[]),
+        )
+        .unwrap();
+        assert_eq!(parts.file_name, "%FF.bin");
+        assert_eq!(parts.os_file_name, OsStr::from_bytes(b"\xff.bin"));
+
+        test_util::round_trip(&split_folders[0], &test_util::data(1000));
+    }
 }
@@ -0,0 +1,183 @@
+//! The scripts written with `--join-scripts`, which join the parts without splitter.
+
+use crate::{manifest::Manifest, Error};
+use std::{fs, path::Path};
+
+/// The name of the script that joins the parts with `cat` on Unix-like systems.
+pub const SH_FILE_NAME: &str = "join.sh";
+
+/// The name of the script that joins the parts with `copy /b` on Windows.
+pub const CMD_FILE_NAME: &str = "join.cmd";
+
+/// Returns whether `path` is one of the join scripts.
+pub fn is_join_script(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|file_name| file_name == SH_FILE_NAME || file_name == CMD_FILE_NAME)
+}
+
+/// Writes both join scripts for the parts of the `manifest` into `split_folder`.
+pub fn write(split_folder: &Path, manifest: &Manifest) -> Result<(), Error> {
+    let part_names = manifest
+        .parts
+        .iter()
+        .map(|part| part.file_name.as_str())
+        .collect::<Vec<&str>>();
+
+    for (file_name, script) in [
+        (
+            SH_FILE_NAME,
+            sh_script(&manifest.file_name, manifest.file_len, &part_names),
+        ),
+        (
+            CMD_FILE_NAME,
+            cmd_script(&manifest.file_name, manifest.file_len, &part_names),
+        ),
+    ] {
+        let path = split_folder.join(file_name);
+        fs::write(&path, script).map_err(Error::io(format!(
+            "Failed to write {}.",
+            path.to_string_lossy()
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// Returns a shell script that joins the parts called `part_names` into `file_name`, which should be `file_len` bytes long.
+/// The parts are listed one by one in the given order, so it does not matter how the shell would sort their names.
+///
+/// The script joins the parts in the folder it is in, wherever it is run from.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::join_script;
+/// # #[cfg(unix)]
+/// # {
+/// use std::{fs, process::Command};
+///
+/// let dir = std::env::temp_dir().join("splitter-join-script-example");
+/// # let _ = fs::remove_dir_all(&dir);
+/// fs::create_dir_all(&dir).unwrap();
+/// // Without zero-padding, "it's-split-10" sorts before "it's-split-2".
+/// let part_names = (1..=10)
+///     .map(|index| format!("it's-split-{}", index))
+///     .collect::<Vec<String>>();
+/// for (index, part_name) in part_names.iter().enumerate() {
+///     fs::write(dir.join(part_name), [index as u8; 3]).unwrap();
+/// }
+/// let part_names = part_names.iter().map(String::as_str).collect::<Vec<&str>>();
+/// fs::write(
+///     dir.join(join_script::SH_FILE_NAME),
+///     join_script::sh_script("it's", 30, &part_names),
+/// )
+/// .unwrap();
+///
+/// let status = Command::new("sh")
+///     .arg(dir.join(join_script::SH_FILE_NAME))
+///     .status()
+///     .unwrap();
+///
+/// assert!(status.success());
+/// let joined = fs::read(dir.join("it's")).unwrap();
+/// assert_eq!(joined, (0..10).flat_map(|index| [index; 3]).collect::<Vec<u8>>());
+/// # fs::remove_dir_all(&dir).unwrap();
+/// # }
+/// ```
+pub fn sh_script(file_name: &str, file_len: u64, part_names: &[&str]) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n\
+         # Joins the parts into {} ({} bytes). Written by splitter.\n\
+         # Run it with: sh {}\n\
+         set -e\n\
+         cd \"$(dirname \"$0\")\"\n\
+         cat",
+        file_name.replace('\n', " "),
+        file_len,
+        SH_FILE_NAME
+    );
+    for part_name in part_names {
+        script += &format!(" \\\n    {}", quote_sh(part_name));
+    }
+    script += &format!(" \\\n    > {}\n", quote_sh(file_name));
+    script
+}
+
+/// Quotes `arg` so that the shell passes it on as it is.
+/// Nothing is special within single quotes, so only single quotes themselves have to be put outside of them.
+fn quote_sh(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Returns a batch file that joins the parts called `part_names` into `file_name`, which should be `file_len` bytes long.
+/// The parts are listed one by one in the given order, and every part is appended on its own,
+/// so that the command line does not get too long for many parts.
+///
+/// The batch file joins the parts in the folder it is in, wherever it is run from.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::join_script;
+/// let script = join_script::cmd_script("100%.bin", 6, &["100%.bin-split-1", "100%.bin-split-2"]);
+///
+/// assert!(script.contains("copy /b \"100%%.bin-split-1\" \"100%%.bin\" >nul\r\n"));
+/// assert!(script.contains("copy /b \"100%%.bin\" + \"100%%.bin-split-2\" \"100%%.bin\" >nul\r\n"));
+/// ```
+pub fn cmd_script(file_name: &str, file_len: u64, part_names: &[&str]) -> String {
+    let output = quote_cmd(file_name);
+    let mut script = format!(
+        "@echo off\r\n\
+         rem Joins the parts into {} ({} bytes). Written by splitter.\r\n\
+         rem Run it by double-clicking it.\r\n\
+         cd /d \"%~dp0\"\r\n",
+        file_name.replace('%', "%%"),
+        file_len
+    );
+    for (index, part_name) in part_names.iter().enumerate() {
+        // `copy /b` appends in place if the destination is also the first source.
+        let sources = match index {
+            0 => quote_cmd(part_name),
+            _ => format!("{} + {}", output, quote_cmd(part_name)),
+        };
+        script += &format!(
+            "copy /b {} {} >nul\r\nif errorlevel 1 exit /b 1\r\n",
+            sources, output
+        );
+    }
+    script
+}
+
+/// Quotes `arg` for a batch file. Filenames on Windows cannot contain double quotes,
+/// but percent signs would still be taken as variables in a batch file, so they are doubled.
+fn quote_cmd(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('%', "%%"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TempDir};
+
+    #[test]
+    fn sh_script_joins_split() {
+        let dir = TempDir::new();
+        let contents = test_util::data(1050);
+        let path = dir.write("it's a file.bin", &contents);
+        let split_folder = test_util::split(&path, &["--size", "100", "--join-scripts"])
+            .unwrap()
+            .output
+            .unwrap();
+
+        // The script is run from somewhere else than the split folder.
+        let status = std::process::Command::new("sh")
+            .arg(split_folder.join(SH_FILE_NAME))
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        let joined = fs::read(split_folder.join("it's a file.bin")).unwrap();
+        assert_eq!(joined, contents);
+    }
+}
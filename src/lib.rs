@@ -0,0 +1,184 @@
+//! Splits files into parts and joins them back together.
+//!
+//! The binary adds prompts and dialogs on top of this, but the splitting and joining itself
+//! does not need them and can be used on its own:
+//!
+//! ```no_run
+//! use std::path::{Path, PathBuf};
+//!
+//! let report = splitter::split_file(Path::new("bigfile.bin"), 100_000_000, Path::new("out"))?;
+//! let parts = report
+//!     .manifest
+//!     .parts
+//!     .iter()
+//!     .map(|part| report.split_folder.join(&part.file_name))
+//!     .collect::<Vec<PathBuf>>();
+//!
+//! splitter::join_files(&parts, Path::new("bigfile.bin"))?;
+//! # Ok::<(), splitter::Error>(())
+//! ```
+
+pub mod args;
+mod buffer_pool;
+pub mod checksum;
+pub mod compression;
+pub mod crypt;
+pub mod encoding;
+mod filter;
+mod free_space;
+mod hashing;
+pub mod join;
+pub mod join_script;
+pub mod manifest;
+pub mod outcome;
+pub mod parity;
+pub mod part_header;
+mod progress;
+pub mod recovery;
+mod retry;
+mod sfv;
+mod sha256sums;
+pub mod split;
+#[cfg(test)]
+mod test_util;
+mod thread_writer;
+pub mod zip;
+
+pub use join::{join_files, JoinReport};
+pub use split::{split_file, SplitReport};
+
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt, io,
+    path::Path,
+};
+
+/// Returns the filename of `path`. The names of the parts are made from it and it is stored in the manifest, which is UTF-8,
+/// so the bytes of a filename that are not UTF-8 are written as `%` followed by their hexadecimal value, such as `%FF`.
+/// Unlike `�`, this keeps names apart that only differ in those bytes. The manifest records the actual filename
+/// with [`get_file_name_bytes`], so that joining restores it.
+pub(crate) fn get_file_name(path: &Path) -> Result<Cow<'_, str>, Error> {
+    path.file_name()
+        .map(escape_file_name)
+        .ok_or_else(|| Error(format!("{} has no filename.", path.to_string_lossy()).into()))
+}
+
+#[cfg(unix)]
+fn escape_file_name(file_name: &OsStr) -> Cow<'_, str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = file_name.as_bytes();
+    if let Ok(file_name) = std::str::from_utf8(bytes) {
+        return file_name.into();
+    }
+
+    let mut escaped = String::new();
+    for chunk in bytes.utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped.into()
+}
+
+// Other platforms have no filenames that are arbitrary bytes.
+#[cfg(not(unix))]
+fn escape_file_name(file_name: &OsStr) -> Cow<'_, str> {
+    file_name.to_string_lossy()
+}
+
+/// Returns the filename of `path` as hexadecimal if it is not UTF-8, and `None` if [`get_file_name`] returns it as it is.
+pub(crate) fn get_file_name_bytes(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?;
+    if file_name.to_str().is_some() {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(to_hex(file_name.as_bytes()))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Turns a filename returned by [`get_file_name_bytes`] back into the filename.
+/// Returns `None` if `hex` is not hexadecimal or if filenames cannot be arbitrary bytes on this platform.
+pub(crate) fn file_name_from_hex(hex: &str) -> Option<OsString> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        let bytes = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(OsString::from_vec(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Formats `bytes` as lowercase hexadecimal, which is how checksums are stored.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// An error with a message that is meant to be shown to the user as is.
+#[derive(Debug)]
+pub struct Error(pub Cow<'static, str>);
+
+impl Error {
+    /// Returns a function that turns an `io::Error` into an `Error` which starts with `context`
+    /// and then says what went wrong, such as "Failed to open file. Permission denied."
+    pub fn io<C: Into<Cow<'static, str>>>(context: C) -> impl FnOnce(io::Error) -> Error {
+        let context = context.into();
+        move |err| Error(format!("{} {}", context, describe_io_error(&err)).into())
+    }
+}
+
+fn describe_io_error(err: &io::Error) -> Cow<'static, str> {
+    use io::ErrorKind::*;
+
+    match err.kind() {
+        PermissionDenied => "Permission denied.".into(),
+        NotFound => "File not found.".into(),
+        AlreadyExists => "File already exists.".into(),
+        UnexpectedEof => "Unexpected end of file.".into(),
+        InvalidFilename => {
+            "The name is too long or contains characters that the filesystem does not allow.".into()
+        }
+        _ => format!("{}.", err).into(),
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error(describe_io_error(&err))
+    }
+}
+
+impl From<Cow<'static, str>> for Error {
+    fn from(err: Cow<'static, str>) -> Self {
+        Error(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
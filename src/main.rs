@@ -1,52 +1,48 @@
-mod join;
-mod split;
+mod input_dialog;
 
+use serde::Serialize;
+use splitter::{
+    args::{OptionKind, Options},
+    join, manifest,
+    outcome::Outcome,
+    part_header,
+    split::{self, NameFormat},
+    Error,
+};
 use std::{
     borrow::Cow,
     env,
-    ffi::OsString,
+    ffi::OsStr,
     fs,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     path::{Path, PathBuf},
+    process,
 };
 
-pub fn get_file_name(path: &Path) -> Result<&str, Error> {
-    path.file_name()
-        .unwrap()
-        .to_str()
-        .ok_or_else(|| Error("Invalid UTF-8".into()))
-}
-
-#[derive(Debug)]
-pub struct Error(Cow<'static, str>);
-
-impl From<io::Error> for Error {
-    fn from(err: io::Error) -> Self {
-        use io::ErrorKind::*;
-
-        let msg = match err.kind() {
-            PermissionDenied => "Permission denied.",
-            NotFound => "File not found.",
-            _ => "Unknown error.",
-        };
-
-        Error(msg.into())
-    }
-}
-
-impl From<Cow<'static, str>> for Error {
-    fn from(err: Cow<'static, str>) -> Self {
-        Error(err)
-    }
-}
-
-fn get_paths(entries: fs::ReadDir) -> Result<Vec<PathBuf>, Error> {
+fn get_paths(entries: fs::ReadDir, options: &Options) -> Result<Vec<PathBuf>, Error> {
     let mut paths_vec = Vec::<PathBuf>::new();
 
     for entry in entries {
         match entry {
             Ok(entry) => {
-                paths_vec.push(entry.path());
+                let path = entry.path();
+
+                // Only the parts and the manifest are joined, so stray files like `.DS_Store` are left out.
+                // Parts with part headers are recognized by those even if their filenames were changed.
+                let is_manifest = path.file_name() == Some(OsStr::new(manifest::FILE_NAME));
+                let is_named_like_part = match options.format {
+                    NameFormat::Numbered | NameFormat::Extension => {
+                        join::is_part(&path, options.separator())
+                    }
+                    NameFormat::Gnu => join::is_gnu_part(&path),
+                };
+                if path.is_file()
+                    && (is_manifest
+                        || is_named_like_part
+                        || matches!(part_header::read_file(&path), Ok(Some(_))))
+                {
+                    paths_vec.push(path);
+                }
             }
             Err(err) => return Err(err.into()),
         }
@@ -59,19 +55,47 @@ fn handle_arg(
     stdin: &mut io::StdinLock,
     stdout: &mut io::StdoutLock,
     stderr: &mut io::StderrLock,
-    arg: OsString,
-) -> Result<Cow<'static, str>, Error> {
-    let path = Path::new(&arg);
-    if path.is_dir() {
+    path: &Path,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    if path.is_dir() && options.extract.is_some() {
+        Err(Error(
+            format!(
+                "{} is a folder. Only files can be extracted from.",
+                path.to_string_lossy()
+            )
+            .into(),
+        ))
+    } else if path.is_dir() {
         match path.read_dir() {
-            Ok(entries) => match get_paths(entries) {
-                Ok(vec) => join::join(vec),
+            Ok(_) if options.list => join::list(path, options),
+            Ok(_) if options.verify => join::verify(
+                options.info_stdout(stdout),
+                path,
+                options.filter.as_deref(),
+                options.passphrase.as_deref(),
+                options.retries(),
+                options.buffer_size(),
+            ),
+            Ok(entries) => match get_paths(entries, options) {
+                Ok(vec) => join::join(stdout, vec, options),
                 Err(err) => Err(err),
             },
             Err(_) => Err(Error("Unknown error".into())),
         }
+    } else if path.is_file() && options.list {
+        Err(Error(
+            format!(
+                "{} is a file. Only split folders can be listed.",
+                path.to_string_lossy()
+            )
+            .into(),
+        ))
     } else if path.is_file() {
-        split::split(stdin, stdout, stderr, path.to_path_buf())
+        match &options.extract {
+            Some((start, len)) => split::extract(stdout, path, start, len, options),
+            None => split::split(stdin, stdout, stderr, path.to_path_buf(), options),
+        }
     } else {
         Err(Error(
             format!("File or directory not found: {}", path.to_string_lossy()).into(),
@@ -79,24 +103,248 @@ fn handle_arg(
     }
 }
 
-fn main() {
-    let message_dialog = match run() {
-        Ok(message) => rfd::MessageDialog::new()
-            .set_description(&message)
-            .set_title("splitter")
-            .set_level(rfd::MessageLevel::Info),
-        Err(Error(message)) => rfd::MessageDialog::new()
-            .set_description(&message)
-            .set_title("splitter")
-            .set_level(rfd::MessageLevel::Error),
+/// Splits every file at `paths` on its own, each into its own split folder, for `--split`.
+/// All files are tried before failing so that every file that could not be split is reported.
+fn split_files(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    stderr: &mut io::StderrLock,
+    paths: &[PathBuf],
+    options: &Options,
+) -> Result<Vec<Outcome>, Error> {
+    let mut outcomes = Vec::<Outcome>::with_capacity(paths.len());
+    let mut failures = Vec::<String>::new();
+
+    for path in paths {
+        let result = if path.is_file() {
+            split::split(stdin, stdout, stderr, path.clone(), options)
+        } else {
+            Err(Error("Not a file, so it cannot be split.".into()))
+        };
+
+        match result {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(Error(message)) => {
+                failures.push(format!("{}: {}", path.to_string_lossy(), message))
+            }
+        }
+    }
+
+    if paths.len() == 1 && !failures.is_empty() {
+        return Err(Error(failures.remove(0).into()));
+    }
+    if !failures.is_empty() {
+        return Err(Error(
+            format!(
+                "{} of {} files could not be split:\n\n{}",
+                failures.len(),
+                paths.len(),
+                failures.join("\n")
+            )
+            .into(),
+        ));
+    }
+
+    Ok(outcomes)
+}
+
+/// Returns the message that tells what was done in the `outcomes`, which are more than one if several files were split with `--split`.
+fn describe(mut outcomes: Vec<Outcome>) -> Cow<'static, str> {
+    match outcomes.len() {
+        1 => outcomes.remove(0).message,
+        len => format!(
+            "Split {} files.\n\n{}",
+            len,
+            outcomes
+                .iter()
+                .map(|outcome| outcome.message.as_ref())
+                .collect::<Vec<&str>>()
+                .join("\n\n")
+        )
+        .into(),
+    }
+}
+
+/// What is printed with `--json` for every operation, or for the error that stopped it.
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    success: bool,
+    #[serde(flatten)]
+    outcome: Option<&'a Outcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// Prints the `result` of [`run`] on stdout for `--json`, with one JSON object per line for every file that was split with `--split`.
+fn print_json(result: &Result<Vec<Outcome>, Error>) {
+    let outputs = match result {
+        Ok(outcomes) => outcomes
+            .iter()
+            .map(|outcome| JsonOutput {
+                success: true,
+                outcome: Some(outcome),
+                error: None,
+            })
+            .collect(),
+        Err(Error(message)) => vec![JsonOutput {
+            success: false,
+            outcome: None,
+            error: Some(message),
+        }],
     };
 
-    message_dialog.show();
+    let mut stdout = io::stdout().lock();
+    for output in outputs {
+        let json = serde_json::to_string(&output).expect("an outcome can always be serialized");
+        let _ = writeln!(stdout, "{}", json);
+    }
+}
+
+/// Asks for the split size of the file at `path` with a dialog, for when there is no terminal to ask in.
+fn ask_split_size(path: &Path) -> Result<String, Error> {
+    let file_len = fs::metadata(path)
+        .map_err(Error::io("Failed to open file."))?
+        .len();
+
+    // `rfd` only has Yes and No buttons, so the presets are offered one by one.
+    // Presets that the file already fits into are left out as nothing would be split.
+    for preset in split::PRESETS
+        .iter()
+        .filter(|preset| preset.split_size < file_len)
+    {
+        let chosen = rfd::MessageDialog::new()
+            .set_description(&format!(
+                "Split the file into parts for {} ({} bytes each)? Otherwise another split size can be entered.",
+                preset.description, preset.split_size
+            ))
+            .set_title("splitter")
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+
+        if chosen {
+            return Ok(preset.name.to_string());
+        }
+    }
+
+    let text = format!("File length: {}\n\n{}", file_len, split::split_size_help());
+
+    loop {
+        let input =
+            input_dialog::ask(&text)?.ok_or_else(|| Error("No split size was given.".into()))?;
+
+        // The input is checked here already so that the user can try again.
+        match split::parse_split_size(&input, file_len) {
+            Ok(_) => break Ok(input),
+            Err(err) => {
+                rfd::MessageDialog::new()
+                    .set_description(&format!("{}. Please try again.", err))
+                    .set_title("splitter")
+                    .set_level(rfd::MessageLevel::Error)
+                    .show();
+            }
+        }
+    }
+}
+
+/// The exit code when something failed.
+const EXIT_FAILURE: i32 = 1;
+/// The exit code when the command line is invalid, such as when an option is unknown. Nothing was done then.
+const EXIT_USAGE: i32 = 2;
+
+/// What splitter was asked to do on the command line.
+enum Command {
+    /// Split or join, depending on the paths that were given.
+    Run(Box<Options>),
+    /// `splitter info <part>...` identifies parts on their own by their part headers.
+    Info(Vec<PathBuf>),
+}
+
+fn parse_command() -> Result<Command, Error> {
+    let mut args = env::args_os().peekable();
+
+    args.next(); // This is probably the program name
+
+    if args.peek().is_some_and(|arg| arg == "info") {
+        args.next();
+        let options = Options::parse(args)?;
+        if options.paths.is_empty() {
+            return Err(Error("Please give the parts to identify.".into()));
+        }
+        return Ok(Command::Info(options.paths));
+    }
+
+    let options = Options::parse(args)?;
+    if options.stdin && !options.paths.is_empty() {
+        return Err(Error("No files can be given with --stdin.".into()));
+    }
+    options.check_conflicts(OptionKind::Split, &[OptionKind::Stdin], None)?;
+    options.check_conflicts(
+        OptionKind::Extract,
+        &[OptionKind::Stdin, OptionKind::Split],
+        None,
+    )?;
+    if options.extract.is_some() && options.paths.len() != 1 {
+        return Err(Error("Please give one file to extract from.".into()));
+    }
+    if options.prefix.is_some() && !options.stdin {
+        return Err(Error(
+            "--prefix can only be used with --stdin. Files are named after their own filenames."
+                .into(),
+        ));
+    }
+
+    Ok(Command::Run(Box::new(options)))
 }
 
-fn run() -> Result<Cow<'static, str>, Error> {
-    let mut args = env::args_os();
+fn info(paths: &[PathBuf]) -> Result<Cow<'static, str>, Error> {
+    let mut infos = Vec::<Cow<str>>::with_capacity(paths.len());
+    for path in paths {
+        infos.push(part_header::info(path)?);
+    }
+    Ok(infos.join("\n").into())
+}
 
+fn main() {
+    let mut quiet = false;
+    let result = match parse_command() {
+        Ok(Command::Run(options)) => {
+            quiet = options.quiet;
+            let json = options.json;
+            let result = run(*options);
+            if json {
+                print_json(&result);
+            }
+            result.map(describe).map_err(|err| (err, EXIT_FAILURE))
+        }
+        Ok(Command::Info(paths)) => info(&paths).map_err(|err| (err, EXIT_FAILURE)),
+        Err(err) => Err((err, EXIT_USAGE)),
+    };
+
+    // With `--quiet`, the exit code and stderr are all that tell how it went.
+    if !quiet {
+        let message_dialog = match &result {
+            Ok(message) => rfd::MessageDialog::new()
+                .set_description(message)
+                .set_title("splitter")
+                .set_level(rfd::MessageLevel::Info),
+            Err((Error(message), _)) => rfd::MessageDialog::new()
+                .set_description(message)
+                .set_title("splitter")
+                .set_level(rfd::MessageLevel::Error),
+        };
+
+        message_dialog.show();
+    }
+
+    // Scripts, such as ones checking archived splits with `--verify`, can tell from this whether anything failed and why.
+    // Without a display to show the dialog on, the error would be lost otherwise, so it is also written to stderr.
+    if let Err((Error(message), exit_code)) = result {
+        let _ = writeln!(io::stderr(), "{}", message);
+        process::exit(exit_code);
+    }
+}
+
+fn run(mut options: Options) -> Result<Vec<Outcome>, Error> {
     // NOTE: I want optimal performance, control and I don't want to unlock on every write (which is what println and friends implicitly do).
     //       This is about the best way I found to do that. I'm locking all standard streams at the start and then pass them around throughout the program.
     //       There might be some better way.
@@ -107,22 +355,126 @@ fn run() -> Result<Cow<'static, str>, Error> {
         stderr_handle.lock(),
     );
 
-    args.next(); // This is probably the program name
-
-    if let Some(arg) = args.next() {
-        handle_arg(&mut stdin, &mut stdout, &mut stderr, arg)
+    if options.stdin {
+        split::split_stdin(&mut stdin, &mut stdout, &options).map(|outcome| vec![outcome])
+    } else if options.split && !options.paths.is_empty() {
+        split_files(
+            &mut stdin,
+            &mut stdout,
+            &mut stderr,
+            &options.paths,
+            &options,
+        )
+    } else if let Some(path) = options.paths.first() {
+        handle_arg(&mut stdin, &mut stdout, &mut stderr, path, &options)
+            .map(|outcome| vec![outcome])
     } else {
-        writeln!(
-            stdout,
-            "Please select one file to split or multiple files to join."
-        )?;
+        if !options.quiet {
+            if options.split {
+                writeln!(stdout, "Please select the files to split.")?;
+            } else {
+                writeln!(
+                    stdout,
+                    "Please select one file to split or multiple files to join."
+                )?;
+            }
+        }
 
         if let Some(paths) = rfd::FileDialog::new().pick_files() {
-            if paths.len() > 1 {
-                join::join(paths)
-            } else if let Some(path) = paths.get(0) {
+            if paths.len() > 1 && options.split {
+                // Without a terminal, nobody would see the prompts for the split sizes, so it is asked once for all files.
+                if options.split_size.is_none()
+                    && options.part_count.is_none()
+                    && options.lines_per_part.is_none()
+                    && !stdin.is_terminal()
+                {
+                    options.split_size = Some(ask_split_size(&paths[0])?);
+                }
+
+                if options.remove_source && !options.dry_run {
+                    options.remove_source = rfd::MessageDialog::new()
+                        .set_description(&format!(
+                            "Do you really want to remove the {} files after they were split?",
+                            paths.len()
+                        ))
+                        .set_title("splitter")
+                        .set_level(rfd::MessageLevel::Warning)
+                        .set_buttons(rfd::MessageButtons::YesNo)
+                        .show();
+                }
+
+                split_files(&mut stdin, &mut stdout, &mut stderr, &paths, &options)
+            } else if paths.len() > 1 && options.verify {
+                let split_folder = paths[0].parent().unwrap_or_else(|| Path::new(""));
+                join::verify(
+                    options.info_stdout(&mut stdout),
+                    split_folder,
+                    options.filter.as_deref(),
+                    options.passphrase.as_deref(),
+                    options.retries(),
+                    options.buffer_size(),
+                )
+                .map(|outcome| vec![outcome])
+            } else if paths.len() > 1 {
+                join::join(&mut stdout, paths, &options).map(|outcome| vec![outcome])
+            } else if let Some(path) = paths.first() {
                 if path.is_file() {
-                    split::split(&mut stdin, &mut stdout, &mut stderr, path.clone())
+                    // With `--fit`, the parts go onto the medium instead.
+                    if options.output_dir.is_none() && options.fit.is_none() {
+                        if !options.quiet {
+                            writeln!(
+                                stdout,
+                                "Please select the folder to create the split folder in or cancel to create it next to the file."
+                            )?;
+                        }
+
+                        options.output_dir = rfd::FileDialog::new().pick_folder();
+                    }
+
+                    if !options.force
+                        && !options.no_folder
+                        && !options.zip_volumes
+                        && options.fit.is_none()
+                    {
+                        let split_folder = split::get_split_folder(path, &options)?;
+
+                        if split_folder.exists() {
+                            options.force = rfd::MessageDialog::new()
+                                .set_description(&format!(
+                                    "Folder {} already exists. Do you want to replace it? Otherwise another split folder is created next to it.",
+                                    split_folder.to_string_lossy()
+                                ))
+                                .set_title("splitter")
+                                .set_level(rfd::MessageLevel::Warning)
+                                .set_buttons(rfd::MessageButtons::YesNo)
+                                .show();
+                        }
+                    }
+
+                    // Without a terminal, nobody would see the prompt for the split size.
+                    if options.split_size.is_none()
+                        && options.part_count.is_none()
+                        && options.lines_per_part.is_none()
+                        && options.fit.is_none()
+                        && !stdin.is_terminal()
+                    {
+                        options.split_size = Some(ask_split_size(path)?);
+                    }
+
+                    if options.remove_source && !options.dry_run {
+                        options.remove_source = rfd::MessageDialog::new()
+                            .set_description(&format!(
+                                "Do you really want to remove {} after it was split?",
+                                path.to_string_lossy()
+                            ))
+                            .set_title("splitter")
+                            .set_level(rfd::MessageLevel::Warning)
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                    }
+
+                    split::split(&mut stdin, &mut stdout, &mut stderr, path.clone(), &options)
+                        .map(|outcome| vec![outcome])
                 } else {
                     Err(Error(
                         "Given entry is not a file and cannot be split.".into(),
@@ -0,0 +1,343 @@
+use crate::{checksum, Error};
+pub use crate::{compression::Compression, encoding::Encoding};
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The name of the file in the split folder that the manifest is stored in.
+pub const FILE_NAME: &str = "manifest.json";
+
+/// Describes a split so that joining it does not have to rely on the filenames of the parts.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    /// The name of the file that was split, which the split folder and the parts are named after.
+    pub file_name: String,
+    /// The full name of the file that was split, if it was too long to name the split folder and the parts after
+    /// and `file_name` is a shortened version of it. See [`crate::split::shorten_file_name`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_file_name: Option<String>,
+    /// The full name of the file that was split as hexadecimal, if it is not UTF-8.
+    /// The other names have the bytes that are not UTF-8 written as `%` and their hexadecimal value.
+    /// See [`crate::get_file_name`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name_bytes: Option<String>,
+    /// The length of the file that was split.
+    pub file_len: u64,
+    pub part_count: usize,
+    pub parts: Vec<Part>,
+    /// The algorithm of the checksums. Manifests that do not name one use SHA-256.
+    #[serde(default)]
+    pub checksum_algorithm: checksum::Algorithm,
+    /// The checksum of the file that was split.
+    #[serde(default, alias = "sha256", skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// How the parts are compressed. If this is not given, they are not compressed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    /// How the parts are encrypted, if they were split with `--encrypt`. See [`crate::crypt`].
+    /// The passphrase and the key are never stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<Encryption>,
+    /// The salt the key of the encrypted parts was derived with, as hexadecimal. See [`crate::crypt::Key::salt`].
+    /// Every encrypted part starts with it as well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// How the parts are encoded as text, if they were split with `--base64`. See [`crate::encoding`].
+    /// The lengths of the parts are those before they were encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<Encoding>,
+    /// The command the parts were piped through when they were written, if they were split with `--filter`.
+    /// Joining them requires a `--filter` that reverses it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// The version of splitter that made the split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// How many bytes the last part was padded with to be as long as the others, if it was padded with `--pad`.
+    /// They are removed from it when it is joined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u64>,
+    /// The template the filenames of the parts were made from, if it was not the default one.
+    /// The filenames themselves are listed with the parts, so this is only for reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_template: Option<String>,
+    /// The length of the CSV header that every part but the first one starts with, if the file was split with `--csv`.
+    /// It is removed from those parts when they are joined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv_header_len: Option<u64>,
+    /// The length of the stripes that the file was dealt out to the parts in, if it was split with `--stripe`.
+    /// The parts do not hold consecutive byte ranges of the file then, so they cannot be joined without this.
+    /// See [`crate::split::stripe_parts`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stripe_size: Option<u64>,
+    /// The length of the part header that every part starts with, if the file was split with `--headers`.
+    /// See [`crate::part_header`]. It is removed from the parts when they are joined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_header_len: Option<u64>,
+    /// When the split was made, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// When the file that was split was last modified, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_modified_at: Option<u64>,
+    /// The permissions of the file that was split, as Unix mode bits. See [`to_mode`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_mode: Option<u32>,
+    /// The parity file of the split, if it was made with `--parity`. See [`crate::parity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parity: Option<Parity>,
+    /// The recovery parts of the split, if it was made with `--redundancy`. See [`crate::recovery`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<Recovery>,
+}
+
+/// The parity file of a split, from which one missing part can be rebuilt.
+#[derive(Serialize, Deserialize)]
+pub struct Parity {
+    /// The filename of the parity file in the split folder.
+    pub file_name: String,
+    /// The lengths of the parts as they are stored, in the order of their indices.
+    /// A rebuilt part is cut to its length, as the parity file is as long as the longest part.
+    pub part_lens: Vec<u64>,
+}
+
+/// The recovery parts of a split, from which as many missing or corrupted parts can be rebuilt as there are recovery parts.
+#[derive(Serialize, Deserialize)]
+pub struct Recovery {
+    /// The code the recovery parts were computed with.
+    pub coding: Coding,
+    /// How many of the shards are parts. Any this many intact shards are enough to rebuild the others.
+    pub data_shards: usize,
+    /// How many of the shards are recovery parts.
+    pub recovery_shards: usize,
+    /// The length of every shard. Shorter parts are padded with zeros to this length for the code.
+    pub shard_len: u64,
+    /// The parts as they are stored in the order of their indices, followed by the recovery parts.
+    pub shards: Vec<Shard>,
+}
+
+/// The code the recovery parts of a split were computed with.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Coding {
+    /// Reed-Solomon over GF(2^8), as computed by the `reed-solomon-erasure` crate.
+    #[serde(rename = "reed-solomon-gf8")]
+    ReedSolomonGf8,
+}
+
+/// A part or a recovery part as it is stored.
+#[derive(Serialize, Deserialize)]
+pub struct Shard {
+    pub file_name: String,
+    /// The length of the shard before it is padded.
+    pub len: u64,
+    /// The checksum of the shard as it is stored, with the algorithm of the manifest.
+    pub checksum: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Encryption {
+    /// ChaCha20-Poly1305 with a key derived from a passphrase with Argon2id.
+    #[serde(rename = "chacha20poly1305-argon2id")]
+    ChaCha20Poly1305Argon2id,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Part {
+    /// The position of this part, starting at 1.
+    pub index: usize,
+    pub file_name: String,
+    /// The length of this part before it was compressed or encrypted, including its part header if it has one,
+    /// the CSV header if it was repeated in it and the padding if it was padded.
+    pub len: u64,
+    /// The checksum of this part before it was compressed or encrypted.
+    #[serde(default, alias = "sha256", skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// The random start of the nonces this part was encrypted with, as hexadecimal, if it is encrypted.
+    /// See [`crate::crypt::EncryptWriter::nonce`]. The part starts with it as well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// Returns `time` in seconds since the Unix epoch, which is how times are stored in the manifest.
+pub fn to_timestamp(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+/// Returns the time that `timestamp`, in seconds since the Unix epoch, stands for. This is the reverse of [`to_timestamp`].
+pub fn from_timestamp(timestamp: u64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_secs(timestamp))
+}
+
+/// Returns `permissions` as Unix mode bits, which is how permissions are stored in the manifest.
+/// Other platforms only tell whether a file is read-only, so that is all the mode says there.
+pub fn to_mode(permissions: &fs::Permissions) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.mode() & 0o7777
+    }
+    #[cfg(not(unix))]
+    {
+        if permissions.readonly() {
+            0o444
+        } else {
+            0o644
+        }
+    }
+}
+
+/// Changes `permissions` to the Unix `mode` stored in the manifest, as far as the platform supports it.
+/// This is the reverse of [`to_mode`].
+pub fn set_mode(permissions: &mut fs::Permissions, mode: u32) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(mode);
+    }
+    #[cfg(not(unix))]
+    {
+        permissions.set_readonly(mode & 0o222 == 0);
+    }
+}
+
+impl Manifest {
+    /// Returns the full name of the file that was split, which the joined file is named after.
+    pub fn original_file_name(&self) -> &str {
+        self.original_file_name
+            .as_deref()
+            .unwrap_or(&self.file_name)
+    }
+
+    /// Returns the full name of the file that was split as it was, even if it is not UTF-8.
+    /// Where it cannot be restored, such as on platforms without such filenames, it is [`Manifest::original_file_name`].
+    pub fn original_os_file_name(&self) -> OsString {
+        self.file_name_bytes
+            .as_deref()
+            .and_then(crate::file_name_from_hex)
+            .unwrap_or_else(|| self.original_file_name().into())
+    }
+
+    /// Writes the manifest into the split folder `dir`.
+    pub fn write(&self, dir: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|_| Error("Failed to serialize the manifest.".into()))?;
+
+        fs::write(dir.join(FILE_NAME), json)
+            .map_err(|_| Error("Failed to write the manifest.".into()))
+    }
+
+    /// Reads the manifest from the split folder `dir`, if it has one.
+    pub fn read(dir: &Path) -> Result<Option<Manifest>, Error> {
+        let json = match fs::read_to_string(dir.join(FILE_NAME)) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let manifest: Manifest = serde_json::from_str(&json)
+            .map_err(|_| Error(format!("The {} is invalid.", FILE_NAME).into()))?;
+        manifest.check_file_names()?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Makes sure that every name in the manifest is a plain filename. The parts are looked up, rebuilt and removed
+    /// in the split folder by these names, so a name with a path in it could reach any file outside of it.
+    fn check_file_names(&self) -> Result<(), Error> {
+        let parity = self.parity.iter().map(|parity| &parity.file_name);
+        let shards = self
+            .recovery
+            .iter()
+            .flat_map(|recovery| &recovery.shards)
+            .map(|shard| &shard.file_name);
+        let names = std::iter::once(&self.file_name)
+            .chain(&self.original_file_name)
+            .chain(self.parts.iter().map(|part| &part.file_name))
+            .chain(parity)
+            .chain(shards);
+
+        for name in names {
+            if !is_plain_file_name(name) {
+                return Err(Error(
+                    format!(
+                        "The {} names a file outside of the split folder: {}",
+                        FILE_NAME, name
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        // The joined file is named after it.
+        if let Some(hex) = &self.file_name_bytes {
+            if crate::file_name_from_hex(hex).is_some_and(|name| !is_plain_file_name(&name)) {
+                return Err(Error(
+                    format!(
+                        "The {} names a file outside of the split folder: {}",
+                        FILE_NAME, hex
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `name` is only a filename, without a root, a prefix, a `..` or any other path component.
+fn is_plain_file_name<S: AsRef<OsStr> + ?Sized>(name: &S) -> bool {
+    let name = name.as_ref();
+    Path::new(name).file_name() == Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn manifest_json(part_file_name: &str) -> String {
+        format!(
+            r#"{{"file_name": "file", "file_len": 1, "part_count": 1, "parts": [{{"index": 1, "file_name": {:?}, "len": 1}}]}}"#,
+            part_file_name
+        )
+    }
+
+    #[test]
+    fn read_accepts_plain_file_names() {
+        let dir = TempDir::new();
+        dir.write(FILE_NAME, manifest_json("file-split-1").as_bytes());
+
+        let manifest = Manifest::read(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.parts[0].file_name, "file-split-1");
+    }
+
+    #[test]
+    fn read_rejects_paths() {
+        let dir = TempDir::new();
+        for name in &[
+            "../file-split-1",
+            "/tmp/file-split-1",
+            "sub/file-split-1",
+            "..",
+            ".",
+        ] {
+            dir.write(FILE_NAME, manifest_json(name).as_bytes());
+            assert!(Manifest::read(dir.path()).is_err(), "{}", name);
+        }
+
+        let json = manifest_json("file-split-1").replacen(
+            r#""file_name": "file""#,
+            r#""file_name": "../file""#,
+            1,
+        );
+        dir.write(FILE_NAME, json.as_bytes());
+        assert!(Manifest::read(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! What splitting, joining, verifying, extracting or listing did, as printed with `--json` for tools that run splitter.
+
+use serde::Serialize;
+use std::{borrow::Cow, path::PathBuf};
+
+/// What was done.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Split,
+    Join,
+    Verify,
+    Extract,
+    List,
+}
+
+/// What an operation did once it succeeded.
+#[derive(Serialize)]
+pub struct Outcome {
+    #[serde(rename = "type")]
+    pub operation: Operation,
+    /// The file that was split or extracted from, or the split folder that was joined, verified or listed.
+    /// There is none for input read from stdin.
+    pub input: Option<PathBuf>,
+    /// The split folder, the ZIP archive, the joined file or the extracted file, or where it would be with `--dry-run`.
+    /// There is none for parts that were only verified or listed or a joined file that was written to stdout.
+    pub output: Option<PathBuf>,
+    /// How many parts or ZIP volumes there are, unless that is only known once the file is compressed.
+    pub part_count: Option<usize>,
+    /// The length of the file that was split, joined or extracted, or that the parts that were verified add up to.
+    pub file_len: u64,
+    /// The lengths of the parts in order, if they are known.
+    /// These are the lengths before compression or encryption, like in the manifest.
+    pub part_lens: Option<Vec<u64>>,
+    /// Whether nothing was written because of `--dry-run`.
+    pub dry_run: bool,
+    /// What is shown to the user without `--json`.
+    pub message: Cow<'static, str>,
+}
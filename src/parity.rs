@@ -0,0 +1,258 @@
+//! The parity file of a split made with `--parity`, from which one missing part can be rebuilt.
+//!
+//! The parity file is the XOR of all parts as they are stored, each padded with zeros to the length of the longest one.
+//! XORing it with all parts but one therefore gives back the one that was left out, followed by zeros
+//! that are cut off with the length of that part recorded in the manifest.
+
+use crate::{
+    manifest::{Manifest, Parity},
+    part_header::read_up_to,
+    Error,
+};
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Returns the name of the parity file of the split of the file called `file_name`,
+/// whose parts have `separator` before their trailing numbers.
+///
+/// ```
+/// assert_eq!(splitter::parity::file_name("video.mp4", "-split-"), "video.mp4-split-parity");
+/// ```
+pub fn file_name(file_name: &str, separator: &str) -> String {
+    format!("{}{}parity", file_name, separator)
+}
+
+/// Writes the first `len` bytes of the XOR of the files at `paths` to `output`, which has to be empty.
+/// Files shorter than that are XORed as if they were padded with zeros. They are XORed `buffer_size` bytes at a time.
+fn xor_files(
+    paths: &[PathBuf],
+    len: u64,
+    output: &mut fs::File,
+    buffer_size: usize,
+) -> Result<(), Error> {
+    // The XOR is built up in `output` itself, which starts out as zeros.
+    // Only one file is open at a time that way, however many parts there are, and none has to fit into memory.
+    output
+        .set_len(len)
+        .map_err(Error::io("Failed to write output."))?;
+
+    let mut parity = vec![0_u8; buffer_size];
+    let mut chunk = vec![0_u8; buffer_size];
+    for path in paths {
+        let mut file = fs::File::open(path).map_err(Error::io(format!(
+            "Failed to open {}.",
+            path.to_string_lossy()
+        )))?;
+        output
+            .seek(SeekFrom::Start(0))
+            .map_err(Error::io("Failed to write output."))?;
+
+        let mut remaining = len;
+        while remaining > 0 {
+            let window = remaining.min(buffer_size as u64) as usize;
+            let read = read_up_to(&mut file, &mut chunk[..window])?;
+            // The rest of a file shorter than `len` is XORed with zeros, which leaves the output as it is.
+            if read == 0 {
+                break;
+            }
+
+            output
+                .read_exact(&mut parity[..read])
+                .map_err(Error::io("Failed to read output."))?;
+            for (parity_byte, byte) in parity.iter_mut().zip(&chunk[..read]) {
+                *parity_byte ^= byte;
+            }
+            output
+                .seek(SeekFrom::Current(-(read as i64)))
+                .and_then(|_| output.write_all(&parity[..read]))
+                .map_err(Error::io("Failed to write output."))?;
+            remaining -= read as u64;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the parity file of the parts of the `manifest` in `split_folder` and returns what the manifest has to record about it.
+/// The parts have to be written completely already. They are read `buffer_size` bytes at a time.
+pub(crate) fn write(
+    split_folder: &Path,
+    manifest: &Manifest,
+    separator: &str,
+    buffer_size: usize,
+) -> Result<Parity, Error> {
+    let paths = manifest
+        .parts
+        .iter()
+        .map(|part| split_folder.join(&part.file_name))
+        .collect::<Vec<PathBuf>>();
+    let mut part_lens = Vec::with_capacity(paths.len());
+    for path in &paths {
+        part_lens.push(
+            fs::metadata(path)
+                .map_err(Error::io(format!(
+                    "Failed to read {}.",
+                    path.to_string_lossy()
+                )))?
+                .len(),
+        );
+    }
+
+    let file_name = file_name(&manifest.file_name, separator);
+    let path = split_folder.join(&file_name);
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .map_err(Error::io(format!(
+            "Failed to create output file {}.",
+            path.to_string_lossy()
+        )))?;
+    xor_files(
+        &paths,
+        part_lens.iter().copied().max().unwrap_or(0),
+        &mut file,
+        buffer_size,
+    )?;
+    file.sync_all()
+        .map_err(Error::io("Failed to write output."))?;
+
+    Ok(Parity {
+        file_name,
+        part_lens,
+    })
+}
+
+/// Rebuilds the missing part at `index`, starting at 0, of the `manifest` in `split_folder` from the `parity` file and all other parts.
+/// The parts of the `manifest` have to be sorted by their indices. They are read `buffer_size` bytes at a time.
+pub(crate) fn rebuild(
+    split_folder: &Path,
+    manifest: &Manifest,
+    parity: &Parity,
+    index: usize,
+    buffer_size: usize,
+) -> Result<(), Error> {
+    let part = &manifest.parts[index];
+    let parity_path = split_folder.join(&parity.file_name);
+    if !parity_path.is_file() {
+        return Err(Error(
+            format!(
+                "Part {} is missing and cannot be rebuilt because the parity file {} is missing too.",
+                part.file_name, parity.file_name
+            )
+            .into(),
+        ));
+    }
+    let len = *parity.part_lens.get(index).ok_or_else(|| {
+        Error(
+            format!(
+                "Part {} is missing and cannot be rebuilt because its length is not recorded.",
+                part.file_name
+            )
+            .into(),
+        )
+    })?;
+
+    let paths = std::iter::once(parity_path)
+        .chain(
+            manifest
+                .parts
+                .iter()
+                .filter(|other| other.index != part.index)
+                .map(|other| split_folder.join(&other.file_name)),
+        )
+        .collect::<Vec<PathBuf>>();
+
+    let path = split_folder.join(&part.file_name);
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(Error::io(format!(
+            "Failed to create output file {}.",
+            path.to_string_lossy()
+        )))?;
+
+    // A part that could not be rebuilt completely must not be mistaken for the original one.
+    let result = xor_files(&paths, len, &mut file, buffer_size).and_then(|()| {
+        file.sync_all()
+            .map_err(Error::io("Failed to write output."))
+    });
+    if result.is_err() {
+        drop(file);
+        let _ = fs::remove_file(&path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{self, TempDir};
+    use std::fs;
+
+    #[test]
+    fn rebuilds_every_part() {
+        let dir = TempDir::new();
+        let data = test_util::data(10_000);
+        let path = dir.write("file.bin", &data);
+        test_util::split(&path, &["--size", "3000", "--parity"]).unwrap();
+
+        let split_folder = dir.path().join("file.bin-split");
+        for number in 1..=4 {
+            let part = split_folder.join(format!("file.bin-split-{}", number));
+            let original_part = fs::read(&part).unwrap();
+            fs::remove_file(&part).unwrap();
+
+            let output = dir.path().join(format!("joined-{}", number));
+            let output_arg = output.to_string_lossy().into_owned();
+            test_util::join(
+                test_util::files_in(&split_folder),
+                &["--output", &output_arg],
+            )
+            .unwrap();
+
+            assert_eq!(fs::read(&output).unwrap(), data, "part {}", number);
+            assert_eq!(fs::read(&part).unwrap(), original_part, "part {}", number);
+        }
+    }
+
+    #[test]
+    fn does_not_rebuild_two_parts() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(10_000));
+        test_util::split(&path, &["--size", "3000", "--parity"]).unwrap();
+
+        let split_folder = dir.path().join("file.bin-split");
+        fs::remove_file(split_folder.join("file.bin-split-1")).unwrap();
+        fs::remove_file(split_folder.join("file.bin-split-3")).unwrap();
+
+        let output = dir.path().join("joined");
+        let output_arg = output.to_string_lossy().into_owned();
+        assert!(test_util::join(
+            test_util::files_in(&split_folder),
+            &["--output", &output_arg]
+        )
+        .is_err());
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn opens_one_part_at_a_time() {
+        // More parts than the usual limit of 1024 open files.
+        let dir = TempDir::new();
+        let data = test_util::data(3000);
+        let path = dir.write("file.bin", &data);
+        test_util::split(&path, &["--size", "1", "--parity", "--max-parts", "3000"]).unwrap();
+
+        let split_folder = dir.path().join("file.bin-split");
+        fs::remove_file(split_folder.join("file.bin-split-1234")).unwrap();
+
+        test_util::round_trip(&split_folder, &data);
+    }
+}
@@ -0,0 +1,223 @@
+//! The headers that parts start with if the file was split with `--headers`.
+//!
+//! A header says which part of which file a part is, so that a part can be identified on its own with `splitter info`,
+//! and so that parts can still be joined in the right order if their filenames were changed.
+//!
+//! The header is made of, in this order:
+//!
+//! - the [`MAGIC`] bytes,
+//! - the [`VERSION`] of the format as 1 byte,
+//! - the index of the part, starting at 1, as 8 bytes,
+//! - the number of parts as 8 bytes,
+//! - the length of the name of the file that was split as 2 bytes, followed by the name itself,
+//! - the length of the file that was split as 8 bytes.
+//!
+//! All numbers are little-endian. Compressed parts have the header at the start of their content.
+
+use crate::{
+    compression::Compression,
+    encoding::{Base64Reader, Encoding},
+    Error,
+};
+use std::{
+    borrow::Cow,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+/// The bytes every header starts with.
+pub const MAGIC: &[u8; 8] = b"SPLITPRT";
+
+/// The version of the format of the header.
+pub const VERSION: u8 = 1;
+
+/// The header of a part.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::part_header::PartHeader;
+/// let header = PartHeader {
+///     index: 2,
+///     part_count: 3,
+///     file_name: "bigfile.bin".to_string(),
+///     file_len: 300,
+/// };
+/// let bytes = header.to_bytes();
+/// assert_eq!(bytes.len() as u64, header.encoded_len());
+///
+/// let read = PartHeader::read(&mut &bytes[..])?.unwrap();
+/// assert_eq!((read.index, read.part_count), (2, 3));
+/// assert_eq!((read.file_name.as_str(), read.file_len), ("bigfile.bin", 300));
+///
+/// assert!(PartHeader::read(&mut &b"not a part"[..])?.is_none());
+/// # Ok::<(), splitter::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PartHeader {
+    /// The position of the part, starting at 1.
+    pub index: u64,
+    pub part_count: u64,
+    /// The name of the file that was split.
+    pub file_name: String,
+    /// The length of the file that was split.
+    pub file_len: u64,
+}
+
+impl PartHeader {
+    /// Returns the length of the header in bytes.
+    pub fn encoded_len(&self) -> u64 {
+        (MAGIC.len() + 1 + 8 + 8 + 2 + self.encoded_file_name().len() + 8) as u64
+    }
+
+    /// Returns the filename as it is written into the header.
+    /// Filenames longer than fit into the header are cut off.
+    fn encoded_file_name(&self) -> &str {
+        truncate(&self.file_name, u16::MAX as usize)
+    }
+
+    /// Returns the header as it is written at the start of the part.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let file_name = self.encoded_file_name();
+
+        let mut bytes = Vec::with_capacity(self.encoded_len() as usize);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&self.part_count.to_le_bytes());
+        bytes.extend_from_slice(&(file_name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(file_name.as_bytes());
+        bytes.extend_from_slice(&self.file_len.to_le_bytes());
+        bytes
+    }
+
+    /// Reads the header from the start of `reader`, if it starts with one.
+    /// If it does not, up to [`MAGIC`]`.len()` bytes of it were read anyway.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Option<PartHeader>, Error> {
+        let mut magic = [0_u8; MAGIC.len()];
+        if read_up_to(reader, &mut magic)? < magic.len() || &magic != MAGIC {
+            return Ok(None);
+        }
+
+        let invalid = || Error("The part header is invalid or cut off.".into());
+
+        let mut version = [0_u8; 1];
+        reader.read_exact(&mut version).map_err(|_| invalid())?;
+        if version[0] != VERSION {
+            return Err(Error(
+                format!(
+                    "The part header has version {}, which is not supported. Please update splitter.",
+                    version[0]
+                )
+                .into(),
+            ));
+        }
+
+        let index = read_u64(reader).map_err(|_| invalid())?;
+        let part_count = read_u64(reader).map_err(|_| invalid())?;
+        let mut file_name_len = [0_u8; 2];
+        reader
+            .read_exact(&mut file_name_len)
+            .map_err(|_| invalid())?;
+        let mut file_name = vec![0_u8; u16::from_le_bytes(file_name_len) as usize];
+        reader.read_exact(&mut file_name).map_err(|_| invalid())?;
+        let file_name = String::from_utf8(file_name).map_err(|_| invalid())?;
+        let file_len = read_u64(reader).map_err(|_| invalid())?;
+
+        if index == 0 || index > part_count {
+            return Err(invalid());
+        }
+
+        Ok(Some(PartHeader {
+            index,
+            part_count,
+            file_name,
+            file_len,
+        }))
+    }
+}
+
+/// Reads the header of the part at `path`, if it has one. Compressed parts are recognized by their content,
+/// so this works even if their filenames do not end in a [`Compression::extension`] anymore.
+/// Parts encoded as text are recognized by their [`Encoding::extension`].
+pub fn read_file(path: &Path) -> Result<Option<PartHeader>, Error> {
+    let encoding = path
+        .file_name()
+        .and_then(|file_name| Encoding::from_file_name(file_name.as_encoded_bytes()));
+    let open = || -> Result<Box<dyn Read>, Error> {
+        let file = fs::File::open(path).map_err(Error::io(format!(
+            "Failed to open {}.",
+            path.to_string_lossy()
+        )))?;
+        Ok(match encoding {
+            Some(Encoding::Base64) => Box::new(Base64Reader::new(file, path)),
+            None => Box::new(file),
+        })
+    };
+
+    let mut magic = [0_u8; 4];
+    let magic_len = read_up_to(&mut open()?, &mut magic)?;
+
+    let mut file = open()?;
+    match Compression::from_magic(&magic[..magic_len]) {
+        // A part that merely starts like compressed data is not a compressed part.
+        Some(compression) => Ok(compression
+            .decoder(file)
+            .ok()
+            .and_then(|mut decoder| PartHeader::read(&mut decoder).ok().flatten())),
+        None => PartHeader::read(&mut file),
+    }
+}
+
+/// Describes the part at `path` going by its header, for `splitter info`.
+pub fn info(path: &Path) -> Result<Cow<'static, str>, Error> {
+    let header = read_file(path)?.ok_or_else(|| {
+        Error(
+            format!(
+                "{} has no part header. Only parts of files that were split with --headers can be identified on their own.",
+                path.to_string_lossy()
+            )
+            .into(),
+        )
+    })?;
+
+    Ok(format!(
+        "{} is part {} of {} of {}, which is {} bytes long.",
+        path.to_string_lossy(),
+        header.index,
+        header.part_count,
+        header.file_name,
+        header.file_len
+    )
+    .into())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0_u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads from `reader` until `buffer` is full or the end is reached and returns how much was read.
+pub(crate) fn read_up_to<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, Error> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(len) => read += len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(read)
+}
+
+/// Cuts `text` off at at most `max_len` bytes, between characters.
+fn truncate(text: &str, max_len: usize) -> &str {
+    let mut len = text.len().min(max_len);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    &text[..len]
+}
@@ -0,0 +1,75 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+/// How much time has to pass at least between two updates so that the terminal is not flooded.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reports the progress of an operation as a percentage on stdout.
+///
+/// If stdout is not a terminal or not given at all, nothing is reported so that logs stay clean.
+pub struct Progress<'a, 'b> {
+    stdout: Option<&'a mut io::StdoutLock<'b>>,
+    label: &'static str,
+    total: u64,
+    done: u64,
+    last_update: Option<Instant>,
+}
+
+impl<'a, 'b> Progress<'a, 'b> {
+    pub fn new(
+        stdout: Option<&'a mut io::StdoutLock<'b>>,
+        label: &'static str,
+        total: u64,
+    ) -> Self {
+        Progress {
+            stdout: stdout.filter(|stdout| stdout.is_terminal()),
+            label,
+            total,
+            done: 0,
+            last_update: None,
+        }
+    }
+
+    /// Records that `len` more bytes were processed.
+    pub fn advance(&mut self, len: u64) {
+        self.done += len;
+
+        if self.stdout.is_none() {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(last_update) = self.last_update {
+            if now.duration_since(last_update) < UPDATE_INTERVAL {
+                return;
+            }
+        }
+        self.last_update = Some(now);
+
+        self.print();
+    }
+
+    /// Prints the final state and ends the line.
+    pub fn finish(&mut self) {
+        self.print();
+
+        if let Some(stdout) = &mut self.stdout {
+            // The progress is only cosmetic so failing to print it is not an error.
+            let _ = writeln!(stdout);
+        }
+    }
+
+    fn print(&mut self) {
+        // `total` can only be estimated for some operations, so `done` might exceed it.
+        let percentage = (self.done * 100)
+            .checked_div(self.total)
+            .map_or(100, |percentage| percentage.min(100));
+
+        if let Some(stdout) = &mut self.stdout {
+            let _ = write!(stdout, "\r{}: {}%", self.label, percentage);
+            let _ = stdout.flush();
+        }
+    }
+}
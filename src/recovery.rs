@@ -0,0 +1,524 @@
+//! The recovery parts of a split made with `--redundancy N`, from which up to N missing or corrupted parts can be rebuilt.
+//!
+//! The parts as they are stored and the recovery parts are the shards of a Reed-Solomon code over GF(2^8):
+//! any of them can be rebuilt from as many other intact shards as there are parts.
+//! Every shard is as long as the longest part, so shorter parts are padded with zeros for the code,
+//! and rebuilt parts are cut to the length recorded in the manifest.
+//!
+//! The code is only computed if splitter is built with the `redundancy` feature, which it is by default.
+//! There can be at most [`MAX_SHARDS`] parts and recovery parts together.
+
+use crate::{
+    checksum::Hasher,
+    manifest::{Coding, Manifest, Recovery, Shard},
+    part_header::read_up_to,
+    Error,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// How many parts and recovery parts a split can have together at most.
+pub const MAX_SHARDS: usize = 256;
+
+/// Returns the name of the recovery part with the `number`, starting at 1, of the split of the file called `file_name`,
+/// whose parts have `separator` before their trailing numbers.
+///
+/// ```
+/// assert_eq!(
+///     splitter::recovery::file_name("video.mp4", "-split-", 2),
+///     "video.mp4-split-recovery-2"
+/// );
+/// ```
+pub fn file_name(file_name: &str, separator: &str, number: usize) -> String {
+    format!("{}{}recovery-{}", file_name, separator, number)
+}
+
+/// Makes sure that `part_count` parts and `recovery_count` recovery parts are not more than the code can handle.
+pub(crate) fn check_shard_count(part_count: usize, recovery_count: usize) -> Result<(), Error> {
+    if part_count + recovery_count > MAX_SHARDS {
+        Err(Error(
+            format!(
+                "--redundancy can only be used with at most {} parts and recovery parts together, but there would be {} parts and {} recovery parts.",
+                MAX_SHARDS, part_count, recovery_count
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Computes the recovery parts from the parts.
+#[cfg(feature = "redundancy")]
+struct Codec(reed_solomon_erasure::galois_8::ReedSolomon);
+
+/// Without the `redundancy` feature, there is no code to compute, so this cannot be created.
+#[cfg(not(feature = "redundancy"))]
+enum Codec {}
+
+impl Codec {
+    #[cfg(feature = "redundancy")]
+    fn new(coding: Coding, data_shards: usize, recovery_shards: usize) -> Result<Codec, Error> {
+        match coding {
+            Coding::ReedSolomonGf8 => {
+                reed_solomon_erasure::galois_8::ReedSolomon::new(data_shards, recovery_shards)
+                    .map(Codec)
+                    .map_err(|_| {
+                        Error(
+                            format!(
+                        "Recovery parts cannot be computed for {} parts and {} recovery parts.",
+                        data_shards, recovery_shards
+                    )
+                            .into(),
+                        )
+                    })
+            }
+        }
+    }
+
+    #[cfg(not(feature = "redundancy"))]
+    fn new(coding: Coding, data_shards: usize, recovery_shards: usize) -> Result<Codec, Error> {
+        let _ = (coding, data_shards, recovery_shards);
+        Err(Error(
+            "splitter was built without the redundancy feature, so it cannot use recovery parts."
+                .into(),
+        ))
+    }
+
+    /// Computes the `recovery` shards from the `data` shards. All of them have to be equally long.
+    #[cfg(feature = "redundancy")]
+    fn encode(&self, data: &[&[u8]], recovery: &mut [&mut [u8]]) {
+        self.0
+            .encode_sep(data, recovery)
+            .expect("the shards are as many and as long as the code expects");
+    }
+
+    #[cfg(not(feature = "redundancy"))]
+    fn encode(&self, _: &[&[u8]], _: &mut [&mut [u8]]) {
+        match *self {}
+    }
+
+    /// Rebuilds the data shards that are not marked as present from the ones that are. All of them have to be equally long.
+    #[cfg(feature = "redundancy")]
+    fn reconstruct(&self, shards: &mut [(&mut [u8], bool)]) -> Result<(), Error> {
+        self.0
+            .reconstruct_data(shards)
+            .map_err(|_| Error("There are too few intact parts and recovery parts.".into()))
+    }
+
+    #[cfg(not(feature = "redundancy"))]
+    fn reconstruct(&self, _: &mut [(&mut [u8], bool)]) -> Result<(), Error> {
+        match *self {}
+    }
+}
+
+/// Opens the file at `path` for reading.
+fn open(path: &Path) -> Result<fs::File, Error> {
+    fs::File::open(path).map_err(Error::io(format!(
+        "Failed to open {}.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Creates the file at `path` for writing, replacing it if it exists.
+fn create(path: &Path) -> Result<fs::File, Error> {
+    fs::File::create(path).map_err(Error::io(format!(
+        "Failed to create output file {}.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Writes `recovery_count` recovery parts for the parts of the `manifest` in `split_folder`
+/// and returns what the manifest has to record about them. The parts have to be written completely already.
+/// Every shard is encoded `buffer_size` bytes at a time, in a buffer of its own.
+pub(crate) fn write(
+    split_folder: &Path,
+    manifest: &Manifest,
+    separator: &str,
+    recovery_count: usize,
+    buffer_size: usize,
+) -> Result<Recovery, Error> {
+    let data_shards = manifest.parts.len();
+    check_shard_count(data_shards, recovery_count)?;
+    let coding = Coding::ReedSolomonGf8;
+    let codec = Codec::new(coding, data_shards, recovery_count)?;
+
+    let mut shards = Vec::<Shard>::with_capacity(data_shards + recovery_count);
+    let mut data_files = Vec::with_capacity(data_shards);
+    for part in &manifest.parts {
+        let path = split_folder.join(&part.file_name);
+        let file = open(&path)?;
+        let len = file
+            .metadata()
+            .map_err(Error::io(format!(
+                "Failed to read {}.",
+                path.to_string_lossy()
+            )))?
+            .len();
+        data_files.push(file);
+        shards.push(Shard {
+            file_name: part.file_name.clone(),
+            len,
+            checksum: String::new(),
+        });
+    }
+    let shard_len = shards.iter().map(|shard| shard.len).max().unwrap_or(0);
+
+    let mut recovery_files = Vec::with_capacity(recovery_count);
+    for number in 1..=recovery_count {
+        let file_name = file_name(&manifest.file_name, separator, number);
+        recovery_files.push(create(&split_folder.join(&file_name))?);
+        shards.push(Shard {
+            file_name,
+            len: shard_len,
+            checksum: String::new(),
+        });
+    }
+
+    let mut hashers = shards
+        .iter()
+        .map(|_| manifest.checksum_algorithm.hasher())
+        .collect::<Vec<Box<dyn Hasher>>>();
+    let mut data = vec![vec![0_u8; buffer_size]; data_shards];
+    let mut recovery = vec![vec![0_u8; buffer_size]; recovery_count];
+
+    // All shards are gone through at once, one window at a time, so that none of them has to fit into memory.
+    let mut remaining = shard_len;
+    while remaining > 0 {
+        let window = remaining.min(buffer_size as u64) as usize;
+
+        for ((file, buffer), hasher) in data_files.iter_mut().zip(&mut data).zip(&mut hashers) {
+            let read = read_up_to(file, &mut buffer[..window])?;
+            hasher.update(&buffer[..read]);
+            buffer[read..window].fill(0);
+        }
+
+        codec.encode(
+            &data
+                .iter()
+                .map(|buffer| &buffer[..window])
+                .collect::<Vec<&[u8]>>(),
+            &mut recovery
+                .iter_mut()
+                .map(|buffer| &mut buffer[..window])
+                .collect::<Vec<&mut [u8]>>(),
+        );
+
+        for ((file, buffer), hasher) in recovery_files
+            .iter_mut()
+            .zip(&recovery)
+            .zip(&mut hashers[data_shards..])
+        {
+            file.write_all(&buffer[..window])
+                .map_err(Error::io("Failed to write output."))?;
+            hasher.update(&buffer[..window]);
+        }
+
+        remaining -= window as u64;
+    }
+
+    for file in recovery_files {
+        file.sync_all()
+            .map_err(Error::io("Failed to write output."))?;
+    }
+    for (shard, hasher) in shards.iter_mut().zip(hashers) {
+        shard.checksum = hasher.finalize();
+    }
+
+    Ok(Recovery {
+        coding,
+        data_shards,
+        recovery_shards: recovery_count,
+        shard_len,
+        shards,
+    })
+}
+
+/// What is wrong with a shard that has to be rebuilt.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Damage {
+    Missing,
+    Corrupted,
+}
+
+/// Returns what is wrong with the `shard` at `path`, if anything.
+/// Unless `verify` is given, only whether it is there and has the right length is checked, not its checksum.
+fn check_shard(
+    path: &Path,
+    shard: &Shard,
+    manifest: &Manifest,
+    verify: bool,
+    buffer_size: usize,
+) -> Result<Option<Damage>, Error> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Some(Damage::Missing)),
+        Err(err) => {
+            return Err(Error::io(format!(
+                "Failed to open {}.",
+                path.to_string_lossy()
+            ))(err))
+        }
+    };
+
+    let len = file
+        .metadata()
+        .map_err(Error::io(format!(
+            "Failed to read {}.",
+            path.to_string_lossy()
+        )))?
+        .len();
+    if len != shard.len {
+        return Ok(Some(Damage::Corrupted));
+    }
+
+    if verify {
+        let mut hasher = manifest.checksum_algorithm.hasher();
+        let mut buffer = vec![0_u8; buffer_size];
+        loop {
+            let read = read_up_to(&mut file, &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        if hasher.finalize() != shard.checksum {
+            return Ok(Some(Damage::Corrupted));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Joins `items` into a list like "a", "a and b" or "a, b and c".
+fn join_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [items @ .., last] => format!("{} and {}", items.join(", "), last),
+    }
+}
+
+/// Rebuilds the parts of the `manifest` in `split_folder` that are missing or corrupted from the `recovery` parts and the intact parts,
+/// and returns a description of what was rebuilt, if anything was.
+/// Unless `verify` is given, parts are only rebuilt if they are missing or do not have their length, without checking their checksums.
+/// The parts of the `manifest` have to be sorted by their indices. Every shard is read `buffer_size` bytes at a time.
+pub(crate) fn repair(
+    split_folder: &Path,
+    manifest: &Manifest,
+    recovery: &Recovery,
+    verify: bool,
+    buffer_size: usize,
+) -> Result<Option<String>, Error> {
+    let shard_count = recovery.data_shards + recovery.recovery_shards;
+    if recovery.shards.len() != shard_count
+        || recovery.data_shards != manifest.parts.len()
+        || manifest
+            .parts
+            .iter()
+            .zip(&recovery.shards)
+            .any(|(part, shard)| part.file_name != shard.file_name)
+    {
+        return Err(Error(
+            format!(
+                "The recovery parts in the {} do not match its parts.",
+                crate::manifest::FILE_NAME
+            )
+            .into(),
+        ));
+    }
+
+    let paths = recovery
+        .shards
+        .iter()
+        .map(|shard| split_folder.join(&shard.file_name))
+        .collect::<Vec<PathBuf>>();
+    let mut damages = Vec::<Option<Damage>>::with_capacity(shard_count);
+    for (path, shard) in paths.iter().zip(&recovery.shards) {
+        damages.push(check_shard(path, shard, manifest, verify, buffer_size)?);
+    }
+
+    let damaged_parts = (0..recovery.data_shards)
+        .filter(|&index| damages[index].is_some())
+        .collect::<Vec<usize>>();
+    if damaged_parts.is_empty() {
+        return Ok(None);
+    }
+
+    let damaged_shards = damages.iter().filter(|damage| damage.is_some()).count();
+    if damaged_shards > recovery.recovery_shards {
+        return Err(Error(
+            format!(
+                "{} parts and recovery parts are missing or corrupted: {}. Only up to {} of them can be rebuilt.",
+                damaged_shards,
+                join_list(
+                    &paths
+                        .iter()
+                        .zip(&damages)
+                        .filter(|(_, damage)| damage.is_some())
+                        .map(|(path, _)| path.to_string_lossy().into_owned())
+                        .collect::<Vec<String>>()
+                ),
+                recovery.recovery_shards
+            )
+            .into(),
+        ));
+    }
+
+    let codec = Codec::new(
+        recovery.coding,
+        recovery.data_shards,
+        recovery.recovery_shards,
+    )?;
+
+    // Any as many shards as there are parts are enough, so the intact parts are completed
+    // with only as many intact recovery parts as there are damaged parts.
+    let used_recovery_shards = (recovery.data_shards..shard_count)
+        .filter(|&index| damages[index].is_none())
+        .take(damaged_parts.len())
+        .collect::<Vec<usize>>();
+    let present = (0..shard_count)
+        .map(|index| {
+            (index < recovery.data_shards && damages[index].is_none())
+                || used_recovery_shards.contains(&index)
+        })
+        .collect::<Vec<bool>>();
+
+    let mut readers = Vec::<Option<fs::File>>::with_capacity(shard_count);
+    for (path, present) in paths.iter().zip(&present) {
+        readers.push(if *present { Some(open(path)?) } else { None });
+    }
+
+    // The parts are rebuilt next to the damaged ones and only replace them once they are known to be right.
+    let rebuilt_paths = damaged_parts
+        .iter()
+        .map(|&index| split_folder.join(format!("{}.partial", recovery.shards[index].file_name)))
+        .collect::<Vec<PathBuf>>();
+    let result = rebuild(
+        &codec,
+        recovery,
+        manifest,
+        &mut readers,
+        &present,
+        &damaged_parts,
+        &rebuilt_paths,
+        buffer_size,
+    );
+    if result.is_err() {
+        for path in &rebuilt_paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+    result?;
+
+    for (&index, rebuilt_path) in damaged_parts.iter().zip(&rebuilt_paths) {
+        fs::rename(rebuilt_path, &paths[index]).map_err(Error::io(format!(
+            "Failed to replace {}.",
+            paths[index].to_string_lossy()
+        )))?;
+    }
+
+    let parts = damaged_parts
+        .iter()
+        .map(|&index| {
+            format!(
+                "{} ({})",
+                manifest.parts[index].index,
+                match damages[index] {
+                    Some(Damage::Missing) => "missing",
+                    _ => "corrupted",
+                }
+            )
+        })
+        .collect::<Vec<String>>();
+    let used = used_recovery_shards
+        .iter()
+        .map(|&index| recovery.shards[index].file_name.clone())
+        .collect::<Vec<String>>();
+    Ok(Some(format!(
+        "{} {} {} rebuilt from the recovery {} {}.",
+        if parts.len() == 1 { "Part" } else { "Parts" },
+        join_list(&parts),
+        if parts.len() == 1 { "was" } else { "were" },
+        if used.len() == 1 { "part" } else { "parts" },
+        join_list(&used)
+    )))
+}
+
+/// Rebuilds the `damaged_parts` of the `recovery` shards into `rebuilt_paths` from the shards that are `present`,
+/// which are read with the `readers`, and checks them against their checksums.
+#[allow(clippy::too_many_arguments)]
+fn rebuild(
+    codec: &Codec,
+    recovery: &Recovery,
+    manifest: &Manifest,
+    readers: &mut [Option<fs::File>],
+    present: &[bool],
+    damaged_parts: &[usize],
+    rebuilt_paths: &[PathBuf],
+    buffer_size: usize,
+) -> Result<(), Error> {
+    let mut writers = Vec::with_capacity(damaged_parts.len());
+    for path in rebuilt_paths {
+        writers.push(create(path)?);
+    }
+    let mut hashers = damaged_parts
+        .iter()
+        .map(|_| manifest.checksum_algorithm.hasher())
+        .collect::<Vec<Box<dyn Hasher>>>();
+    let mut buffers = vec![vec![0_u8; buffer_size]; readers.len()];
+    let mut offset = 0;
+
+    while offset < recovery.shard_len {
+        let window = (recovery.shard_len - offset).min(buffer_size as u64) as usize;
+
+        for (reader, buffer) in readers.iter_mut().zip(&mut buffers) {
+            if let Some(reader) = reader {
+                let read = read_up_to(reader, &mut buffer[..window])?;
+                buffer[read..window].fill(0);
+            }
+        }
+
+        codec.reconstruct(
+            &mut buffers
+                .iter_mut()
+                .zip(present)
+                .map(|(buffer, present)| (&mut buffer[..window], *present))
+                .collect::<Vec<(&mut [u8], bool)>>(),
+        )?;
+
+        // The padding of shorter parts is not part of them.
+        for ((&index, writer), hasher) in damaged_parts.iter().zip(&mut writers).zip(&mut hashers) {
+            let len = recovery.shards[index]
+                .len
+                .saturating_sub(offset)
+                .min(window as u64) as usize;
+            writer
+                .write_all(&buffers[index][..len])
+                .map_err(Error::io("Failed to write output."))?;
+            hasher.update(&buffers[index][..len]);
+        }
+
+        offset += window as u64;
+    }
+
+    for ((&index, writer), hasher) in damaged_parts.iter().zip(writers).zip(hashers) {
+        writer
+            .sync_all()
+            .map_err(Error::io("Failed to write output."))?;
+        if hasher.finalize() != recovery.shards[index].checksum {
+            return Err(Error(
+                format!(
+                    "Part {} could not be rebuilt correctly. The recovery parts are probably corrupted as well.",
+                    recovery.shards[index].file_name
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
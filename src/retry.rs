@@ -0,0 +1,82 @@
+//! Retrying reads of parts that fail for reasons that may go away by themselves,
+//! such as a hiccup of the network when the parts are in a folder mounted over SMB or NFS.
+
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+/// How long to wait before the first retry. Every following retry waits twice as long as the one before.
+const FIRST_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Returns whether `err` may go away if the same operation is tried again.
+/// Errors that say something about the file itself, such as that it does not exist, do not.
+fn is_transient(err: &io::Error) -> bool {
+    !matches!(
+        err.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::Unsupported
+            | io::ErrorKind::OutOfMemory
+            // This is retried right away by whoever reads, without counting as a failure.
+            | io::ErrorKind::Interrupted
+    )
+}
+
+/// Runs `operation` until it succeeds, but at most `retries` more times after it fails with a transient error,
+/// and waits a little longer before every retry.
+/// `operation` is told whether it is a retry.
+fn retry<T, F: FnMut(bool) -> io::Result<T>>(retries: usize, mut operation: F) -> io::Result<T> {
+    let mut failures = 0;
+    loop {
+        match operation(failures > 0) {
+            Err(err) if failures < retries && is_transient(&err) => {
+                thread::sleep(FIRST_BACKOFF * 2_u32.saturating_pow(failures as u32));
+                failures += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Reads a file and retries reads that fail with a transient error from where the file was read up to.
+pub(crate) struct RetryReader {
+    file: fs::File,
+    /// How many bytes of the file were read successfully.
+    position: u64,
+    retries: usize,
+}
+
+impl RetryReader {
+    /// Opens the file at `path`, which is retried up to `retries` times as well.
+    pub(crate) fn open(path: &Path, retries: usize) -> io::Result<RetryReader> {
+        let file = retry(retries, |_| fs::File::open(path))?;
+        Ok(RetryReader {
+            file,
+            position: 0,
+            retries,
+        })
+    }
+}
+
+impl Read for RetryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let file = &mut self.file;
+        let position = self.position;
+        // A read that failed may have moved the file position anyway, so it is put back first.
+        let read = retry(self.retries, |retrying| {
+            if retrying {
+                file.seek(SeekFrom::Start(position))?;
+            }
+            file.read(buf)
+        })?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::Error;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Returns the name of the SFV file of the split of the file called `file_name`.
+pub fn file_name(file_name: &str) -> String {
+    format!("{}.sfv", file_name)
+}
+
+/// Writes the SFV file `path` which lists the `parts` as pairs of their filenames and CRC32 checksums.
+pub fn write(path: &Path, parts: &[(String, u32)]) -> Result<(), Error> {
+    let mut sfv = String::from("; Generated by splitter\n");
+    for (file_name, crc32) in parts {
+        sfv += &format!("{} {:08x}\n", file_name, crc32);
+    }
+
+    fs::write(path, sfv).map_err(Error::io(format!(
+        "Failed to write {}.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Reads the SFV file `path` and returns the filenames and CRC32 checksums it lists.
+///
+/// Lines starting with `;` are comments. The checksum is the last word of every other line,
+/// so filenames may contain spaces.
+pub fn read(path: &Path) -> Result<Vec<(String, u32)>, Error> {
+    let sfv = fs::read_to_string(path).map_err(Error::io(format!(
+        "Failed to read {}.",
+        path.to_string_lossy()
+    )))?;
+
+    sfv.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(|line| {
+            line.rsplit_once(' ')
+                .and_then(|(file_name, crc32)| {
+                    u32::from_str_radix(crc32, 16)
+                        .ok()
+                        .map(|crc32| (file_name.trim_end().to_string(), crc32))
+                })
+                .ok_or_else(|| {
+                    Error(
+                        format!("{} has an invalid line: {}", path.to_string_lossy(), line).into(),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Passes everything written to it on to `inner` and computes the CRC32 checksum of it on the way.
+pub struct Crc32Writer<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Crc32Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Crc32Writer {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    /// Returns the CRC32 checksum of everything that was written.
+    pub fn finalize(self) -> u32 {
+        self.hasher.finalize()
+    }
+}
+
+impl<W: Write> Write for Crc32Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
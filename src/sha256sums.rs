@@ -0,0 +1,84 @@
+use crate::Error;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// The name of the file listing the SHA-256 checksums of the parts in the format of `sha256sum`.
+pub const FILE_NAME: &str = "SHA256SUMS";
+
+/// Writes the file `path` which lists the `parts` as pairs of their filenames and SHA-256 checksums,
+/// so that they can be checked with `sha256sum -c`.
+pub fn write(path: &Path, parts: &[(String, String)]) -> Result<(), Error> {
+    let mut sums = String::new();
+    for (file_name, sha256) in parts {
+        sums += &format!("{}  {}\n", sha256, file_name);
+    }
+
+    fs::write(path, sums).map_err(Error::io(format!(
+        "Failed to write {}.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Reads the file `path` and returns the filenames and SHA-256 checksums it lists.
+///
+/// Both the text mode (`<hex>  <filename>`) and the binary mode (`<hex> *<filename>`) of `sha256sum` are accepted.
+pub fn read(path: &Path) -> Result<Vec<(String, String)>, Error> {
+    let sums = fs::read_to_string(path).map_err(Error::io(format!(
+        "Failed to read {}.",
+        path.to_string_lossy()
+    )))?;
+
+    sums.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split_once(' ')
+                .filter(|(sha256, file_name)| {
+                    sha256.len() == 64
+                        && sha256.bytes().all(|byte| byte.is_ascii_hexdigit())
+                        && (file_name.starts_with(' ') || file_name.starts_with('*'))
+                })
+                .map(|(sha256, file_name)| (file_name[1..].to_string(), sha256.to_lowercase()))
+                .ok_or_else(|| {
+                    Error(
+                        format!("{} has an invalid line: {}", path.to_string_lossy(), line).into(),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Passes everything written to it on to `inner` and computes the SHA-256 checksum of it on the way.
+pub struct Sha256Writer<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Sha256Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Sha256Writer {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Returns the SHA-256 checksum of everything that was written in hexadecimal.
+    pub fn finalize(self) -> String {
+        crate::to_hex(&self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for Sha256Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
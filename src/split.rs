@@ -1,132 +1,4117 @@
-use crate::Error;
+use crate::{
+    args::{OptionKind, Options},
+    checksum,
+    compression::Encoder,
+    crypt::{self, EncryptWriter, Key},
+    encoding::{Base64Writer, Encoding},
+    filter::FilterWriter,
+    free_space,
+    hashing::{Checksums, HashingWorker},
+    join, join_script,
+    manifest::{self, Manifest},
+    outcome::{Operation, Outcome},
+    parity,
+    part_header::{self, PartHeader},
+    progress::Progress,
+    recovery,
+    sfv::{self, Crc32Writer},
+    sha256sums::{self, Sha256Writer},
+    thread_writer::ThreadWriter,
+    zip, Error,
+};
 use parse_size::parse_size;
+use sha2::{Digest, Sha256};
 use std::{
     borrow::Cow,
+    ffi::OsStr,
     fs,
-    io::{self, BufRead, Read, Write},
-    path::PathBuf,
-    usize,
+    io::{self, BufRead, IsTerminal, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::SystemTime,
 };
 
-fn get_split_size(
-    stdin: &mut io::StdinLock,
-    stdout: &mut io::StdoutLock,
-    stderr: &mut io::StderrLock,
-) -> Result<u64, Error> {
-    fn parse_split_size(
-        stdin: &mut io::StdinLock,
-        input: &mut String,
-    ) -> Result<u64, &'static str> {
-        stdin.read_line(input).map_err(|_| "Failed to read input")?;
-
-        match parse_size(input.trim()) {
-            Ok(size) => Ok(size),
-            Err(err) => {
-                use parse_size::Error::*;
-
-                let err = match err {
-                    PosOverflow => "Size too big",
-                    Empty => "No input",
-                    _ => "Invalid input",
-                };
-                Err(err)
+/// The size of the working buffer the file's content is streamed through, unless another one is given with `--buffer-size`.
+/// This is the most that is held in memory at once, regardless of the file's length.
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Files at least this long are hashed on a thread of its own.
+/// For smaller files, the overhead of the thread is not worth it.
+const PARALLEL_THRESHOLD: u64 = 32 * 1024 * 1024;
+
+/// How many split folders of the same file can exist next to each other before splitting fails.
+const MAX_SPLIT_FOLDERS: usize = 100;
+
+/// How many parts a split may create without asking first, unless another limit is given with `--max-parts`.
+/// A tiny split size such as 1 byte would otherwise create millions of files.
+pub const DEFAULT_MAX_PARTS: usize = 10_000;
+
+/// The smallest split size that parts can be compressed to.
+/// Compressed data has a header and a trailer, so smaller parts would hardly hold any of the file.
+const MIN_COMPRESSED_SPLIT_SIZE: u64 = 128;
+
+/// How many bytes compressed data can have after the last flush, for the trailer that ends it.
+const COMPRESSED_TRAILER_LEN: u64 = 16;
+
+/// How many bytes of the free space of a medium are left free with `--fit`,
+/// as the filesystem needs some room of its own to store the part.
+const FIT_MARGIN: u64 = 1024 * 1024;
+
+/// The longest filename that most filesystems allow, in bytes.
+/// Windows counts UTF-16 code units instead, of which a filename never has more than it has bytes in UTF-8.
+pub const MAX_FILE_NAME_LEN: usize = 255;
+
+/// How many bytes the names of the split folder and the parts may add to the name of the file they are named after,
+/// such as `-split-100` or `-split-stripe-00001.zst.b64`, and `.` and `.partial` or `.replaced` for the hidden split folders.
+const NAME_SUFFIX_RESERVE: usize = 64;
+
+/// Returns the name the split folder and the parts of the file called `file_name` are named after.
+/// It is shortened with [`shorten_file_name`] if the names made from it would not fit into [`MAX_FILE_NAME_LEN`] bytes otherwise.
+/// The manifest records the full name then, so that joining restores it.
+pub(crate) fn get_base_name(file_name: &str) -> Cow<'_, str> {
+    shorten_file_name(file_name, MAX_FILE_NAME_LEN - NAME_SUFFIX_RESERVE)
+}
+
+/// Returns `file_name` cut down to at most `max_len` bytes, or as it is if it is short enough.
+/// The extension is kept, so it is the end of the name before it that is cut off.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split::shorten_file_name;
+/// let file_name = format!("{}.tar", "a".repeat(250));
+/// let shortened = shorten_file_name(&file_name, 200);
+/// assert_eq!(shortened.len(), 200);
+/// assert!(shortened.ends_with("aaa.tar"));
+///
+/// assert_eq!(shorten_file_name("backup.tar", 200), "backup.tar");
+/// // Characters are never cut in half.
+/// assert_eq!(shorten_file_name("ääää.txt", 9), "ää.txt");
+/// ```
+pub fn shorten_file_name(file_name: &str, max_len: usize) -> Cow<'_, str> {
+    if file_name.len() <= max_len {
+        return file_name.into();
+    }
+
+    // Anything after the last dot that is too long to be an extension is cut off like the rest of the name.
+    let extension = match file_name.rfind('.') {
+        Some(dot) if dot > 0 && file_name.len() - dot <= 16 && file_name.len() - dot < max_len => {
+            &file_name[dot..]
+        }
+        _ => "",
+    };
+    let mut stem_len = max_len - extension.len();
+    while !file_name.is_char_boundary(stem_len) {
+        stem_len -= 1;
+    }
+    format!("{}{}", &file_name[..stem_len], extension).into()
+}
+
+/// Returns the template the filenames of the parts are made from. See [`format_part_name`].
+/// If no template was given, it is `{name}<separator>{index}`, or `{name}<separator>stripe-{index}` with `--stripe`.
+fn get_name_template(options: &Options) -> Cow<'_, str> {
+    match (&options.name_template, options.format) {
+        (Some(name_template), _) => name_template.as_str().into(),
+        // Parts of a striped split must not be mistaken for consecutive parts when they are joined without the manifest.
+        (None, NameFormat::Numbered) if options.stripe.is_some() => format!(
+            "{{name}}{}{}-{{index}}",
+            options.separator(),
+            join::STRIPE_MARKER
+        )
+        .into(),
+        (None, NameFormat::Numbered) => format!("{{name}}{}{{index}}", options.separator()).into(),
+        (None, NameFormat::Gnu) => format!("{}{{index}}", join::GNU_PREFIX).into(),
+        (None, NameFormat::Extension) => "{name}.{index}".into(),
+    }
+}
+
+/// How the parts are numbered in their filenames, given with `--format`.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameFormat {
+    /// Zero-padded trailing numbers such as `bigfile.bin-split-01`.
+    #[default]
+    Numbered,
+    /// Alphabetic suffixes like the ones of GNU split, such as `xaa`, `xab` and so on,
+    /// so that the parts can be joined with `cat x* > bigfile.bin`. See [`gnu_suffix`].
+    Gnu,
+    /// Numbers with at least 3 digits as extensions, such as `bigfile.bin.001`,
+    /// like the ones of HJSplit and many other tools on Windows. This is given as `--format 001`.
+    Extension,
+}
+
+impl NameFormat {
+    /// Returns the format called `name`, ignoring case.
+    pub fn parse(name: &str) -> Result<NameFormat, Error> {
+        if name.eq_ignore_ascii_case("numbered") {
+            Ok(NameFormat::Numbered)
+        } else if name.eq_ignore_ascii_case("gnu") {
+            Ok(NameFormat::Gnu)
+        } else if name == "001" {
+            Ok(NameFormat::Extension)
+        } else {
+            Err(Error(
+                format!(
+                    "Unknown format: {}. Supported formats are: numbered, gnu, 001.",
+                    name
+                )
+                .into(),
+            ))
+        }
+    }
+}
+
+/// Returns the alphabetic suffix of part `number`, starting at 1, with `len` letters, the way GNU split names its parts.
+/// `aa` is part 1, `az` is part 26, `ba` is part 27 and so on.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split::{gnu_suffix, gnu_suffix_len};
+/// assert_eq!(gnu_suffix(1, 2), "aa");
+/// assert_eq!(gnu_suffix(26, 2), "az");
+/// assert_eq!(gnu_suffix(27, 2), "ba");
+/// assert_eq!(gnu_suffix(676, 2), "zz");
+///
+/// // Beyond 676 parts, the suffixes of all parts get longer so that they still sort in order.
+/// assert_eq!(gnu_suffix_len(676), 2);
+/// assert_eq!(gnu_suffix_len(677), 3);
+/// assert_eq!(gnu_suffix(676, 3), "azz");
+/// assert_eq!(gnu_suffix(677, 3), "baa");
+/// ```
+pub fn gnu_suffix(number: usize, len: usize) -> String {
+    let mut rest = number - 1;
+    let mut suffix = vec![b'a'; len];
+    for letter in suffix.iter_mut().rev() {
+        *letter = b'a' + (rest % 26) as u8;
+        rest /= 26;
+    }
+    // The suffix only consists of ASCII letters.
+    String::from_utf8(suffix).unwrap()
+}
+
+/// Returns how many letters the suffixes of `part_count` parts need at least so that every part has its own,
+/// which is at least 2 like with GNU split.
+pub fn gnu_suffix_len(part_count: usize) -> usize {
+    min_gnu_suffix_len(part_count).max(2)
+}
+
+/// Returns how many letters the suffixes of `part_count` parts need so that every part has its own.
+fn min_gnu_suffix_len(part_count: usize) -> usize {
+    let mut len = 1;
+    let mut count = 26_usize;
+    while count < part_count {
+        len += 1;
+        count = count.saturating_mul(26);
+    }
+    len
+}
+
+/// Makes sure that the parts named with `template` can be told apart and stay in the split folder.
+fn check_name_template(template: &str) -> Result<(), Error> {
+    if !template.contains("{index}") {
+        Err(Error(
+            "The name template must contain the {index} placeholder.".into(),
+        ))
+    } else if template.contains('/') || template.contains('\\') {
+        Err(Error(
+            "The name template must not contain path separators.".into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Makes the filename of a part from `template` by replacing `{name}` with the original filename,
+/// `{stem}` and `{ext}` with the original filename without its extension and its extension without the dot,
+/// `{index}` with the part's number as it is written, such as zero-padded, and `{total}` with the number of parts.
+///
+/// # Examples
+///
+/// ```ignore
+/// let file_name = format_part_name("{name}.part{index}of{total}", "foo.bin", "03", 12);
+///
+/// assert_eq!(file_name, "foo.bin.part03of12");
+///
+/// let file_name = format_part_name("{stem}_{index}of{total}.{ext}", "disc.bin", "1", 5);
+///
+/// assert_eq!(file_name, "disc_1of5.bin");
+/// ```
+fn format_part_name(template: &str, name: &str, index: &str, total: usize) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(OsStr::to_str).unwrap_or(name);
+    let ext = path.extension().and_then(OsStr::to_str).unwrap_or("");
+
+    template
+        .replace("{name}", name)
+        .replace("{stem}", stem)
+        .replace("{ext}", ext)
+        .replace("{index}", index)
+        .replace("{total}", &total.to_string())
+}
+
+/// A split size for a common target that can be given by name instead of a size, with `--preset` or as the split size.
+pub struct Preset {
+    pub name: &'static str,
+    pub split_size: u64,
+    /// What the parts are meant for, for when the preset is offered in a dialog.
+    pub description: &'static str,
+}
+
+/// All presets.
+pub const PRESETS: &[Preset] = &[
+    // The biggest file FAT32 can store is one byte short of 4 GiB.
+    Preset {
+        name: "fat32",
+        split_size: 4 * 1024 * 1024 * 1024 - 1,
+        description: "a FAT32 drive",
+    },
+    // A single-layer DVD holds about 4.38 GiB.
+    Preset {
+        name: "dvd",
+        split_size: 4_700_372_992,
+        description: "a DVD",
+    },
+    Preset {
+        name: "cd",
+        split_size: 700 * 1024 * 1024,
+        description: "a CD",
+    },
+    // Many email providers limit attachments to 25 MB.
+    Preset {
+        name: "email",
+        split_size: 25 * 1000 * 1000,
+        description: "email attachments",
+    },
+];
+
+/// Returns the preset called `name`, ignoring case.
+///
+/// ```
+/// # use splitter::split::get_preset;
+/// assert_eq!(get_preset("CD").map(|preset| preset.split_size), Some(734_003_200));
+/// assert_eq!(get_preset("email").map(|preset| preset.split_size), Some(25_000_000));
+/// assert!(get_preset("floppy").is_none());
+/// ```
+pub fn get_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+/// Returns the names of all presets, separated by commas.
+pub fn preset_names() -> String {
+    PRESETS
+        .iter()
+        .map(|preset| preset.name)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// Returns the split size that divides `file_len` into `part_count` parts, which is how long the longest of them is.
+fn split_size_for_parts(file_len: u64, part_count: u64) -> Result<u64, &'static str> {
+    if part_count == 0 {
+        Err("The number of parts must be at least 1")
+    } else if part_count > file_len {
+        Err("The file does not have enough bytes for that many parts")
+    } else {
+        // Rounding up alone would leave too little for the last parts, such as with 9 bytes and 4 parts,
+        // which is why `balanced_parts` spreads the remainder over the first parts instead.
+        Ok(file_len.div_ceil(part_count))
+    }
+}
+
+/// Returns the number of parts that `input` asks for if it is one like `/4`.
+fn parse_part_count(input: &str) -> Option<u64> {
+    input.trim().strip_prefix('/')?.trim().parse().ok()
+}
+
+/// Makes sure that a file of `file_len` bytes can be split into parts of `split_size` bytes.
+/// A file of exactly the split size makes a single part, just like a split size of `100%`.
+/// That is still a valid split, such as for a file that only has to be below an upload limit but should come with its manifest.
+/// An empty file and a file shorter than the split size cannot be split.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split::check_split_size;
+/// assert!(check_split_size(0, 100).is_err());
+/// assert!(check_split_size(1, 100).is_err());
+/// assert!(check_split_size(99, 100).is_err());
+/// assert!(check_split_size(100, 100).is_ok());
+/// assert!(check_split_size(101, 100).is_ok());
+/// assert!(check_split_size(1, 1).is_ok());
+/// ```
+pub fn check_split_size(file_len: u64, split_size: u64) -> Result<(), Error> {
+    if file_len == 0 {
+        Err(Error("The file is empty. Nothing to split.".into()))
+    } else if file_len < split_size {
+        Err(Error(
+            format!(
+                "The file is only {} bytes long, which is less than the split size of {} bytes. Nothing to split.",
+                file_len, split_size
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses a split size such as `100MB` or `1GiB`, a percentage of `file_len` such as `25%`,
+/// a number of parts such as `/4` or the name of a preset such as `fat32`.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split::parse_split_size;
+/// assert_eq!(parse_split_size("10MB", 1000), Ok(10_000_000));
+/// assert_eq!(parse_split_size("1KiB", 1000), Ok(1024));
+/// assert_eq!(parse_split_size("/4", 1000), Ok(250));
+/// assert_eq!(parse_split_size("25%", 1000), Ok(250));
+/// assert_eq!(parse_split_size("100%", 1000), Ok(1000));
+/// assert!(parse_split_size("0%", 1000).is_err());
+/// assert!(parse_split_size("101%", 1000).is_err());
+/// // A split size of 0 would never fill a part.
+/// assert!(parse_split_size("0", 1000).is_err());
+/// assert!(parse_split_size("0 MB", 1000).is_err());
+/// assert!(parse_split_size("big", 1000).is_err());
+/// assert!(parse_split_size("/four", 1000).is_err());
+/// assert!(parse_split_size("", 1000).is_err());
+/// ```
+pub fn parse_split_size(input: &str, file_len: u64) -> Result<u64, &'static str> {
+    match parse_len(input, file_len)? {
+        0 => Err("Size must be greater than zero"),
+        split_size => Ok(split_size),
+    }
+}
+
+/// Parses a length like [`parse_split_size`] does, except that it can be 0, which is where `--extract` can start.
+fn parse_len(input: &str, file_len: u64) -> Result<u64, &'static str> {
+    let input = input.trim();
+
+    if let Some(preset) = get_preset(input) {
+        return Ok(preset.split_size);
+    }
+
+    if let Some(part_count) = input.strip_prefix('/') {
+        let part_count = part_count
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| "Invalid number of parts")?;
+
+        return split_size_for_parts(file_len, part_count);
+    }
+
+    if let Some(percentage) = input.strip_suffix('%') {
+        let percentage = percentage
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| "Invalid percentage")?;
+
+        // 100% is the whole file in one part, which is still a valid split.
+        if !(percentage > 0.0 && percentage <= 100.0) {
+            return Err("The percentage must be above 0% and at most 100%");
+        }
+
+        // Rounding up makes sure that a percentage never results in more parts than it implies.
+        let split_size = (file_len as f64 * percentage / 100.0).ceil() as u64;
+
+        return if split_size == 0 {
+            Err("The percentage is too small for this file")
+        } else {
+            Ok(split_size)
+        };
+    }
+
+    match parse_size(input) {
+        Ok(size) => Ok(size),
+        Err(err) => {
+            use parse_size::Error::*;
+
+            let err = match err {
+                PosOverflow => "Size too big",
+                Empty => "No input",
+                _ => "Invalid input",
+            };
+            Err(err)
+        }
+    }
+}
+
+/// Returns what to tell the user when asking for a split size.
+pub fn split_size_help() -> String {
+    format!(
+        "Enter a size like 100MB, a percentage like 25%, a number of parts like /4 or one of these presets: {}.",
+        preset_names()
+    )
+}
+
+/// Asks for the split size of a file of `file_len` bytes.
+/// The number of parts is returned with it if that is what was given, such as with `/4`.
+fn get_split_size(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    stderr: &mut io::StderrLock,
+    file_len: u64,
+) -> Result<(u64, Option<u64>), Error> {
+    let mut input = String::new();
+
+    writeln!(stdout, "{}", split_size_help())?;
+
+    loop {
+        write!(stdout, "Split size:  ")?;
+        stdout.flush()?;
+
+        let read = stdin
+            .read_line(&mut input)
+            .map_err(Error::io("Failed to read input."))?;
+
+        if read == 0 {
+            return Err(Error("No split size was given.".into()));
+        }
+
+        match parse_split_size(&input, file_len) {
+            Ok(split_size) => {
+                let input = input.trim();
+                if input.ends_with('%') || input.starts_with('/') {
+                    writeln!(stdout, "That is {} bytes.", split_size)?;
+                }
+                break Ok((split_size, parse_part_count(input)));
+            }
+            Err(err) => writeln!(stderr, "{}. Please try again.", err)?,
+        };
+
+        input.clear();
+    }
+}
+
+/// Makes sure that the file at `path` is not a part of a split, going by its name and its part header, unless `--force-split` is given.
+/// Splitting a part again is hardly ever wanted and makes a split folder within a split folder that is hard to join.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::{args::Options, split::check_not_a_part};
+/// # use std::{ffi::OsString, fs, iter};
+/// let dir = std::env::temp_dir().join("splitter-check-not-a-part-example");
+/// # let _ = fs::remove_dir_all(&dir);
+/// fs::create_dir_all(&dir).unwrap();
+/// let part = dir.join("backup.tar-split-3");
+/// fs::write(&part, b"part").unwrap();
+///
+/// let options = Options::parse(iter::empty()).unwrap();
+/// assert!(check_not_a_part(&part, &options).is_err());
+/// assert!(check_not_a_part(&dir.join("backup.tar"), &options).is_ok());
+///
+/// let options = Options::parse(iter::once(OsString::from("--force-split"))).unwrap();
+/// assert!(check_not_a_part(&part, &options).is_ok());
+/// # fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn check_not_a_part(path: &Path, options: &Options) -> Result<(), Error> {
+    if options.force_split {
+        return Ok(());
+    }
+
+    // A file that cannot be read is reported once it is opened to be split.
+    let has_header = path.is_file() && matches!(part_header::read_file(path), Ok(Some(_)));
+    if join::is_named_part(path, options.separator()) || has_header {
+        Err(Error(
+            format!(
+                "{} looks like a part of a split. To join it, select its split folder instead, or use --force-split to split it anyway.",
+                path.to_string_lossy()
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// The length and the modification time of the file being split, to notice if another process modifies it in the meantime.
+/// Parts of a file that changed while it was split would not fit together, so splitting is aborted then, unless `--allow-changes` is given.
+struct SourceState {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl SourceState {
+    fn new(path: &Path, metadata: &fs::Metadata) -> SourceState {
+        SourceState {
+            path: path.to_path_buf(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Returns an error if the file's length or modification time is not what it was when this was created.
+    fn check(&self) -> Result<(), Error> {
+        let metadata = fs::metadata(&self.path).map_err(Error::io(format!(
+            "Failed to read the metadata of {}.",
+            self.path.to_string_lossy()
+        )))?;
+        if metadata.len() == self.len && metadata.modified().ok() == self.modified {
+            Ok(())
+        } else {
+            Err(Error(
+                format!(
+                    "{} was modified while it was split, so the parts would not fit together. Use --allow-changes to split it anyway.",
+                    self.path.to_string_lossy()
+                )
+                .into(),
+            ))
+        }
+    }
+}
+
+pub fn split(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    stderr: &mut io::StderrLock,
+    path_buf: PathBuf,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    check_not_a_part(&path_buf, options)?;
+
+    // A passphrase on its own would leave the parts unencrypted without anyone noticing.
+    if options.passphrase.is_some() && !options.encrypt {
+        return Err(Error(
+            "--passphrase can only be used with --encrypt when splitting.".into(),
+        ));
+    }
+
+    let file = fs::File::open(&path_buf).map_err(Error::io("Failed to open file."))?;
+
+    let metadata = file.metadata()?;
+    let file_len = metadata.len();
+    let source = (!options.allow_changes).then(|| SourceState::new(&path_buf, &metadata));
+
+    if !options.quiet {
+        writeln!(stdout, "File length: {}", file_len)?;
+    }
+
+    // There is no point in asking for a split size for an empty file.
+    if file_len == 0 {
+        return Err(Error("The file is empty. Nothing to split.".into()));
+    }
+
+    if let Some(fit) = &options.fit {
+        return split_fit(
+            stdin,
+            stdout,
+            &path_buf,
+            file,
+            file_len,
+            fit,
+            source.as_ref(),
+            options,
+        );
+    }
+
+    // A number of parts is kept along with the split size so that the parts can be balanced.
+    let (split_size, part_count) = match (&options.split_size, options.part_count) {
+        // Split by lines, there is no split size.
+        _ if options.lines_per_part.is_some() => {
+            options.check_conflicts(
+                OptionKind::LinesPerPart,
+                &[
+                    OptionKind::Size,
+                    OptionKind::Parts,
+                    OptionKind::Halve,
+                    OptionKind::Lines,
+                    OptionKind::Text,
+                    OptionKind::Csv,
+                    OptionKind::Delimiter,
+                    OptionKind::Pad,
+                ],
+                None,
+            )?;
+            (None, None)
+        }
+        (Some(_), Some(_)) => {
+            return Err(Error("--size and --parts cannot be used together.".into()))
+        }
+        (Some(split_size), None) => (
+            Some(
+                parse_split_size(split_size, file_len)
+                    .map_err(|err| Error(format!("{}: {}.", err, split_size).into()))?,
+            ),
+            parse_part_count(split_size),
+        ),
+        (None, Some(part_count)) => (
+            Some(
+                split_size_for_parts(file_len, part_count as u64)
+                    .map_err(|err| Error(format!("{}: {}.", err, part_count).into()))?,
+            ),
+            Some(part_count as u64),
+        ),
+        (None, None) => {
+            let (split_size, part_count) = get_split_size(stdin, stdout, stderr, file_len)?;
+            (Some(split_size), part_count)
+        }
+    };
+
+    // The parts take up about as much space as the file, and it is better to know that it does not fit before writing them.
+    if !options.no_space_check {
+        free_space::check(&get_parent_folder(&path_buf, options), file_len)?;
+    }
+
+    if options.zip_volumes {
+        return match split_size {
+            Some(split_size) => split_zip_volumes(
+                stdin, stdout, &path_buf, file, file_len, split_size, options,
+            ),
+            None => Err(Error(
+                "--zip-volumes cannot be used with --lines-per-part.".into(),
+            )),
+        };
+    }
+
+    if let Some(split_size) = split_size.filter(|_| is_streamed(options)) {
+        return split_streamed(
+            stdin,
+            stdout,
+            path_buf,
+            file,
+            file_len,
+            split_size,
+            source.as_ref(),
+            options,
+        );
+    }
+
+    let plan = plan_split(&path_buf, file_len, split_size, part_count, options)?;
+    if let Some(recovery_count) = options.redundancy {
+        recovery::check_shard_count(plan.parts.len(), recovery_count)?;
+    }
+
+    // With `--jsonl`, records that do not fit into a part are not cut but become parts of their own.
+    let warning = match split_size {
+        Some(split_size) if options.jsonl => {
+            let oversized_parts = plan
+                .file_names
+                .iter()
+                .zip(&plan.parts)
+                .filter(|(_, part)| **part > split_size)
+                .map(|(file_name, _)| file_name.as_str())
+                .collect::<Vec<&str>>();
+
+            if oversized_parts.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n\nWarning: some records are longer than the split size on their own, so they became parts of their own: {}",
+                    oversized_parts.join(", ")
+                )
+            }
+        }
+        _ => String::new(),
+    };
+
+    let max_parts = options.max_parts.unwrap_or(DEFAULT_MAX_PARTS);
+    if !options.dry_run && plan.parts.len() > max_parts {
+        confirm_part_count(stdin, stdout, plan.parts.len(), max_parts)?;
+    }
+
+    if options.dry_run {
+        if !options.quiet {
+            for (file_name, part) in plan.file_names.iter().zip(plan.part_lens()) {
+                writeln!(stdout, "{}: {} bytes", file_name, part)?;
+            }
+        }
+
+        return Ok(Outcome {
+            operation: Operation::Split,
+            input: Some(path_buf),
+            output: Some(plan.split_folder.clone()),
+            part_count: Some(plan.parts.len()),
+            file_len,
+            part_lens: Some(plan.part_lens()),
+            dry_run: true,
+            message: format!(
+                "Dry run. {} parts would be created in {}.{}",
+                plan.parts.len(),
+                plan.split_folder.to_string_lossy(),
+                warning
+            )
+            .into(),
+        });
+    }
+
+    // The passphrase is asked for last so that nothing else can fail after it was typed in.
+    let key = if options.encrypt {
+        Some(Key::generate(&crypt::ask_passphrase(
+            true,
+            options.passphrase.as_deref(),
+        )?)?)
+    } else {
+        None
+    };
+
+    let report = write_split(
+        options.info_stdout(stdout),
+        file,
+        file_len,
+        plan,
+        key.as_ref(),
+        source.as_ref(),
+        options,
+    )?;
+    let description = describe_parts(&report.manifest) + &warning;
+
+    finish_split(
+        stdout,
+        &path_buf,
+        &report,
+        description,
+        key.as_ref(),
+        options,
+    )
+}
+
+/// Splits the file at `path_buf` with [`write_streamed_split`] so that its compressed parts are at most `split_size` bytes long.
+/// How many parts there are is only known once the file is compressed.
+#[allow(clippy::too_many_arguments)]
+fn split_streamed(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    path_buf: PathBuf,
+    file: fs::File,
+    file_len: u64,
+    split_size: u64,
+    source: Option<&SourceState>,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    check_split_size(file_len, split_size)?;
+    check_streamed_split_size(split_size, options)?;
+
+    if options.dry_run {
+        let split_folder = if options.force {
+            get_split_folder(&path_buf, options)?
+        } else {
+            find_free_split_folder(
+                &get_parent_folder(&path_buf, options),
+                &get_base_name(&crate::get_file_name(&path_buf)?),
+            )?
+        };
+
+        let message = format!(
+            "Dry run. Parts of at most {} bytes each after compression would be created in {}. How many there are is only known once the file is compressed.",
+            split_size,
+            split_folder.to_string_lossy()
+        );
+        return Ok(Outcome {
+            operation: Operation::Split,
+            input: Some(path_buf),
+            output: Some(split_folder),
+            part_count: None,
+            file_len,
+            part_lens: None,
+            dry_run: true,
+            message: message.into(),
+        });
+    }
+
+    // Compression rarely makes data much longer, so there are hardly ever more parts than without it.
+    let estimated_part_count = fixed_chunks(file_len, split_size).len();
+    if let Some(recovery_count) = options.redundancy {
+        recovery::check_shard_count(estimated_part_count, recovery_count)?;
+    }
+    let max_parts = options.max_parts.unwrap_or(DEFAULT_MAX_PARTS);
+    if estimated_part_count > max_parts {
+        confirm_part_count(stdin, stdout, estimated_part_count, max_parts)?;
+    }
+
+    let file_metadata = file.metadata().ok();
+    let mut reader = io::BufReader::with_capacity(options.buffer_size(), file);
+    let mut progress = Progress::new(options.info_stdout(stdout), "Splitting", file_len);
+    let report = write_streamed_split(
+        &mut reader,
+        &path_buf,
+        split_size,
+        None,
+        file_metadata.as_ref(),
+        source,
+        &mut progress,
+        options,
+    )?;
+    if report.manifest.file_len < file_len {
+        return Err(Error(
+            "The file ended unexpectedly. It might have been modified while splitting.".into(),
+        ));
+    }
+
+    let description = describe_streamed_parts(&report.manifest, split_size);
+    finish_split(stdout, &path_buf, &report, description, None, options)
+}
+
+/// Splits the file at `path` onto removable media mounted at the folder `fit_dir`, for `--fit`.
+/// Every part fills what is free on the medium, except for [`FIT_MARGIN`], but is at most the split size if one is given.
+/// After every part but the last one, the user is asked to swap the medium.
+/// Before that, the file is checked against the `source` it was opened as, if one is given, as it is for any other split.
+///
+/// The split folder is created on every medium. As the lengths of the parts are only known at the end,
+/// the manifest is written to the last medium.
+#[allow(clippy::too_many_arguments)]
+fn split_fit(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    path: &Path,
+    mut file: fs::File,
+    file_len: u64,
+    fit_dir: &Path,
+    source: Option<&SourceState>,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    // Every part is written to the medium right away, so anything that needs all parts at once or sizes them otherwise is out.
+    options.check_conflicts(
+        OptionKind::Fit,
+        &[
+            OptionKind::Parts,
+            OptionKind::Halve,
+            OptionKind::Lines,
+            OptionKind::LinesPerPart,
+            OptionKind::Delimiter,
+            OptionKind::Text,
+            OptionKind::Jsonl,
+            OptionKind::Csv,
+            OptionKind::Pad,
+            OptionKind::Stripe,
+            OptionKind::Headers,
+            OptionKind::Filter,
+            OptionKind::Compress,
+            OptionKind::Encrypt,
+            OptionKind::Base64,
+            OptionKind::ZipVolumes,
+            OptionKind::Parity,
+            OptionKind::Redundancy,
+            OptionKind::OutputDir,
+            OptionKind::NoFolder,
+            OptionKind::Resume,
+            OptionKind::Verify,
+            OptionKind::RemoveSource,
+            OptionKind::DryRun,
+            OptionKind::FormatGnu,
+        ],
+        None,
+    )?;
+
+    let name_template = get_name_template(options);
+    check_name_template(&name_template)?;
+    if name_template.contains("{total}") {
+        return Err(Error(
+            "--fit cannot be used with {total} in the name template because the number of parts is only known at the end.".into(),
+        ));
+    }
+
+    if file_len == 0 {
+        return Err(Error("The file is empty. Nothing to split.".into()));
+    }
+    let max_part_len = match &options.split_size {
+        Some(split_size) => parse_split_size(split_size, file_len)
+            .map_err(|err| Error(format!("{}: {}.", err, split_size).into()))?,
+        None => u64::MAX,
+    };
+
+    let original_file_name = crate::get_file_name(path)?.into_owned();
+    let file_name = get_base_name(&original_file_name).into_owned();
+    let split_folder = if options.force {
+        fit_dir.join(format!("{}-split", file_name))
+    } else {
+        find_free_split_folder(fit_dir, &file_name)?
+    };
+
+    let mut open_options = fs::OpenOptions::new();
+    if options.force {
+        open_options.write(true).create(true).truncate(true);
+    } else {
+        open_options.write(true).create_new(true);
+    }
+
+    let file_metadata = file.metadata().ok();
+    let mut buffer = vec![0_u8; options.buffer_size()];
+    let mut hashing = HashingWorker::new(options.checksum, false);
+    let mut parts = Vec::<manifest::Part>::new();
+    let mut sfv_parts = Vec::<(String, u32)>::new();
+    let mut remaining = file_len;
+    while remaining > 0 {
+        // Every medium gets a split folder of its own.
+        fs::create_dir_all(&split_folder).map_err(Error::io(format!(
+            "Failed to create folder {}.",
+            split_folder.to_string_lossy()
+        )))?;
+
+        let available = free_space::available(&split_folder).map_err(Error::io(format!(
+            "Failed to find out how much space is free in {}.",
+            fit_dir.to_string_lossy()
+        )))?;
+        let part_len = remaining
+            .min(max_part_len)
+            .min(available.saturating_sub(FIT_MARGIN));
+        if part_len == 0 {
+            ask_for_medium(
+                stdin,
+                stdout,
+                &format!(
+                    "There are only {} bytes free in {}. Please insert a medium with more free space.",
+                    available,
+                    fit_dir.to_string_lossy()
+                ),
+            )?;
+            continue;
+        }
+
+        let index = parts.len() + 1;
+        // The number of parts is not known yet, so the trailing numbers are only zero-padded with --suffix-length.
+        let part_file_name = part_file_names(&file_name, index, &name_template, options)?
+            .pop()
+            .unwrap_or_default();
+        let output_path = split_folder.join(&part_file_name);
+        let mut output_file = open_options.open(&output_path).map_err(Error::io(format!(
+            "Failed to create output file {}.",
+            output_path.to_string_lossy()
+        )))?;
+        let mut output = Crc32Writer::new(&mut output_file);
+
+        hashing.start_part(options.sha256sums);
+        let mut progress = Progress::new(options.info_stdout(stdout), "Splitting", part_len);
+        copy_part(&mut file, &mut output, part_len, &mut buffer, |chunk| {
+            hashing.update(chunk);
+            progress.advance(chunk.len() as u64);
+        })?;
+        progress.finish();
+        sfv_parts.push((part_file_name.clone(), output.finalize()));
+
+        // The medium is about to be removed, so the part has to be on it rather than in a cache.
+        output_file
+            .sync_all()
+            .map_err(Error::io("Failed to write output."))?;
+
+        // This is checked before the medium is swapped, so that nobody swaps media for parts that would not fit together.
+        if let Some(source) = source {
+            source.check()?;
+        }
+
+        parts.push(manifest::Part {
+            index,
+            file_name: part_file_name,
+            len: part_len,
+            checksum: None,
+            nonce: None,
+        });
+        remaining -= part_len;
+
+        if remaining > 0 {
+            ask_for_medium(
+                stdin,
+                stdout,
+                &format!(
+                    "Part {} with {} bytes was written to {}. {} bytes are left. Please swap the medium.",
+                    index,
+                    part_len,
+                    split_folder.to_string_lossy(),
+                    remaining
+                ),
+            )?;
+        }
+    }
+
+    let part_count = parts.len();
+    let mut manifest = Manifest {
+        original_file_name: (file_name != original_file_name).then_some(original_file_name),
+        file_name,
+        file_name_bytes: crate::get_file_name_bytes(path),
+        file_len,
+        part_count,
+        parts,
+        checksum_algorithm: options.checksum,
+        checksum: None,
+        compression: None,
+        encryption: None,
+        salt: None,
+        encoding: None,
+        filter: None,
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        padding: None,
+        name_template: options.name_template.clone(),
+        csv_header_len: None,
+        stripe_size: None,
+        part_header_len: None,
+        created_at: manifest::to_timestamp(SystemTime::now()),
+        file_modified_at: file_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(manifest::to_timestamp),
+        file_mode: file_metadata
+            .as_ref()
+            .map(|metadata| manifest::to_mode(&metadata.permissions())),
+        parity: None,
+        recovery: None,
+    };
+    write_checksums(
+        &split_folder,
+        &mut manifest,
+        hashing.finish()?,
+        sfv_parts,
+        vec![None; part_count],
+        options,
+    )?;
+    if options.join_scripts {
+        join_script::write(&split_folder, &manifest)?;
+    }
+
+    let description = format!(
+        "{}\n\nThe {} is on the last medium. To join the parts, copy them all into one folder together with it.",
+        describe_parts(&manifest),
+        manifest::FILE_NAME
+    );
+    let report = SplitReport {
+        split_folder,
+        manifest,
+    };
+    finish_split(stdout, path, &report, description, None, options)
+}
+
+/// Tells the user what `message` says and waits until they swapped the medium for `--fit` and pressed Enter.
+fn ask_for_medium(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    message: &str,
+) -> Result<(), Error> {
+    if !stdin.is_terminal() {
+        return Err(Error(
+            format!(
+                "{} --fit needs a terminal to wait for the medium to be swapped.",
+                message
+            )
+            .into(),
+        ));
+    }
+
+    write!(stdout, "{} Press Enter to continue.  ", message)?;
+    stdout.flush()?;
+
+    let mut input = String::new();
+    let read = stdin
+        .read_line(&mut input)
+        .map_err(Error::io("Failed to read input."))?;
+    if read == 0 {
+        Err(Error(
+            "The split was cancelled. The parts that were written so far cannot be joined without the rest.".into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes the file at `path` into a spanned ZIP archive with volumes of at most `split_size` bytes, for `--zip-volumes`.
+/// See [`zip`]. The volumes are put where the split folder would be created, but not into a folder of their own.
+fn split_zip_volumes(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    path: &Path,
+    mut file: fs::File,
+    file_len: u64,
+    split_size: u64,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    // The archive is read by other tools, so anything that only splitter would understand cannot be used.
+    options.check_conflicts(
+        OptionKind::ZipVolumes,
+        &[
+            OptionKind::Halve,
+            OptionKind::Lines,
+            OptionKind::Delimiter,
+            OptionKind::Text,
+            OptionKind::Jsonl,
+            OptionKind::Csv,
+            OptionKind::Pad,
+            OptionKind::Stripe,
+            OptionKind::Headers,
+            OptionKind::Filter,
+            OptionKind::Compress,
+            OptionKind::Encrypt,
+            OptionKind::Base64,
+            OptionKind::Parity,
+            OptionKind::Redundancy,
+            OptionKind::NoFolder,
+            OptionKind::NameTemplate,
+            OptionKind::Resume,
+            OptionKind::Verify,
+            OptionKind::RemoveSource,
+            OptionKind::Sfv,
+            OptionKind::Sha256sums,
+            OptionKind::JoinScripts,
+        ],
+        None,
+    )?;
+
+    check_split_size(file_len, split_size)?;
+    if split_size < zip::MIN_VOLUME_SIZE {
+        return Err(Error(
+            format!(
+                "The split size must be at least {} bytes for ZIP volumes.",
+                zip::MIN_VOLUME_SIZE
+            )
+            .into(),
+        ));
+    }
+
+    let file_name = crate::get_file_name(path)?;
+    let entry = zip::Entry {
+        file_name: &file_name,
+        len: file_len,
+        modified_at: file
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(manifest::to_timestamp),
+    };
+    let volume_count = entry.volume_count(split_size);
+    let folder = get_parent_folder(path, options);
+    let volume_paths = zip::volume_paths(&folder, &get_base_name(&file_name), volume_count);
+    let archive_path = &volume_paths[volume_count - 1];
+
+    if options.dry_run {
+        return Ok(Outcome {
+            operation: Operation::Split,
+            input: Some(path.to_path_buf()),
+            output: Some(archive_path.clone()),
+            part_count: Some(volume_count),
+            file_len,
+            part_lens: None,
+            dry_run: true,
+            message: format!(
+                "Dry run. {} would be written in {} volumes.",
+                archive_path.to_string_lossy(),
+                volume_count
+            )
+            .into(),
+        });
+    }
+
+    // Existing files are only overwritten with --force, so all volumes can be removed again if writing them fails.
+    if !options.force {
+        if let Some(path) = volume_paths.iter().find(|path| path.exists()) {
+            return Err(Error(
+                format!(
+                    "{} already exists. Use --force to overwrite it.",
+                    path.to_string_lossy()
+                )
+                .into(),
+            ));
+        }
+    }
+
+    let max_parts = options.max_parts.unwrap_or(DEFAULT_MAX_PARTS);
+    if volume_count > max_parts {
+        confirm_part_count(stdin, stdout, volume_count, max_parts)?;
+    }
+
+    if options.output_dir.is_some() {
+        fs::create_dir_all(&folder).map_err(Error::io(format!(
+            "Failed to create folder {}.",
+            folder.to_string_lossy()
+        )))?;
+    }
+
+    let mut buffer = vec![0_u8; options.buffer_size()];
+
+    // The checksum is in the header before the content, so the file is read twice.
+    let mut crc32 = crc32fast::Hasher::new();
+    let mut progress = Progress::new(options.info_stdout(stdout), "Checksumming", file_len);
+    copy_part(&mut file, &mut io::sink(), file_len, &mut buffer, |chunk| {
+        crc32.update(chunk);
+        progress.advance(chunk.len() as u64);
+    })?;
+    progress.finish();
+    let crc32 = crc32.finalize();
+    file.seek(SeekFrom::Start(0))
+        .map_err(Error::io("Failed reading file."))?;
+
+    let mut open_options = fs::OpenOptions::new();
+    if options.force {
+        open_options.write(true).create(true).truncate(true);
+    } else {
+        open_options.write(true).create_new(true);
+    }
+
+    let mut progress = Progress::new(options.info_stdout(stdout), "Splitting", file_len);
+    let result = zip::Writer::new(&entry, crc32, &volume_paths, split_size, &open_options)
+        .and_then(|mut writer| {
+            copy_part(&mut file, &mut writer, file_len, &mut buffer, |chunk| {
+                progress.advance(chunk.len() as u64)
+            })?;
+            writer.finish()
+        });
+    progress.finish();
+
+    if let Err(err) = result {
+        for path in &volume_paths {
+            let _ = fs::remove_file(path);
+        }
+        return Err(err);
+    }
+
+    let message = format!(
+        "Successful split into a ZIP archive. Archive: {}\n\nCreated {} volumes of at most {} bytes each.\n\nKeep all volumes in the same folder and open {} with a zip tool that supports spanned archives, such as 7-Zip, to extract the file.",
+        archive_path.to_string_lossy(),
+        volume_count,
+        split_size,
+        zip::volume_file_name(&file_name, volume_count, volume_count)
+    );
+    // The volumes also hold the ZIP headers, so their lengths are only known to the archive.
+    Ok(Outcome {
+        operation: Operation::Split,
+        input: Some(path.to_path_buf()),
+        output: Some(archive_path.clone()),
+        part_count: Some(volume_count),
+        file_len,
+        part_lens: None,
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Returns whether a file is split with [`split_streamed`], which is the case for compressed parts of the split size
+/// that only hold consecutive bytes of the file.
+/// Parts that are split by their content, or that hold more than the bytes of the file, are sized before compression instead.
+fn is_streamed(options: &Options) -> bool {
+    options.compress.is_some()
+        && options.part_count.is_none()
+        && options.lines_per_part.is_none()
+        && options.delimiter.is_none()
+        && options.pad.is_none()
+        && options.filter.is_none()
+        && options.stripe.is_none()
+        && !(options.halve
+            || options.lines
+            || options.strict
+            || options.text
+            || options.jsonl
+            || options.csv
+            || options.headers
+            || options.encrypt
+            || options.base64
+            || options.no_folder
+            || options.resume)
+}
+
+/// Verifies the parts of the split of the file at `path_buf` described by the `report` and removes the file if that was asked for,
+/// and returns what the split did, with a message that reports it along with the `description` of its parts.
+fn finish_split(
+    stdout: &mut io::StdoutLock,
+    path_buf: &Path,
+    report: &SplitReport,
+    description: String,
+    key: Option<&Key>,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut message = format!(
+        "Successful split. Split folder: {}\n\n{}",
+        report.split_folder.to_string_lossy(),
+        description
+    );
+
+    if options.no_folder && report.manifest.padding.is_some() {
+        message += "\n\nWarning: without a manifest, the padding is not removed from the last part when the parts are joined.";
+    }
+
+    // The parts are the only copy of the data once the file is removed, so they are always verified before that.
+    if options.verify || options.remove_source {
+        verify_split(
+            options.info_stdout(stdout),
+            path_buf,
+            report,
+            key,
+            options.pad,
+            options.retries(),
+            options.buffer_size(),
+        )?;
+        message += "\n\nAll parts were verified against the file.";
+    }
+
+    if options.remove_source {
+        remove_source(path_buf, report)?;
+        message += &format!("\n\n{} was removed.", path_buf.to_string_lossy());
+    }
+
+    message += "\n\nNote that altering the trailing numbers of the filenames may result in corruption when the files are joined.";
+
+    Ok(Outcome {
+        operation: Operation::Split,
+        input: Some(path_buf.to_path_buf()),
+        output: Some(report.split_folder.clone()),
+        part_count: Some(report.manifest.parts.len()),
+        file_len: report.manifest.file_len,
+        part_lens: Some(report.manifest.parts.iter().map(|part| part.len).collect()),
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Writes the `len` bytes of the file at `path` from byte `start` on into a file of their own, for `--extract`.
+/// Both are parsed like split sizes, so `100MB` and `25%` work as well.
+/// The file is written to `--output`, or to `extracted-<name>` in the current folder if it is not given.
+pub fn extract(
+    stdout: &mut io::StdoutLock,
+    path: &Path,
+    start: &str,
+    len: &str,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+    let file_len = file.metadata()?.len();
+
+    if !options.quiet {
+        writeln!(stdout, "File length: {}", file_len)?;
+    }
+
+    // Unlike the length, the start can be 0.
+    let start =
+        parse_len(start, file_len).map_err(|err| Error(format!("{}: {}.", err, start).into()))?;
+    let len = parse_split_size(len, file_len)
+        .map_err(|err| Error(format!("{}: {}.", err, len).into()))?;
+    if start.checked_add(len).is_none_or(|end| end > file_len) {
+        return Err(Error(
+            format!(
+                "{} bytes from byte {} on cannot be extracted because the file is only {} bytes long.",
+                len, start, file_len
+            )
+            .into(),
+        ));
+    }
+
+    let output_path = match &options.output {
+        Some(output) => output.clone(),
+        None => PathBuf::from(format!("extracted-{}", crate::get_file_name(path)?)),
+    };
+
+    let mut open_options = fs::OpenOptions::new();
+    if options.force {
+        open_options.write(true).create(true).truncate(true);
+    } else {
+        open_options.write(true).create_new(true);
+    }
+    let mut output = open_options
+        .open(&output_path)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::AlreadyExists => Error(
+                format!(
+                    "Failed to create output file. {} already exists. Please remove it or use --force.",
+                    output_path.to_string_lossy()
+                )
+                .into(),
+            ),
+            _ => Error::io("Failed to create output file.")(err),
+        })?;
+
+    let mut progress = Progress::new(options.info_stdout(stdout), "Extracting", len);
+    let result = file
+        .seek(SeekFrom::Start(start))
+        .map_err(Error::io("Failed to read file."))
+        .and_then(|_| {
+            let mut range = (&mut file).take(len);
+            let mut buffer = vec![0_u8; options.buffer_size()];
+            loop {
+                let read = range
+                    .read(&mut buffer)
+                    .map_err(Error::io("Failed to read file."))?;
+                if read == 0 {
+                    break;
+                }
+                output
+                    .write_all(&buffer[..read])
+                    .map_err(Error::io("Failed to write output."))?;
+                progress.advance(read as u64);
+            }
+            output
+                .sync_all()
+                .map_err(Error::io("Failed to write output."))
+        });
+    progress.finish();
+
+    if let Err(Error(message)) = result {
+        // A cut off range must not be mistaken for the whole range.
+        drop(output);
+        let _ = fs::remove_file(&output_path);
+        return Err(Error(message));
+    }
+
+    let message = format!(
+        "Successful extraction of {} bytes from byte {} on. Extracted file: {}",
+        len,
+        start,
+        output_path.to_string_lossy()
+    );
+    Ok(Outcome {
+        operation: Operation::Extract,
+        input: Some(path.to_path_buf()),
+        output: Some(output_path),
+        part_count: None,
+        file_len: len,
+        part_lens: None,
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Asks whether to go on with a split into `part_count` parts, which is more than `max_parts`.
+/// Without a terminal to ask on, this fails instead.
+fn confirm_part_count(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    part_count: usize,
+    max_parts: usize,
+) -> Result<(), Error> {
+    let message = format!(
+        "This would create {} parts, which is more than {}.",
+        part_count, max_parts
+    );
+
+    if !stdin.is_terminal() {
+        return Err(Error(
+            format!(
+                "{} Please use a bigger split size or raise the limit with --max-parts.",
+                message
+            )
+            .into(),
+        ));
+    }
+
+    write!(stdout, "{} Do you want to continue? [y/N]  ", message)?;
+    stdout.flush()?;
+
+    let mut input = String::new();
+    stdin
+        .read_line(&mut input)
+        .map_err(Error::io("Failed to read input."))?;
+
+    if matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(())
+    } else {
+        Err(Error("The split was cancelled.".into()))
+    }
+}
+
+/// Reads the parts in the `report` back and compares them with the file at `path` that was split.
+/// If the last part was padded, the padding has to consist of the `pad` byte.
+/// Encrypted parts are decrypted with the `key` they were encrypted with.
+/// Reads of the parts that fail with an error that may go away are retried up to `retries` times.
+/// The parts and the file are read `buffer_size` bytes at a time.
+fn verify_split(
+    stdout: Option<&mut io::StdoutLock>,
+    path: &Path,
+    report: &SplitReport,
+    key: Option<&Key>,
+    pad: Option<u8>,
+    retries: usize,
+    buffer_size: usize,
+) -> Result<(), Error> {
+    let mut file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+
+    let mut progress = Progress::new(stdout, "Verifying", report.manifest.file_len);
+    let mut buffer = vec![0_u8; buffer_size];
+    let mut file_buffer = vec![0_u8; buffer_size];
+
+    // The CSV header that is repeated in the parts is at the start of the file.
+    let mut csv_header = vec![0_u8; report.manifest.csv_header_len.unwrap_or(0) as usize];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut csv_header))
+        .map_err(Error::io("Failed to read file."))?;
+
+    let mut remaining_file_len = report.manifest.file_len;
+
+    for (index, part) in report.manifest.parts.iter().enumerate() {
+        let part_path = report.split_folder.join(&part.file_name);
+        let mismatch = || {
+            Error(
+                format!(
+                    "Part {} does not match the file. It was not written correctly.",
+                    part.file_name
+                )
+                .into(),
+            )
+        };
+
+        // The part header is not in the file at all and a repeated CSV header is not in it at this point,
+        // so they are compared with what they should be instead.
+        let mut expected_header = match report.manifest.part_header_len {
+            Some(_) => get_part_header(
+                report.manifest.original_file_name(),
+                report.manifest.file_len,
+                index,
+                report.manifest.parts.len(),
+            )
+            .to_bytes(),
+            None => Vec::new(),
+        };
+        if index > 0 {
+            expected_header.extend_from_slice(&csv_header);
+        }
+        let mut header = expected_header.as_slice();
+
+        // Both sides are streamed in chunks so that neither has to fit into memory.
+        let mut part_len = 0;
+        join::read_chunks(
+            &mut join::open_part_reader(
+                &part_path,
+                report.manifest.encoding,
+                report.manifest.compression,
+                None,
+                key,
+                retries,
+            )?,
+            &mut buffer,
+            |chunk| {
+                part_len += chunk.len() as u64;
+                let header_chunk_len = header.len().min(chunk.len());
+                let (header_chunk, chunk) = chunk.split_at(header_chunk_len);
+                if header_chunk != &header[..header_chunk_len] {
+                    return Err(mismatch());
+                }
+                header = &header[header_chunk_len..];
+
+                // Anything after the end of the file is padding.
+                let (chunk, padding) =
+                    chunk.split_at(remaining_file_len.min(chunk.len() as u64) as usize);
+                if !padding.is_empty() && padding.iter().any(|byte| Some(*byte) != pad) {
+                    return Err(mismatch());
+                }
+                remaining_file_len -= chunk.len() as u64;
+
+                let file_chunk = &mut file_buffer[..chunk.len()];
+                file.read_exact(file_chunk)
+                    .map_err(|err| match err.kind() {
+                        io::ErrorKind::UnexpectedEof => mismatch(),
+                        _ => Error::io("Failed to read file.")(err),
+                    })?;
+
+                if chunk != file_chunk {
+                    return Err(mismatch());
+                }
+
+                progress.advance(chunk.len() as u64);
+                Ok(())
+            },
+        )?;
+
+        if part_len != part.len {
+            return Err(mismatch());
+        }
+    }
+
+    progress.finish();
+
+    Ok(())
+}
+
+/// Removes the file at `path` that was split, once the parts in the `report` are safely on disk.
+fn remove_source(path: &Path, report: &SplitReport) -> Result<(), Error> {
+    // The parts are the only copy of the data from now on, so they must not get lost in a cache.
+    for part in &report.manifest.parts {
+        let part_path = report.split_folder.join(&part.file_name);
+
+        fs::File::open(&part_path)
+            .and_then(|part| part.sync_all())
+            .map_err(Error::io(format!(
+                "Failed to write {} to disk. The file was not removed.",
+                part_path.to_string_lossy()
+            )))?;
+    }
+
+    fs::remove_file(path).map_err(Error::io(format!(
+        "The split was successful but {} could not be removed.",
+        path.to_string_lossy()
+    )))
+}
+
+/// Splits the file at `path` into parts of `split_size` bytes in a split folder in `out_dir`.
+///
+/// Unlike [`split`], this neither asks for anything nor prints anything.
+pub fn split_file(path: &Path, split_size: u64, out_dir: &Path) -> Result<SplitReport, Error> {
+    let options = Options {
+        output_dir: Some(out_dir.to_path_buf()),
+        ..Options::default()
+    };
+
+    let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+    let metadata = file.metadata()?;
+    let file_len = metadata.len();
+    let plan = plan_split(path, file_len, Some(split_size), None, &options)?;
+
+    write_split(
+        None,
+        file,
+        file_len,
+        plan,
+        None,
+        Some(&SourceState::new(path, &metadata)),
+        &options,
+    )
+}
+
+/// The name that input read from stdin is split under if no `--prefix` is given.
+const STDIN_FILE_NAME: &str = "stdin";
+
+/// Splits what is read from `stdin` into parts as it comes in, without knowing its length in advance.
+///
+/// The split size has to be given with `--size`, and a name for the parts with `--prefix` unless `--output-dir` is given.
+pub fn split_stdin(
+    stdin: &mut io::StdinLock,
+    stdout: &mut io::StdoutLock,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    // Percentages and numbers of parts depend on the length, which is only known at the end.
+    let split_size = match (&options.split_size, options.part_count) {
+        (Some(split_size), None)
+            if !split_size.trim().ends_with('%') && !split_size.trim().starts_with('/') =>
+        {
+            parse_split_size(split_size, u64::MAX)
+                .map_err(|err| Error(format!("{}: {}.", err, split_size).into()))?
+        }
+        _ => {
+            return Err(Error(
+                "--stdin requires a size in bytes given with --size because the length of the input is not known in advance."
+                    .into(),
+            ))
+        }
+    };
+    check_streamed_split_size(split_size, options)?;
+
+    options.check_conflicts(
+        OptionKind::Stdin,
+        &[
+            OptionKind::Halve,
+            OptionKind::Lines,
+            OptionKind::LinesPerPart,
+            OptionKind::Delimiter,
+            OptionKind::Text,
+            OptionKind::Jsonl,
+            OptionKind::Pad,
+            OptionKind::Csv,
+            OptionKind::Stripe,
+            OptionKind::Headers,
+            OptionKind::Filter,
+            OptionKind::Encrypt,
+            OptionKind::Passphrase,
+            OptionKind::Base64,
+            OptionKind::ZipVolumes,
+            OptionKind::NoFolder,
+            OptionKind::Resume,
+            OptionKind::Verify,
+            OptionKind::RemoveSource,
+            OptionKind::DryRun,
+        ],
+        None,
+    )?;
+    check_join_scripts(options)?;
+
+    // There is no filename to name the parts after, so one has to be given, unless the parts go into a folder of their own.
+    let file_name = match (&options.prefix, &options.output_dir) {
+        (Some(prefix), _) => prefix.as_str(),
+        (None, Some(_)) => STDIN_FILE_NAME,
+        (None, None) => return Err(Error(
+            "--stdin requires --prefix to name the parts after, or --output-dir to put them into."
+                .into(),
+        )),
+    };
+
+    if !options.quiet {
+        writeln!(stdout, "Reading from stdin.")?;
+    }
+
+    // Nothing is created for empty input.
+    if stdin
+        .fill_buf()
+        .map_err(Error::io("Failed to read input."))?
+        .is_empty()
+    {
+        return Err(Error(
+            "Nothing was read from stdin. Nothing to split.".into(),
+        ));
+    }
+
+    // Nobody can be asked in the middle of reading stdin, so there is only the limit.
+    let max_parts = options.max_parts.unwrap_or(DEFAULT_MAX_PARTS);
+    let mut progress = Progress::new(None, "Splitting", 0);
+    let report = write_streamed_split(
+        stdin,
+        Path::new(file_name),
+        split_size,
+        Some(max_parts),
+        None,
+        None,
+        &mut progress,
+        options,
+    )?;
+
+    let message = format!(
+        "Successful split of {} bytes from stdin. Split folder: {}\n\n{}\n\nNote that altering the trailing numbers of the filenames may result in corruption when the files are joined.",
+        report.manifest.file_len,
+        report.split_folder.to_string_lossy(),
+        describe_streamed_parts(&report.manifest, split_size)
+    );
+    Ok(Outcome {
+        operation: Operation::Split,
+        input: None,
+        output: Some(report.split_folder),
+        part_count: Some(report.manifest.parts.len()),
+        file_len: report.manifest.file_len,
+        part_lens: Some(report.manifest.parts.iter().map(|part| part.len).collect()),
+        dry_run: false,
+        message: message.into(),
+    })
+}
+
+/// Makes sure that `split_size` is not too small for the parts to be compressed into with [`write_streamed_split`].
+fn check_streamed_split_size(split_size: u64, options: &Options) -> Result<(), Error> {
+    if options.compress.is_some() && split_size < MIN_COMPRESSED_SPLIT_SIZE {
+        Err(Error(
+            format!(
+                "The split size must be at least {} bytes for compressed parts.",
+                MIN_COMPRESSED_SPLIT_SIZE
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Splits what is read from `reader` into parts of `split_size` bytes as it comes in, without knowing its length in advance.
+/// The parts are named after `path`, which does not have to exist, and put into a new split folder next to it.
+/// Parts compressed with `--compress` are at most `split_size` bytes long once they are compressed.
+///
+/// If `reader` would be split into more than `max_parts` parts, an error is returned instead.
+/// The modification time and the permissions of the file are recorded from its `file_metadata` if it is given.
+/// If the `source` file is given, splitting is aborted as soon as it is modified.
+#[allow(clippy::too_many_arguments)]
+fn write_streamed_split<R: BufRead>(
+    reader: &mut R,
+    path: &Path,
+    split_size: u64,
+    max_parts: Option<usize>,
+    file_metadata: Option<&fs::Metadata>,
+    source: Option<&SourceState>,
+    progress: &mut Progress,
+    options: &Options,
+) -> Result<SplitReport, Error> {
+    let original_file_name = crate::get_file_name(path)?.into_owned();
+    let file_name = get_base_name(&original_file_name).into_owned();
+    let name_template = get_name_template(options);
+    check_name_template(&name_template)?;
+
+    let parent_folder = get_parent_folder(path, options);
+    if options.output_dir.is_some() {
+        fs::create_dir_all(&parent_folder).map_err(Error::io(format!(
+            "Failed to create folder {}.",
+            parent_folder.to_string_lossy()
+        )))?;
+    }
+    let split_folder = if options.force {
+        get_split_folder(path, options)?
+    } else {
+        find_free_split_folder(&parent_folder, &file_name)?
+    };
+
+    write_atomically(&split_folder, &file_name, options, |split_folder| {
+        write_streamed_parts(
+            reader,
+            split_folder,
+            original_file_name.clone(),
+            crate::get_file_name_bytes(path),
+            &name_template,
+            split_size,
+            max_parts,
+            file_metadata,
+            source,
+            progress,
+            options,
+        )
+    })
+}
+
+/// Writes the parts of a split made by [`write_streamed_split`] of the file called `original_file_name` into `split_folder`.
+/// `file_name_bytes` is the name if it is not UTF-8. See [`crate::get_file_name_bytes`].
+#[allow(clippy::too_many_arguments)]
+fn write_streamed_parts<R: BufRead>(
+    reader: &mut R,
+    split_folder: PathBuf,
+    original_file_name: String,
+    file_name_bytes: Option<String>,
+    name_template: &str,
+    split_size: u64,
+    max_parts: Option<usize>,
+    file_metadata: Option<&fs::Metadata>,
+    source: Option<&SourceState>,
+    progress: &mut Progress,
+    options: &Options,
+) -> Result<SplitReport, Error> {
+    let file_name = get_base_name(&original_file_name).into_owned();
+    create_split_folder(&split_folder, &file_name, options)?;
+
+    let mut buffer = vec![0_u8; options.buffer_size()];
+    let mut part_lens = Vec::<u64>::new();
+    let mut sfv_crc32s = Vec::<u32>::new();
+    let mut encoded_sha256s = Vec::<Option<String>>::new();
+    let mut hashing = HashingWorker::new(
+        options.checksum,
+        thread::available_parallelism().is_ok_and(|thread_count| thread_count.get() > 1),
+    );
+
+    // The number of parts is only known at the end, and so are the filenames if they are zero-padded
+    // or contain the number of parts. Until then, the parts have temporary names.
+    let temporary_path = |index: usize| split_folder.join(format!(".{}.partial", index));
+
+    while !reader
+        .fill_buf()
+        .map_err(Error::io("Failed to read input."))?
+        .is_empty()
+    {
+        if let Some(max_parts) = max_parts.filter(|max_parts| part_lens.len() == *max_parts) {
+            return Err(Error(
+                format!(
+                    "The input would be split into more than {} parts. Please use a bigger split size or raise the limit with --max-parts.",
+                    max_parts
+                )
+                .into(),
+            ));
+        }
+
+        let output_path = temporary_path(part_lens.len() + 1);
+        let output_file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&output_path)
+            .map_err(Error::io(format!(
+                "Failed to create output file {}.",
+                output_path.to_string_lossy()
+            )))?;
+        let mut output = Crc32Writer::new(output_file);
+
+        hashing.start_part(options.sha256sums && options.compress.is_none());
+        let inspect = |chunk: &[u8]| {
+            hashing.update(chunk);
+            progress.advance(chunk.len() as u64);
+        };
+
+        let (part_len, encoded_sha256) = match options.compress {
+            Some(compression) => {
+                let mut encoder = compression
+                    .encoder(CountingWriter::new(Sha256Writer::new(&mut output)))
+                    .map_err(Error::io("Failed to write output."))?;
+                let part_len = copy_compressed(reader, &mut encoder, split_size, inspect)?;
+                let sha256 = encoder
+                    .finish()
+                    .map_err(Error::io("Failed to write output."))?
+                    .inner
+                    .finalize();
+                (part_len, Some(sha256))
+            }
+            None => {
+                let part_len = copy_up_to(reader, &mut output, split_size, &mut buffer, inspect)?;
+                (part_len, None)
+            }
+        };
+
+        part_lens.push(part_len);
+        sfv_crc32s.push(output.finalize());
+        encoded_sha256s.push(encoded_sha256);
+
+        if let Some(source) = source {
+            source.check()?;
+        }
+    }
+
+    progress.finish();
+
+    let file_names = part_file_names(&file_name, part_lens.len(), name_template, options)?;
+    for (index, file_name) in file_names.iter().enumerate() {
+        let output_path = split_folder.join(file_name);
+        fs::rename(temporary_path(index + 1), &output_path).map_err(Error::io(format!(
+            "Failed to create output file {}.",
+            output_path.to_string_lossy()
+        )))?;
+    }
+
+    let mut manifest = Manifest {
+        original_file_name: (file_name != original_file_name).then_some(original_file_name),
+        file_name,
+        file_name_bytes,
+        file_len: part_lens.iter().sum(),
+        part_count: part_lens.len(),
+        parts: part_lens
+            .iter()
+            .zip(&file_names)
+            .enumerate()
+            .map(|(index, (len, file_name))| manifest::Part {
+                index: index + 1,
+                file_name: file_name.clone(),
+                len: *len,
+                checksum: None,
+                nonce: None,
+            })
+            .collect(),
+        checksum_algorithm: options.checksum,
+        checksum: None,
+        compression: options.compress,
+        encryption: None,
+        salt: None,
+        encoding: None,
+        filter: None,
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        padding: None,
+        name_template: options.name_template.clone(),
+        csv_header_len: None,
+        stripe_size: None,
+        part_header_len: None,
+        created_at: manifest::to_timestamp(SystemTime::now()),
+        file_modified_at: file_metadata
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(manifest::to_timestamp),
+        file_mode: file_metadata.map(|metadata| manifest::to_mode(&metadata.permissions())),
+        parity: None,
+        recovery: None,
+    };
+
+    if options.parity {
+        manifest.parity = Some(parity::write(
+            &split_folder,
+            &manifest,
+            options.separator(),
+            options.buffer_size(),
+        )?);
+    }
+    if let Some(recovery_count) = options.redundancy {
+        manifest.recovery = Some(recovery::write(
+            &split_folder,
+            &manifest,
+            options.separator(),
+            recovery_count,
+            options.buffer_size(),
+        )?);
+    }
+
+    let sfv_parts = file_names.into_iter().zip(sfv_crc32s).collect();
+    write_checksums(
+        &split_folder,
+        &mut manifest,
+        hashing.finish()?,
+        sfv_parts,
+        encoded_sha256s,
+        options,
+    )?;
+
+    if options.join_scripts {
+        join_script::write(&split_folder, &manifest)?;
+    }
+
+    Ok(SplitReport {
+        split_folder,
+        manifest,
+    })
+}
+
+/// Describes the parts of a split made by [`write_streamed_split`] with a `split_size`.
+/// Compressed parts are described by the length they have at most once they are compressed,
+/// as the manifest only has the lengths they had before.
+fn describe_streamed_parts(manifest: &Manifest, split_size: u64) -> String {
+    match (manifest.compression, manifest.parts.len()) {
+        (Some(_), 1) => format!(
+            "Created 1 part of at most {} bytes after compression.",
+            split_size
+        ),
+        (Some(_), part_count) => format!(
+            "Created {} parts of at most {} bytes each after compression.",
+            part_count, split_size
+        ),
+        (None, _) => describe_parts(manifest),
+    }
+}
+
+/// How many parts [`describe_parts`] lists one by one at most if they do not have the same size.
+const LISTED_PART_COUNT: usize = 10;
+
+/// Describes how many parts the split in the `manifest` has and how long they are, so that it can be checked at a glance.
+fn describe_parts(manifest: &Manifest) -> String {
+    let lens = manifest
+        .parts
+        .iter()
+        .map(|part| part.len)
+        .collect::<Vec<u64>>();
+    let steps = [
+        (manifest.compression.is_some(), "compression"),
+        (manifest.encryption.is_some(), "encryption"),
+        (manifest.encoding.is_some(), "Base64 encoding"),
+    ]
+    .iter()
+    .filter(|(applied, _)| *applied)
+    .map(|(_, step)| *step)
+    .collect::<Vec<&str>>();
+    let compression_note = match steps.as_slice() {
+        [] => String::new(),
+        [step] => format!(" before {}", step),
+        [steps @ .., last] => format!(" before {} and {}", steps.join(", "), last),
+    };
+
+    let description = match lens.as_slice() {
+        [] => "No parts were created.".to_string(),
+        [len] => format!("Created 1 part of {} bytes{}.", len, compression_note),
+        [first, .., last]
+            if lens[..lens.len() - 1].iter().all(|len| len == first) && last != first =>
+        {
+            format!(
+                "Created {} parts of {} bytes each{}, except for the last one with {} bytes.",
+                lens.len(),
+                first,
+                compression_note,
+                last
+            )
+        }
+        [first, ..] if lens.iter().all(|len| len == first) => format!(
+            "Created {} parts of {} bytes each{}.",
+            lens.len(),
+            first,
+            compression_note
+        ),
+        _ if lens.len() <= LISTED_PART_COUNT => format!(
+            "Created {} parts{}:\n\n{}",
+            lens.len(),
+            compression_note,
+            manifest
+                .parts
+                .iter()
+                .map(|part| format!("{}: {} bytes", part.file_name, part.len))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ),
+        _ => format!(
+            "Created {} parts of {} to {} bytes{}.",
+            lens.len(),
+            lens.iter().min().unwrap_or(&0),
+            lens.iter().max().unwrap_or(&0),
+            compression_note
+        ),
+    };
+
+    let description = match manifest.stripe_size {
+        Some(stripe_size) => format!(
+            "{}\n\nThe file was dealt out to the parts in stripes of {} bytes, so they can only be joined with the {}.",
+            description,
+            stripe_size,
+            manifest::FILE_NAME
+        ),
+        None => description,
+    };
+
+    match manifest.padding {
+        Some(padding) => format!(
+            "{} The last part is padded with {} bytes.",
+            description, padding
+        ),
+        None => description,
+    }
+}
+
+/// What a split is going to create, worked out before anything is written.
+struct Plan {
+    /// The lengths of the consecutive byte ranges of the file that go into the parts,
+    /// or of all stripes that go into them with `--stripe`.
+    parts: Vec<u64>,
+    /// The header that every part but the first one starts with, if the file is split with `--csv`.
+    /// The first part already starts with it because it is at the start of the file.
+    csv_header: Option<Vec<u8>>,
+    /// How many bytes the last part is padded with up to the split size, if it is padded with `--pad`.
+    padding: u64,
+    /// The length of the part header every part starts with, if the file is split with `--headers`.
+    part_header_len: Option<u64>,
+    /// The length of the stripes the file is dealt out to the parts in, if it is split with `--stripe`.
+    /// The parts do not hold consecutive byte ranges of the file then.
+    stripe_size: Option<u64>,
+    file_names: Vec<String>,
+    /// The name the split folder and the parts are named after. See [`get_base_name`].
+    file_name: String,
+    /// The full name of the file, which is recorded in the manifest and the part headers.
+    original_file_name: String,
+    /// The full name of the file as hexadecimal if it is not UTF-8. See [`crate::get_file_name_bytes`].
+    file_name_bytes: Option<String>,
+    /// The folder the split folder is created in.
+    parent_folder: PathBuf,
+    /// The folder the parts are written to. With `--no-folder`, this is the same as `parent_folder`.
+    split_folder: PathBuf,
+}
+
+impl Plan {
+    /// Returns the lengths of the parts as they are written,
+    /// which includes the part header, the repeated CSV header and the padding.
+    fn part_lens(&self) -> Vec<u64> {
+        let header_len = self
+            .csv_header
+            .as_ref()
+            .map_or(0, |header| header.len() as u64);
+
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                let header_len = if index == 0 { 0 } else { header_len };
+                let padding = if index + 1 == self.parts.len() {
+                    self.padding
+                } else {
+                    0
+                };
+                self.part_header_len.unwrap_or(0) + part + header_len + padding
+            })
+            .collect()
+    }
+}
+
+/// What a split created.
+pub struct SplitReport {
+    /// The folder the parts were written to.
+    pub split_folder: PathBuf,
+    /// Describes the parts. With `--no-folder`, it is not written to the split folder.
+    pub manifest: Manifest,
+}
+
+/// Works out the parts that splitting the file at `path` into parts of `split_size` bytes results in
+/// and where they go. Nothing is created yet.
+///
+/// There is no `split_size` if the file is split with `--lines-per-part`.
+/// If a `part_count` was asked for, the parts are balanced instead of leaving a short last part.
+fn plan_split(
+    path: &Path,
+    file_len: u64,
+    split_size: Option<u64>,
+    part_count: Option<u64>,
+    options: &Options,
+) -> Result<Plan, Error> {
+    let name_template = get_name_template(options);
+    check_name_template(&name_template)?;
+
+    // The filter writes the parts however it likes, so they cannot be read back or checked as they are stored.
+    if options.filter.is_some() {
+        options.check_conflicts(
+            OptionKind::Filter,
+            &[
+                OptionKind::Compress,
+                OptionKind::Parity,
+                OptionKind::Redundancy,
+                OptionKind::Resume,
+                OptionKind::Verify,
+                OptionKind::RemoveSource,
+                OptionKind::Sfv,
+                OptionKind::Sha256sums,
+            ],
+            Some("the parts are written by the filter"),
+        )?;
+    }
+
+    if options.resume {
+        options.check_conflicts(
+            OptionKind::Resume,
+            &[
+                OptionKind::NoFolder,
+                OptionKind::Compress,
+                OptionKind::Encrypt,
+                OptionKind::Base64,
+            ],
+            Some("it relies on the manifest and the lengths of the parts"),
+        )?;
+    }
+
+    if options.no_folder {
+        options.check_conflicts(
+            OptionKind::NoFolder,
+            &[OptionKind::Sha256sums],
+            Some(&format!(
+                "{} could be mixed up with the checksums of other files",
+                sha256sums::FILE_NAME
+            )),
+        )?;
+        options.check_conflicts(
+            OptionKind::NoFolder,
+            &[OptionKind::NameTemplate],
+            Some("such parts can only be joined with the manifest of a split folder"),
+        )?;
+        options.check_conflicts(
+            OptionKind::NoFolder,
+            &[OptionKind::Parity, OptionKind::Redundancy],
+            Some("missing parts can only be rebuilt with the manifest of a split folder when joining"),
+        )?;
+        options.check_conflicts(
+            OptionKind::NoFolder,
+            &[OptionKind::Csv],
+            Some("the repeated headers can only be removed with the manifest of a split folder when joining"),
+        )?;
+    }
+
+    if options.parity {
+        options.check_conflicts(
+            OptionKind::Parity,
+            &[OptionKind::Redundancy],
+            Some("the recovery parts can already rebuild a missing part"),
+        )?;
+    }
+
+    if options.strict && options.delimiter.is_none() {
+        return Err(Error("--strict can only be used with --delimiter.".into()));
+    }
+
+    check_join_scripts(options)?;
+
+    let mut csv_header = None;
+    let parts = match (split_size, options.lines_per_part) {
+        (_, Some(lines_per_part)) => {
+            let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+            let parts = line_count_parts(
+                io::BufReader::with_capacity(options.buffer_size(), file),
+                lines_per_part,
+            )?;
+
+            if parts.is_empty() {
+                return Err(Error("The file is empty. Nothing to split.".into()));
+            } else if parts.len() == 1 {
+                return Err(Error(
+                    format!(
+                        "The file does not have more than {} lines. Nothing to split.",
+                        lines_per_part
+                    )
+                    .into(),
+                ));
+            }
+            parts
+        }
+        (Some(split_size), None) => {
+            check_split_size(file_len, split_size)?;
+
+            if options.lines {
+                options.check_conflicts(OptionKind::Lines, &[OptionKind::Halve], None)?;
+            }
+
+            if options.pad.is_some() {
+                options.check_conflicts(
+                    OptionKind::Pad,
+                    &[
+                        OptionKind::Halve,
+                        OptionKind::Lines,
+                        OptionKind::Text,
+                        OptionKind::Jsonl,
+                        OptionKind::Csv,
+                        OptionKind::Delimiter,
+                    ],
+                    Some("it only pads parts of the split size"),
+                )?;
+            }
+
+            if options.delimiter.is_some() {
+                options.check_conflicts(
+                    OptionKind::Delimiter,
+                    &[
+                        OptionKind::Halve,
+                        OptionKind::Lines,
+                        OptionKind::Text,
+                        OptionKind::Jsonl,
+                        OptionKind::Csv,
+                    ],
+                    None,
+                )?;
+            }
+
+            if options.text {
+                options.check_conflicts(
+                    OptionKind::Text,
+                    &[
+                        OptionKind::Halve,
+                        OptionKind::Lines,
+                        OptionKind::Jsonl,
+                        OptionKind::Csv,
+                    ],
+                    None,
+                )?;
+            }
+
+            if options.jsonl {
+                options.check_conflicts(
+                    OptionKind::Jsonl,
+                    &[OptionKind::Halve, OptionKind::Csv],
+                    Some("it already splits into whole records"),
+                )?;
+            }
+
+            if options.csv {
+                options.check_conflicts(
+                    OptionKind::Csv,
+                    &[OptionKind::Lines, OptionKind::Halve],
+                    Some("it already splits into whole records"),
+                )?;
+            }
+
+            if options.csv {
+                let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+                let (header, parts) = csv_parts(
+                    io::BufReader::with_capacity(options.buffer_size(), file),
+                    split_size,
+                )?;
+                csv_header = Some(header);
+                parts
+            } else if let Some(delimiter) = &options.delimiter {
+                let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+                delimiter_parts(
+                    io::BufReader::with_capacity(options.buffer_size(), file),
+                    delimiter,
+                    split_size,
+                    options.strict,
+                )?
+            } else if options.lines || options.jsonl {
+                let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+                line_parts(
+                    io::BufReader::with_capacity(options.buffer_size(), file),
+                    split_size,
+                )?
+            } else if options.text {
+                let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+                text_parts(file, file_len, split_size)?
+            } else if options.halve {
+                // Halving ends once the parts are shorter than the split size, which a part of 1 byte never is.
+                if split_size == 1 {
+                    return Err(Error(
+                        "--halve cannot be used with a split size of 1 byte because no part can be shorter than that.".into(),
+                    ));
+                }
+                split_parts(file_len, split_size)
+            } else if let Some(part_count) = part_count {
+                balanced_parts(file_len, part_count)
+            } else {
+                fixed_chunks(file_len, split_size)
+            }
+        }
+        (None, None) => unreachable!(),
+    };
+
+    if options.jsonl {
+        let file = fs::File::open(path).map_err(Error::io("Failed to open file."))?;
+        check_jsonl_boundaries(
+            io::BufReader::with_capacity(options.buffer_size(), file),
+            &parts,
+        )?;
+    }
+
+    // The parts are only counted as they would be without stripes.
+    let (parts, stripe_size) = match &options.stripe {
+        Some(stripe) => {
+            let stripe_size = plan_stripes(stripe, file_len, parts.len(), options)?;
+            (
+                stripe_parts(file_len, stripe_size, parts.len()),
+                Some(stripe_size),
+            )
+        }
+        None => (parts, None),
+    };
+
+    // The other parts are exactly the split size, so only the last one can be shorter.
+    let padding = match (options.pad, split_size, parts.last()) {
+        (Some(_), Some(split_size), Some(last_part)) => split_size - last_part,
+        _ => 0,
+    };
+
+    let original_file_name = crate::get_file_name(path)?.into_owned();
+    let file_name_bytes = crate::get_file_name_bytes(path);
+    let file_name = get_base_name(&original_file_name).into_owned();
+    let file_names = part_file_names(&file_name, parts.len(), &name_template, options)?;
+
+    // Only the index differs between the part headers, so they all have the same length.
+    let part_header_len = options
+        .headers
+        .then(|| get_part_header(&original_file_name, file_len, 0, parts.len()).encoded_len());
+
+    // With `--no-folder`, the parts go directly into the folder the split folder would be created in.
+    let parent_folder = get_parent_folder(path, options);
+    let split_folder = if options.no_folder {
+        parent_folder.clone()
+    } else if options.force || options.resume {
+        get_split_folder(path, options)?
+    } else {
+        find_free_split_folder(&parent_folder, &file_name)?
+    };
+
+    Ok(Plan {
+        parts,
+        csv_header,
+        padding,
+        part_header_len,
+        stripe_size,
+        file_names,
+        file_name,
+        original_file_name,
+        file_name_bytes,
+        parent_folder,
+        split_folder,
+    })
+}
+
+/// Parses the `stripe` size given with `--stripe` for a split of a file of `file_len` bytes into `part_count` parts
+/// and makes sure that every part gets at least one stripe.
+fn plan_stripes(
+    stripe: &str,
+    file_len: u64,
+    part_count: usize,
+    options: &Options,
+) -> Result<u64, Error> {
+    // Only the bytes of the file are dealt out, so anything that is added to the parts or depends on where they end is out.
+    options.check_conflicts(
+        OptionKind::Stripe,
+        &[
+            OptionKind::LinesPerPart,
+            OptionKind::Lines,
+            OptionKind::Delimiter,
+            OptionKind::Text,
+            OptionKind::Jsonl,
+            OptionKind::Csv,
+            OptionKind::Pad,
+            OptionKind::Headers,
+            OptionKind::Filter,
+            OptionKind::Compress,
+            OptionKind::Encrypt,
+            OptionKind::Base64,
+            OptionKind::NoFolder,
+            OptionKind::Resume,
+            OptionKind::Verify,
+            OptionKind::RemoveSource,
+        ],
+        None,
+    )?;
+
+    let stripe_size = parse_split_size(stripe, file_len)
+        .map_err(|err| Error(format!("{}: {}.", err, stripe).into()))?;
+    if stripe_size
+        .checked_mul(part_count.saturating_sub(1) as u64)
+        .is_none_or(|len| len >= file_len)
+    {
+        return Err(Error(
+            format!(
+                "The stripe size {} is too big for {} parts. The file is only {} bytes long, so not every part would get a stripe.",
+                stripe_size, part_count, file_len
+            )
+            .into(),
+        ));
+    }
+
+    Ok(stripe_size)
+}
+
+/// Makes sure that the parts can be joined by simply putting them one after another if `--join-scripts` is given,
+/// as that is all the join scripts do.
+fn check_join_scripts(options: &Options) -> Result<(), Error> {
+    if !options.join_scripts {
+        return Ok(());
+    }
+
+    options.check_conflicts(
+        OptionKind::JoinScripts,
+        &[
+            OptionKind::Csv,
+            OptionKind::Pad,
+            OptionKind::Stripe,
+            OptionKind::Headers,
+            OptionKind::Filter,
+            OptionKind::Compress,
+            OptionKind::Encrypt,
+            OptionKind::Base64,
+            OptionKind::NoFolder,
+        ],
+        Some("the parts cannot be joined by putting them one after another"),
+    )
+}
+
+/// Returns the part header of the part at `index`, starting at 0, of the `part_count` parts of the file called `file_name`.
+fn get_part_header(file_name: &str, file_len: u64, index: usize, part_count: usize) -> PartHeader {
+    PartHeader {
+        index: index as u64 + 1,
+        part_count: part_count as u64,
+        file_name: file_name.to_string(),
+        file_len,
+    }
+}
+
+/// Creates the parts of `file` that are described by `plan` and the manifest.
+/// Progress is reported on `stdout` if it is given.
+/// The parts are encrypted with the `key` if one is given.
+fn write_split(
+    stdout: Option<&mut io::StdoutLock>,
+    file: fs::File,
+    file_len: u64,
+    mut plan: Plan,
+    key: Option<&Key>,
+    source: Option<&SourceState>,
+    options: &Options,
+) -> Result<SplitReport, Error> {
+    // Without a split folder of their own, the parts can only be written where they belong.
+    if options.no_folder {
+        return write_split_into(stdout, file, file_len, plan, key, source, options);
+    }
+
+    let split_folder = plan.split_folder.clone();
+    let file_name = plan.file_name.clone();
+    write_atomically(&split_folder, &file_name, options, |folder| {
+        plan.split_folder = folder;
+        write_split_into(stdout, file, file_len, plan, key, source, options)
+    })
+}
+
+/// Writes the parts of the `plan` into its split folder, which [`write_split`] may have swapped for a temporary one.
+/// If the `source` file is given, splitting is aborted as soon as it is modified.
+fn write_split_into(
+    stdout: Option<&mut io::StdoutLock>,
+    mut file: fs::File,
+    file_len: u64,
+    plan: Plan,
+    key: Option<&Key>,
+    source: Option<&SourceState>,
+    options: &Options,
+) -> Result<SplitReport, Error> {
+    let part_lens = plan.part_lens();
+    let Plan {
+        parts,
+        csv_header,
+        padding,
+        part_header_len,
+        stripe_size,
+        file_names,
+        file_name,
+        original_file_name,
+        file_name_bytes,
+        parent_folder,
+        split_folder,
+    } = plan;
+
+    if options.output_dir.is_some() {
+        fs::create_dir_all(&parent_folder).map_err(Error::io(format!(
+            "Failed to create folder {}.",
+            parent_folder.to_string_lossy()
+        )))?;
+    }
+
+    let file_metadata = file.metadata().ok();
+    let mut manifest = Manifest {
+        original_file_name: (file_name != original_file_name).then_some(original_file_name),
+        file_name,
+        file_name_bytes,
+        file_len,
+        part_count: parts.len(),
+        parts: part_lens
+            .into_iter()
+            .zip(file_names)
+            .enumerate()
+            .map(|(index, (len, file_name))| manifest::Part {
+                index: index + 1,
+                file_name,
+                len,
+                checksum: None,
+                nonce: None,
+            })
+            .collect(),
+        checksum_algorithm: options.checksum,
+        checksum: None,
+        compression: options.compress,
+        encryption: key.map(|_| manifest::Encryption::ChaCha20Poly1305Argon2id),
+        salt: key.map(Key::salt),
+        encoding: options.base64.then_some(Encoding::Base64),
+        filter: options.filter.clone(),
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        padding: (padding > 0).then_some(padding),
+        name_template: options.name_template.clone(),
+        csv_header_len: csv_header.as_ref().map(|header| header.len() as u64),
+        stripe_size,
+        part_header_len,
+        created_at: manifest::to_timestamp(SystemTime::now()),
+        file_modified_at: file_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(manifest::to_timestamp),
+        file_mode: file_metadata
+            .as_ref()
+            .map(|metadata| manifest::to_mode(&metadata.permissions())),
+        parity: None,
+        recovery: None,
+    };
+
+    let resuming = options.resume && split_folder.is_dir();
+
+    if options.no_folder {
+        // Without a split folder of their own, the parts could collide with other files.
+        // Those are reported before anything is written.
+        if let Some(part) = manifest
+            .parts
+            .iter()
+            .find(|part| split_folder.join(&part.file_name).exists())
+        {
+            return Err(Error(
+                format!(
+                    "Failed to create output file. {} already exists.",
+                    split_folder.join(&part.file_name).to_string_lossy()
+                )
+                .into(),
+            ));
+        }
+    } else if resuming {
+        check_resumable(&split_folder, &manifest)?;
+    } else {
+        create_split_folder(&split_folder, &manifest.file_name, options)?;
+
+        // The manifest is written before the parts so that an interrupted split can be resumed.
+        // The checksums are only added once all parts were written.
+        manifest.write(&split_folder)?;
+    }
+
+    let mut open_options = fs::OpenOptions::new();
+    if resuming {
+        // Parts that were only partially written are written again from the start.
+        open_options.write(true).create(true).truncate(true);
+    } else {
+        open_options.write(true).create_new(true);
+    }
+
+    if let Some(stripe_size) = stripe_size {
+        let (checksums, sfv_parts) = write_stripes(
+            stdout,
+            &mut file,
+            &split_folder,
+            &manifest,
+            stripe_size,
+            &open_options,
+            options,
+        )?;
+        if let Some(source) = source {
+            source.check()?;
+        }
+        let encoded_sha256s = vec![None; manifest.parts.len()];
+        return finish_write_split(
+            split_folder,
+            manifest,
+            checksums,
+            sfv_parts,
+            encoded_sha256s,
+            options,
+        );
+    }
+
+    // There is no point in allocating more than the biggest part could ever fill.
+    let largest_part = parts.iter().copied().max().unwrap_or(0);
+    let mut buffer = vec![0_u8; largest_part.min(options.buffer_size() as u64) as usize];
+
+    // This reads the file's content sequentially into the working buffer and writes it to the split file until
+    // that split file has reached its size, then it goes on to the next split file and so on.
+    // The split file sizes are determined by `fixed_chunks`, `balanced_parts` or `split_parts`.
+    // They are based on this `file`'s length so at the end there will be nothing left to read.
+    let mut sfv_parts = Vec::<(String, u32)>::with_capacity(parts.len());
+    let mut encoded_sha256s = Vec::<Option<String>>::with_capacity(parts.len());
+    let mut progress = Progress::new(stdout, "Splitting", file_len);
+
+    let available_threads =
+        thread::available_parallelism().map_or(1, |thread_count| thread_count.get());
+
+    // Hashing on its own thread only pays off if that thread does not have to share a core with the reads and writes.
+    let mut hashing = HashingWorker::new(
+        options.checksum,
+        file_len >= PARALLEL_THRESHOLD && available_threads > 1,
+    );
+
+    // The part headers have the full name of the file, even if the parts are named after a shortened one.
+    let original_file_name = manifest.original_file_name().to_string();
+
+    for (index, part) in manifest.parts.iter_mut().enumerate() {
+        let output_path = split_folder.join(&part.file_name);
+
+        // When resuming, parts that already have their full length are kept, but only where they match the file.
+        // After a power loss, a part can have its full length but hold zeros instead of what was written.
+        // The file is read anyway because its content is needed for the checksums.
+        let is_written = resuming
+            && fs::metadata(&output_path)
+                .is_ok_and(|metadata| metadata.is_file() && metadata.len() == part.len);
+
+        // With `--filter`, the part is piped into the command instead, which writes it itself.
+        let mut filter = match &options.filter {
+            Some(command) => Some(FilterWriter::spawn(command, &output_path)?),
+            None => None,
+        };
+
+        let output: Box<dyn Write + '_> = if let Some(filter) = &mut filter {
+            Box::new(filter)
+        } else if is_written {
+            let output_file = fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&output_path)
+                .map_err(Error::io(format!(
+                    "Failed to open {}.",
+                    output_path.to_string_lossy()
+                )))?;
+            Box::new(RepairingWriter::new(output_file))
+        } else {
+            Box::new(open_options.open(&output_path).map_err(Error::io(format!(
+                "Failed to create output file {}.",
+                output_path.to_string_lossy()
+            )))?)
+        };
+        // The CRC32 checksum is of the part as it is stored, so for compressed or encrypted parts it is of that data.
+        let mut output = Crc32Writer::new(output);
+
+        // Like the CRC32 checksum, the checksum in SHA256SUMS is of the part as it is stored.
+        // Parts that are neither compressed, encrypted nor encoded are stored as they are read, so their checksum is computed from the file.
+        let stored_as_read = options.compress.is_none() && key.is_none() && !options.base64;
+        hashing.start_part(options.sha256sums && stored_as_read);
+
+        // The part header comes first so that it can be read without knowing anything else about the part.
+        let mut header = match part_header_len {
+            Some(_) => {
+                get_part_header(&original_file_name, file_len, index, parts.len()).to_bytes()
+            }
+            None => Vec::new(),
+        };
+        // Every part but the first one gets a copy of the CSV header, which is not part of the file at this point.
+        if let Some(csv_header) = &csv_header {
+            if index > 0 {
+                header.extend_from_slice(csv_header);
+            }
+        }
+        hashing.update_part(&header);
+
+        // Like the CSV header, the padding is not part of the file.
+        let padding = if index + 1 == parts.len() { padding } else { 0 };
+        let mut pad = io::repeat(options.pad.unwrap_or(0));
+
+        let mut write_content = |mut writer: &mut dyn Write| {
+            writer
+                .write_all(&header)
+                .map_err(Error::io("Failed to write output."))?;
+            copy_part(&mut file, &mut writer, parts[index], &mut buffer, |chunk| {
+                hashing.update(chunk);
+                progress.advance(chunk.len() as u64);
+            })?;
+            copy_part(&mut pad, &mut writer, padding, &mut buffer, |chunk| {
+                hashing.update_part(chunk)
+            })
+        };
+        let mut encode = |writer: &mut dyn Write| match options.compress {
+            Some(compression) => {
+                let mut encoder = compression
+                    .encoder(writer)
+                    .map_err(Error::io("Failed to write output."))?;
+                write_content(&mut encoder)?;
+                // This writes the end of the compressed data, which would otherwise only be attempted on drop without reporting errors.
+                encoder
+                    .finish()
+                    .map_err(Error::io("Failed to write output."))?;
+                Ok(())
+            }
+            None => write_content(writer),
+        };
+
+        // Parts are compressed before they are encrypted, as encrypted data cannot be compressed.
+        // Encoding them as text comes last so that the text is all that has to survive being sent.
+        let encoded_sha256 = if stored_as_read {
+            encode(&mut output)?;
+            None
+        } else {
+            let mut encrypt = |writer: &mut dyn Write| match key {
+                Some(key) => {
+                    let mut encrypted = EncryptWriter::new(writer, key)?;
+                    // The manifest records the nonces so that parts that were swapped or mixed in are noticed when joining.
+                    part.nonce = Some(encrypted.nonce());
+                    encode(&mut encrypted)?;
+                    encrypted.finish()?;
+                    Ok(())
+                }
+                None => encode(writer),
+            };
+            let mut stored = Sha256Writer::new(&mut output);
+            if options.base64 {
+                let mut base64 = Base64Writer::new(&mut stored);
+                encrypt(&mut base64)?;
+                base64
+                    .finish()
+                    .map_err(Error::io("Failed to write output."))?;
+            } else {
+                encrypt(&mut stored)?;
+            }
+            Some(stored.finalize())
+        };
+
+        sfv_parts.push((part.file_name.clone(), output.finalize()));
+        encoded_sha256s.push(encoded_sha256);
+
+        if let Some(filter) = filter {
+            filter.finish()?;
+        }
+
+        if let Some(source) = source {
+            source.check()?;
+        }
+    }
+
+    let checksums = hashing.finish()?;
+
+    progress.finish();
+
+    finish_write_split(
+        split_folder,
+        manifest,
+        checksums,
+        sfv_parts,
+        encoded_sha256s,
+        options,
+    )
+}
+
+/// Writes the parity file or the recovery parts and the checksums once the parts of the `manifest` were written to `split_folder`.
+/// See [`write_checksums`] for the `checksums`, `sfv_parts` and `encoded_sha256s`.
+fn finish_write_split(
+    split_folder: PathBuf,
+    mut manifest: Manifest,
+    checksums: Checksums,
+    sfv_parts: Vec<(String, u32)>,
+    encoded_sha256s: Vec<Option<String>>,
+    options: &Options,
+) -> Result<SplitReport, Error> {
+    if options.parity {
+        manifest.parity = Some(parity::write(
+            &split_folder,
+            &manifest,
+            options.separator(),
+            options.buffer_size(),
+        )?);
+    }
+    if let Some(recovery_count) = options.redundancy {
+        manifest.recovery = Some(recovery::write(
+            &split_folder,
+            &manifest,
+            options.separator(),
+            recovery_count,
+            options.buffer_size(),
+        )?);
+    }
+
+    write_checksums(
+        &split_folder,
+        &mut manifest,
+        checksums,
+        sfv_parts,
+        encoded_sha256s,
+        options,
+    )?;
+
+    if options.join_scripts {
+        join_script::write(&split_folder, &manifest)?;
+    }
+
+    Ok(SplitReport {
+        split_folder,
+        manifest,
+    })
+}
+
+/// Writes `file` into the parts of the `manifest` in `split_folder`, dealt out to them in stripes of `stripe_size` bytes,
+/// and returns the checksums of the parts and the file and the CRC32 checksums of the parts.
+/// Progress is reported on `stdout` if it is given.
+///
+/// The file is read from start to end and all parts are written at the same time,
+/// each on a thread of its own if there are enough, so that parts on different drives are written to at the same time.
+fn write_stripes(
+    stdout: Option<&mut io::StdoutLock>,
+    file: &mut fs::File,
+    split_folder: &Path,
+    manifest: &Manifest,
+    stripe_size: u64,
+    open_options: &fs::OpenOptions,
+    options: &Options,
+) -> Result<(Checksums, Vec<(String, u32)>), Error> {
+    let part_count = manifest.parts.len();
+    let available_threads =
+        thread::available_parallelism().map_or(1, |thread_count| thread_count.get());
+    // Every part is written on a thread of its own if there is a core for each.
+    let threaded = part_count > 1 && part_count <= available_threads;
+
+    let mut outputs = Vec::<Crc32Writer<Box<dyn Write>>>::with_capacity(part_count);
+    for part in &manifest.parts {
+        let output_path = split_folder.join(&part.file_name);
+        let output_file = open_options.open(&output_path).map_err(Error::io(format!(
+            "Failed to create output file {}.",
+            output_path.to_string_lossy()
+        )))?;
+        let output: Box<dyn Write> = if threaded {
+            Box::new(ThreadWriter::new(output_file))
+        } else {
+            Box::new(output_file)
+        };
+        outputs.push(Crc32Writer::new(output));
+    }
+
+    // The parts are stored as they are read, so the SHA-256 checksums for SHA256SUMS are of what is read as well.
+    let mut part_hashers = manifest
+        .parts
+        .iter()
+        .map(|_| options.checksum.hasher())
+        .collect::<Vec<Box<dyn checksum::Hasher>>>();
+    let mut sha256s = manifest
+        .parts
+        .iter()
+        .map(|_| options.sha256sums.then(Sha256::new))
+        .collect::<Vec<Option<Sha256>>>();
+    let mut file_hasher = options.checksum.hasher();
+
+    let mut buffer = vec![0_u8; stripe_size.min(options.buffer_size() as u64) as usize];
+    let mut progress = Progress::new(stdout, "Splitting", manifest.file_len);
+    let mut remaining = manifest.file_len;
+    let mut index = 0;
+    while remaining > 0 {
+        let len = remaining.min(stripe_size);
+        copy_part(file, &mut outputs[index], len, &mut buffer, |chunk| {
+            file_hasher.update(chunk);
+            part_hashers[index].update(chunk);
+            if let Some(sha256) = &mut sha256s[index] {
+                sha256.update(chunk);
+            }
+            progress.advance(chunk.len() as u64);
+        })?;
+        remaining -= len;
+        index = (index + 1) % part_count;
+    }
+
+    // This waits for the threads to write what is left and reports what went wrong on them.
+    for output in &mut outputs {
+        output
+            .flush()
+            .map_err(Error::io("Failed to write output."))?;
+    }
+    let sfv_parts = manifest
+        .parts
+        .iter()
+        .zip(outputs)
+        .map(|(part, output)| (part.file_name.clone(), output.finalize()))
+        .collect();
+
+    progress.finish();
+
+    let checksums = Checksums {
+        parts: part_hashers
+            .into_iter()
+            .map(|hasher| hasher.finalize())
+            .collect(),
+        stored_sha256s: sha256s
+            .into_iter()
+            .map(|sha256| sha256.map(|sha256| crate::to_hex(&sha256.finalize())))
+            .collect(),
+        file: file_hasher.finalize(),
+    };
+    Ok((checksums, sfv_parts))
+}
+
+/// Adds the `checksums` to the `manifest` and writes it into `split_folder`,
+/// along with the SFV file made of `sfv_parts` and the SHA256SUMS file if they were asked for.
+/// `encoded_sha256s` are the SHA-256 checksums of the parts as they are stored, if they are compressed or encrypted.
+fn write_checksums(
+    split_folder: &Path,
+    manifest: &mut Manifest,
+    checksums: Checksums,
+    sfv_parts: Vec<(String, u32)>,
+    encoded_sha256s: Vec<Option<String>>,
+    options: &Options,
+) -> Result<(), Error> {
+    let mut sha256sums_parts = Vec::<(String, String)>::with_capacity(manifest.parts.len());
+    for ((part, checksum), (encoded_sha256, stored_sha256)) in manifest
+        .parts
+        .iter_mut()
+        .zip(checksums.parts)
+        .zip(encoded_sha256s.into_iter().zip(checksums.stored_sha256s))
+    {
+        part.checksum = Some(checksum);
+        if let Some(sha256) = encoded_sha256.or(stored_sha256) {
+            sha256sums_parts.push((part.file_name.clone(), sha256));
+        }
+    }
+    manifest.checksum = Some(checksums.file);
+    // A manifest next to unrelated files could be mistaken for the manifest of another split.
+    if !options.no_folder {
+        manifest.write(split_folder)?;
+    }
+
+    if options.sfv {
+        sfv::write(
+            &split_folder.join(sfv::file_name(&manifest.file_name)),
+            &sfv_parts,
+        )?;
+    }
+
+    if options.sha256sums {
+        sha256sums::write(&split_folder.join(sha256sums::FILE_NAME), &sha256sums_parts)?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure that the split folder `split_folder` of an interrupted split is for the same parts as the `manifest`,
+/// so that the split can be resumed.
+fn check_resumable(split_folder: &Path, manifest: &Manifest) -> Result<(), Error> {
+    let previous_manifest = Manifest::read(split_folder)?.ok_or_else(|| {
+        Error(
+            format!(
+                "The split in {} cannot be resumed because it has no {}.",
+                split_folder.to_string_lossy(),
+                manifest::FILE_NAME
+            )
+            .into(),
+        )
+    })?;
+
+    let is_same_split = previous_manifest.file_name == manifest.file_name
+        && previous_manifest.file_len == manifest.file_len
+        && previous_manifest.compression == manifest.compression
+        && previous_manifest.csv_header_len == manifest.csv_header_len
+        && previous_manifest.parts.len() == manifest.parts.len()
+        && previous_manifest
+            .parts
+            .iter()
+            .zip(&manifest.parts)
+            .all(|(previous_part, part)| {
+                previous_part.file_name == part.file_name && previous_part.len == part.len
+            });
+
+    if is_same_split {
+        Ok(())
+    } else {
+        Err(Error(
+            format!(
+                "The split in {} cannot be resumed because it was made with a different file or split size.",
+                split_folder.to_string_lossy()
+            )
+            .into(),
+        ))
+    }
+}
+
+/// Returns the filenames of the `part_count` parts that are named after `file_name`.
+fn part_file_names(
+    file_name: &str,
+    part_count: usize,
+    name_template: &str,
+    options: &Options,
+) -> Result<Vec<String>, Error> {
+    // The trailing numbers are zero-padded so that the parts are listed in order when sorted by name.
+    // Alphabetic suffixes all have the same length for the same reason.
+    let (min_number_width, default_number_width) = match options.format {
+        NameFormat::Numbered => (part_count.to_string().len(), part_count.to_string().len()),
+        NameFormat::Gnu => (min_gnu_suffix_len(part_count), gnu_suffix_len(part_count)),
+        NameFormat::Extension => (
+            part_count.to_string().len(),
+            part_count.to_string().len().max(3),
+        ),
+    };
+    let number_width = match options.suffix_length {
+        Some(suffix_length) if suffix_length < min_number_width => {
+            return Err(Error(
+                format!(
+                    "A suffix length of {} is too short for {} parts.",
+                    suffix_length, part_count
+                )
+                .into(),
+            ))
+        }
+        Some(suffix_length) => suffix_length,
+        None => default_number_width,
+    };
+
+    Ok((1..=part_count)
+        .map(|index| {
+            let index = match options.format {
+                NameFormat::Numbered | NameFormat::Extension => {
+                    format!("{:0width$}", index, width = number_width)
+                }
+                NameFormat::Gnu => gnu_suffix(index, number_width),
+            };
+            let file_name = format_part_name(name_template, file_name, &index, part_count);
+
+            let file_name = match options.compress {
+                Some(compression) => file_name + compression.extension(),
+                None => file_name,
+            };
+            if options.base64 {
+                file_name + Encoding::Base64.extension()
+            } else {
+                file_name
+            }
+        })
+        .collect())
+}
+
+/// Returns the folder that the split folder of the file at `path` is created in.
+/// This is next to the file unless another folder was chosen.
+fn get_parent_folder(path: &Path, options: &Options) -> PathBuf {
+    match &options.output_dir {
+        Some(output_dir) => output_dir.clone(),
+        None => path.parent().unwrap_or_else(|| Path::new("")).to_path_buf(),
+    }
+}
+
+/// Returns the path of the split folder of the file at `path`.
+/// If that already exists, another one is picked unless it is replaced with `--force`.
+pub fn get_split_folder(path: &Path, options: &Options) -> Result<PathBuf, Error> {
+    let file_name = crate::get_file_name(path)?;
+    Ok(get_parent_folder(path, options).join(format!("{}-split", get_base_name(&file_name))))
+}
+
+/// Returns the first of `<name>-split`, `<name>-split-2`, `<name>-split-3` and so on in `parent_folder` that does not exist yet.
+///
+/// The parts in the folder are still named after the file, so the folder name does not matter for joining.
+fn find_free_split_folder(parent_folder: &Path, name: &str) -> Result<PathBuf, Error> {
+    let split_folder = parent_folder.join(format!("{}-split", name));
+    if !split_folder.exists() {
+        return Ok(split_folder);
+    }
+
+    (2..=MAX_SPLIT_FOLDERS)
+        .map(|number| parent_folder.join(format!("{}-split-{}", name, number)))
+        .find(|split_folder| !split_folder.exists())
+        .ok_or_else(|| {
+            Error(
+                format!(
+                    "Folders {}-split to {}-split-{} already exist. Please remove previous split folders or use --force.",
+                    name, name, MAX_SPLIT_FOLDERS
+                )
+                .into(),
+            )
+        })
+}
+
+/// Returns the path of the hidden folder next to `split_folder` that a split is written to before it gets its name.
+fn get_temporary_split_folder(split_folder: &Path) -> PathBuf {
+    let name = split_folder
+        .file_name()
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default();
+    split_folder.with_file_name(format!(".{}.partial", name))
+}
+
+/// Returns the path of the hidden folder next to `split_folder` that a previous split folder is moved to while it is replaced.
+fn get_replaced_split_folder(split_folder: &Path) -> PathBuf {
+    let name = split_folder
+        .file_name()
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default();
+    split_folder.with_file_name(format!(".{}.replaced", name))
+}
+
+/// Runs `write` to write the split of the file called `file_name` into a temporary folder next to `split_folder`,
+/// which only gets the name of the split folder once the parts, the manifest and everything else were written.
+/// If anything fails, the temporary folder is removed, so that a split either succeeds or leaves nothing behind.
+/// With `--resume`, it is kept instead, so that the split can be resumed again.
+///
+/// A temporary folder that is left over because splitting was interrupted, such as with Ctrl+C,
+/// is continued with `--resume` and replaced otherwise.
+/// A previous split folder is only replaced with `--force`, once the new split is complete.
+/// It is moved aside before the new one gets its name and only removed after that, so that there always is one of them.
+fn write_atomically<F: FnOnce(PathBuf) -> Result<SplitReport, Error>>(
+    split_folder: &Path,
+    file_name: &str,
+    options: &Options,
+    write: F,
+) -> Result<SplitReport, Error> {
+    let temporary_folder = get_temporary_split_folder(split_folder);
+    let resuming = options.resume && temporary_folder.is_dir();
+
+    // Splits that were interrupted before they were written to a temporary folder are resumed where they are.
+    if options.resume && !resuming && split_folder.is_dir() {
+        return write(split_folder.to_path_buf());
+    }
+
+    if split_folder.exists() {
+        if !options.force {
+            return Err(Error(
+                format!(
+                    "Folder {} already exists. Please remove the previous split folder or use --force.",
+                    split_folder.to_string_lossy()
+                )
+                .into(),
+            ));
+        }
+        check_replaceable(split_folder, file_name, options)?;
+    }
+    if !resuming && temporary_folder.exists() {
+        check_replaceable(&temporary_folder, file_name, options)?;
+        fs::remove_dir_all(&temporary_folder)?;
+    }
+
+    match write(temporary_folder.clone()) {
+        Ok(mut report) => {
+            replace_split_folder(&temporary_folder, split_folder)?;
+            report.split_folder = split_folder.to_path_buf();
+            Ok(report)
+        }
+        Err(err) if options.resume => Err(Error(
+            format!(
+                "{}\n\nThe parts written so far were kept in {}. Run the split again with --resume to continue it.",
+                err,
+                temporary_folder.to_string_lossy()
+            )
+            .into(),
+        )),
+        Err(err) => {
+            // If this fails too, the folder is hidden and replaced by the next split anyway.
+            let _ = fs::remove_dir_all(&temporary_folder);
+            Err(err)
+        }
+    }
+}
+
+/// Gives the complete split in `temporary_folder` the name `split_folder`, replacing the previous split folder if there is one.
+fn replace_split_folder(temporary_folder: &Path, split_folder: &Path) -> Result<(), Error> {
+    let rename = |from: &Path, to: &Path| {
+        fs::rename(from, to).map_err(Error::io(format!(
+            "Failed to rename {} to {}.",
+            from.to_string_lossy(),
+            to.to_string_lossy()
+        )))
+    };
+
+    if !split_folder.exists() {
+        return rename(temporary_folder, split_folder);
+    }
+
+    // A folder left over from a replacement that was interrupted was already replaced.
+    let replaced_folder = get_replaced_split_folder(split_folder);
+    if replaced_folder.exists() {
+        fs::remove_dir_all(&replaced_folder)?;
+    }
+    rename(split_folder, &replaced_folder)?;
+    if let Err(err) = rename(temporary_folder, split_folder) {
+        // The previous split folder is put back so that it is not lost. If that fails too, it is still there under the other name.
+        let _ = fs::rename(&replaced_folder, split_folder);
+        return Err(err);
+    }
+
+    // The new split is complete at this point, and the folder is hidden and removed by the next replacement anyway.
+    let _ = fs::remove_dir_all(&replaced_folder);
+    Ok(())
+}
+
+/// Makes sure that the folder `dir` only contains parts of the file called `file_name`
+/// and the files describing them, so that it can be replaced.
+fn check_replaceable(dir: &Path, file_name: &str, options: &Options) -> Result<(), Error> {
+    if is_split_folder(dir, file_name, options.separator())? {
+        Ok(())
+    } else {
+        Err(Error(
+            format!(
+                "Folder {} already exists and contains files that are not parts of {}. Refusing to remove it.",
+                dir.to_string_lossy(),
+                file_name
+            )
+            .into(),
+        ))
+    }
+}
+
+/// Creates the split folder. If it already exists and `--force` is given, it is replaced,
+/// but only if it looks like a previous split folder.
+fn create_split_folder(
+    split_folder: &Path,
+    file_name: &str,
+    options: &Options,
+) -> Result<(), Error> {
+    match fs::create_dir(split_folder) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            if !options.force {
+                return Err(Error(
+                    format!(
+                        "Folder {} already exists. Please remove the previous split folder or use --force.",
+                        split_folder.to_string_lossy()
+                    )
+                    .into(),
+                ));
+            }
+
+            if !is_split_folder(split_folder, file_name, options.separator())? {
+                return Err(Error(
+                    format!(
+                        "Folder {} already exists and contains files that are not parts of {}. Refusing to remove it.",
+                        split_folder.to_string_lossy(),
+                        file_name
+                    )
+                    .into(),
+                ));
+            }
+
+            fs::remove_dir_all(split_folder)?;
+            fs::create_dir(split_folder).map_err(Error::io(format!(
+                "Failed to create folder {}.",
+                split_folder.to_string_lossy()
+            )))
+        }
+        Err(err) => Err(Error::io(format!(
+            "Failed to create folder {}.",
+            split_folder.to_string_lossy()
+        ))(err)),
+    }
+}
+
+/// Returns whether `dir` is a folder that only contains parts of the file called `file_name`
+/// and the files describing them such as the manifest, which means that it is safe to remove.
+/// The trailing numbers of the parts follow `separator`.
+fn is_split_folder(dir: &Path, file_name: &str, separator: &str) -> Result<bool, Error> {
+    if !dir.is_dir() {
+        return Ok(false);
+    }
+
+    // Parts with custom names are only recognized by being listed in the manifest.
+    let listed_parts = match Manifest::read(dir) {
+        Ok(Some(manifest)) if manifest.file_name != file_name => return Ok(false),
+        Ok(Some(manifest)) => manifest
+            .parts
+            .into_iter()
+            .map(|part| part.file_name)
+            .collect(),
+        _ => Vec::new(),
+    };
+    let part_prefix = format!("{}{}", file_name, separator);
+
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        let is_manifest = path.file_name() == Some(OsStr::new(manifest::FILE_NAME));
+        let is_sfv = path.file_name() == Some(OsStr::new(&sfv::file_name(file_name)));
+        let is_sha256sums = path.file_name() == Some(OsStr::new(sha256sums::FILE_NAME));
+        let is_join_script = join_script::is_join_script(&path);
+        let is_listed = listed_parts
+            .iter()
+            .any(|part| path.file_name() == Some(OsStr::new(part)));
+
+        let is_part = join::is_part(&path, separator)
+            && path
+                .file_name()
+                .and_then(OsStr::to_str)
+                .is_some_and(|name| name.starts_with(&part_prefix));
+
+        if !path.is_file()
+            || !(is_manifest || is_sfv || is_sha256sums || is_join_script || is_listed || is_part)
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Copies exactly `len` bytes from `reader` to `writer` using `buffer`.
+/// Every chunk that is copied is also passed to `inspect`.
+///
+/// A single read is allowed to return fewer bytes than requested so this keeps reading until
+/// `len` bytes were copied. If `reader` ends before that, an error is returned.
+fn copy_part<R: Read, W: Write, F: FnMut(&[u8])>(
+    reader: &mut R,
+    writer: &mut W,
+    len: u64,
+    buffer: &mut [u8],
+    inspect: F,
+) -> Result<(), Error> {
+    if copy_up_to(reader, writer, len, buffer, inspect)? == len {
+        Ok(())
+    } else {
+        Err(Error(
+            "The file ended unexpectedly. It might have been modified while splitting.".into(),
+        ))
+    }
+}
+
+/// Copies `len` bytes from `reader` to `writer` using `buffer`, or less if `reader` ends before that,
+/// and returns how many bytes were copied.
+/// Every chunk that is copied is also passed to `inspect`.
+fn copy_up_to<R: Read, W: Write, F: FnMut(&[u8])>(
+    reader: &mut R,
+    writer: &mut W,
+    len: u64,
+    buffer: &mut [u8],
+    mut inspect: F,
+) -> Result<u64, Error> {
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let chunk_len = remaining.min(buffer.len() as u64) as usize;
+        let read = match reader.read(&mut buffer[..chunk_len]) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::io("Failed reading file.")(err)),
+        };
+        inspect(&buffer[..read]);
+        writer
+            .write_all(&buffer[..read])
+            .map_err(Error::io("Failed to write output."))?;
+        remaining -= read as u64;
+    }
+
+    Ok(len - remaining)
+}
+
+/// Compresses what is read from `reader` into `encoder` until `reader` ends or the compressed data
+/// would get longer than `max_len` bytes once the encoder is finished, and returns how many bytes were read.
+/// Every chunk that is read is also passed to `inspect`.
+///
+/// The encoder is not flushed, as every flush makes the compression worse, except when what it might still hold back
+/// could otherwise no longer fit into `max_len` even if it cannot be compressed at all.
+/// Only then is it flushed to find out how long the compressed data really is.
+fn copy_compressed<R: BufRead, W: Write, F: FnMut(&[u8])>(
+    reader: &mut R,
+    encoder: &mut Encoder<CountingWriter<W>>,
+    max_len: u64,
+    mut inspect: F,
+) -> Result<u64, Error> {
+    // Data that cannot be compressed grows by a little for every block, plus the header.
+    let max_compressed_len = |len: u64| len * 129 / 128 + 64;
+
+    let mut len = 0;
+    // What was written to the encoder since it was last flushed, which might not have been compressed yet.
+    let mut unflushed_len = 0;
+
+    loop {
+        let chunk = reader
+            .fill_buf()
+            .map_err(Error::io("Failed to read input."))?;
+        if chunk.is_empty() {
+            break;
+        }
+
+        let remaining = max_len.saturating_sub(encoder.get_ref().len + COMPRESSED_TRAILER_LEN);
+        let chunk_len = if max_compressed_len(unflushed_len + chunk.len() as u64) <= remaining {
+            chunk.len()
+        } else {
+            if unflushed_len != 0 {
+                encoder
+                    .flush()
+                    .map_err(Error::io("Failed to write output."))?;
+                unflushed_len = 0;
+                continue;
+            }
+
+            // Each chunk is only as long as still fits into what is left of `max_len`.
+            let max_chunk_len = remaining.saturating_sub(64) * 128 / 129;
+            if max_chunk_len == 0 {
+                break;
+            }
+            chunk.len().min(max_chunk_len as usize)
+        };
+        let chunk = &chunk[..chunk_len];
+
+        inspect(chunk);
+        encoder
+            .write_all(chunk)
+            .map_err(Error::io("Failed to write output."))?;
+
+        reader.consume(chunk_len);
+        len += chunk_len as u64;
+        unflushed_len += chunk_len as u64;
+    }
+
+    Ok(len)
+}
+
+/// Writes to a part that was already written before, but only from where it first differs from what is written to it.
+/// Until then, what is written is compared with what is in the part.
+struct RepairingWriter {
+    file: fs::File,
+    buffer: Vec<u8>,
+    differs: bool,
+}
+
+impl RepairingWriter {
+    fn new(file: fs::File) -> Self {
+        RepairingWriter {
+            file,
+            buffer: Vec::new(),
+            differs: false,
+        }
+    }
+}
+
+impl Write for RepairingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.differs {
+            let position = self.file.stream_position()?;
+            self.buffer.resize(buf.len(), 0);
+            match self.file.read_exact(&mut self.buffer) {
+                Ok(()) if self.buffer == buf => return Ok(buf.len()),
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+                Err(err) => return Err(err),
+            }
+            self.differs = true;
+            self.file.seek(SeekFrom::Start(position))?;
+        }
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Passes everything written to it on to `inner` and counts how many bytes that were.
+struct CountingWriter<W> {
+    inner: W,
+    len: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        CountingWriter { inner, len: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Divides `file_len` into parts of exactly `split_size` in order, except for the last part
+/// which holds whatever remains. This is what `split -b` does.
+///
+/// This is the default way of splitting. Part `n` holds the `n`th `split_size` bytes of the file.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let parts = split::fixed_chunks(10, 3);
+///
+/// assert_eq!(parts, [3, 3, 3, 1]);
+/// // Without a remainder, there is no shorter last part.
+/// assert_eq!(split::fixed_chunks(9, 3), [3, 3, 3]);
+/// assert_eq!(split::fixed_chunks(11, 5), [5, 5, 1]);
+/// assert_eq!(split::fixed_chunks(5, 5), [5]);
+/// ```
+pub fn fixed_chunks(file_len: u64, split_size: u64) -> Vec<u64> {
+    let mut parts = vec![split_size; (file_len / split_size) as usize];
+
+    let remainder = file_len % split_size;
+    if remainder != 0 {
+        parts.push(remainder);
+    }
+
+    parts
+}
+
+/// Divides `file_len` into `part_count` parts whose lengths differ by 1 byte at most.
+/// The first parts are the longer ones, so that parts are never longer than the ones before them.
+///
+/// This is what splitting into a number of parts with `--parts` or `/4` does.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// assert_eq!(split::balanced_parts(9, 4), [3, 2, 2, 2]);
+/// assert_eq!(split::balanced_parts(10, 4), [3, 3, 2, 2]);
+/// assert_eq!(split::balanced_parts(12, 4), [3, 3, 3, 3]);
+/// ```
+pub fn balanced_parts(file_len: u64, part_count: u64) -> Vec<u64> {
+    let (len, remainder) = (file_len / part_count, file_len % part_count);
+
+    (0..part_count)
+        .map(|index| if index < remainder { len + 1 } else { len })
+        .collect()
+}
+
+/// Deals `file_len` out to `part_count` parts in stripes of `stripe_size` bytes and returns how long the parts are.
+/// The first stripe goes to the first part, the second one to the second part and so on,
+/// and after the last part, the next stripe goes to the first part again. Only the very last stripe can be shorter.
+///
+/// This is only used with `--stripe`, so that parts written to different drives at once are all written to all the time.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let parts = split::stripe_parts(10, 2, 2);
+///
+/// assert_eq!(parts, [6, 4]);
+///
+/// let parts = split::stripe_parts(11, 2, 3);
+///
+/// assert_eq!(parts, [4, 4, 3]);
+/// ```
+pub fn stripe_parts(file_len: u64, stripe_size: u64, part_count: usize) -> Vec<u64> {
+    // Every round deals one stripe to every part.
+    let (rounds, mut remainder) = match stripe_size.checked_mul(part_count as u64) {
+        Some(round_len) => (file_len / round_len, file_len % round_len),
+        None => (0, file_len),
+    };
+
+    (0..part_count)
+        .map(|_| {
+            let last_stripe = remainder.min(stripe_size);
+            remainder -= last_stripe;
+            rounds * stripe_size + last_stripe
+        })
+        .collect()
+}
+
+/// Divides the content of `reader` into parts of at most `split_size` bytes that each end after a newline,
+/// except for the last part which holds whatever remains.
+///
+/// This is only used with `--lines`. A line that is longer than `split_size` on its own is not cut
+/// but becomes a part of its own that is longer than `split_size`.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let parts = split::line_parts(&b"ab\ncd\nefgh\nij"[..], 5)?;
+///
+/// assert_eq!(parts, [3, 3, 5, 2]);
+///
+/// let parts = split::line_parts(&b"a\nbbbbbbbb\nc"[..], 4)?;
+///
+/// assert_eq!(parts, [2, 9, 1]);
+/// # Ok::<(), splitter::Error>(())
+/// ```
+pub fn line_parts<R: BufRead>(reader: R, split_size: u64) -> Result<Vec<u64>, Error> {
+    delimiter_parts(reader, b"\n", split_size, false)
+}
+
+/// Divides the content of `reader` into parts of at most `split_size` bytes that each end after the `delimiter`,
+/// except for the last part which holds whatever remains. This is [`line_parts`] with any delimiter instead of a newline.
+///
+/// This is only used with `--delimiter`. If a record, which ends with the `delimiter`, is longer than `split_size`
+/// on its own, it becomes a part of its own unless `strict` is set, in which case this fails.
+///
+/// The content is searched as it is read, so a delimiter can be split across reads.
+/// The delimiters do not overlap: the search goes on after the end of the one that was found.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let parts = split::delimiter_parts(&b"aa::bbbb::cc::d"[..], b"::", 7, false)?;
+///
+/// assert_eq!(parts, [4, 6, 5]);
+///
+/// assert!(split::delimiter_parts(&b"aaaaaaaa::b"[..], b"::", 7, true).is_err());
+/// # Ok::<(), splitter::Error>(())
+/// ```
+pub fn delimiter_parts<R: BufRead>(
+    mut reader: R,
+    delimiter: &[u8],
+    split_size: u64,
+    strict: bool,
+) -> Result<Vec<u64>, Error> {
+    let no_delimiter = |part_start: u64| {
+        Error(
+            format!(
+                "No delimiter was found within {} bytes from byte {} on. Please use a bigger split size or leave out --strict to make such records parts of their own.",
+                split_size, part_start
+            )
+            .into(),
+        )
+    };
+
+    let mut parts = Vec::<u64>::new();
+    // The content that is searched for the delimiter: what was read last,
+    // after the end of what was read before in case a delimiter started there.
+    let mut window = Vec::<u8>::new();
+
+    // These are positions in the content: where the current part starts,
+    // and the end of its last record that still fits into it.
+    let mut part_start = 0;
+    let mut last_record_end = 0;
+    let mut position = 0;
+
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::io("Failed reading file.")(err)),
+        };
+        let read = chunk.len();
+
+        // A whole delimiter cannot be in what is kept, so none is found twice.
+        let kept = window.len().min(delimiter.len() - 1);
+        window.drain(..window.len() - kept);
+        window.extend_from_slice(chunk);
+        reader.consume(read);
+        let window_start = position - kept as u64;
+        position += read as u64;
+
+        for offset in 0..window.len() {
+            let delimiter_start = window_start + offset as u64;
+            if delimiter_start < last_record_end || !window[offset..].starts_with(delimiter) {
+                continue;
             }
+            let record_end = delimiter_start + delimiter.len() as u64;
+
+            if record_end - part_start > split_size {
+                // The record does not fit anymore, so the part ends before it, if it has any records at all.
+                if last_record_end > part_start {
+                    parts.push(last_record_end - part_start);
+                    part_start = last_record_end;
+                }
+
+                // A record that does not fit into any part is not cut.
+                if record_end - part_start > split_size {
+                    if strict {
+                        return Err(no_delimiter(part_start));
+                    }
+                    parts.push(record_end - part_start);
+                    part_start = record_end;
+                }
+            }
+
+            last_record_end = record_end;
         }
     }
 
-    let mut input = String::new();
+    // The last record might not end with a delimiter. It is handled like any other record.
+    if position - part_start > split_size && last_record_end > part_start {
+        parts.push(last_record_end - part_start);
+        part_start = last_record_end;
+    }
+    if strict && position - part_start > split_size {
+        return Err(no_delimiter(part_start));
+    }
+    if position > part_start {
+        parts.push(position - part_start);
+    }
 
-    loop {
-        write!(stdout, "Split size:  ")?;
-        stdout.flush()?;
+    Ok(parts)
+}
 
-        match parse_split_size(stdin, &mut input) {
-            Ok(split_size) => break Ok(split_size),
-            Err(err) => writeln!(stderr, "{}. Please try again.", err)?,
+/// Divides the content of `reader`, which is `len` bytes of UTF-8 text, into parts of at most `split_size` bytes
+/// that each end between two characters, except for the last part which holds whatever remains.
+///
+/// This is only used with `--text`. A part that would end in the middle of a character instead ends before it,
+/// so every part is valid UTF-8 on its own. Only if that would leave nothing in the part, it ends after the character.
+/// No bytes are added or removed, so joining the parts still restores the text exactly.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// # use std::io::Cursor;
+/// // "é" is 2 bytes and "€" is 3 bytes long, so the boundaries after 4 and 8 bytes are moved back.
+/// let text = "abcé€€";
+/// let parts = split::text_parts(Cursor::new(text), text.len() as u64, 4)?;
+///
+/// assert_eq!(parts, [3, 2, 3, 3]);
+///
+/// // A 4-byte character does not fit into a part of 2 bytes at all.
+/// let text = "a😀b";
+/// let parts = split::text_parts(Cursor::new(text), text.len() as u64, 2)?;
+///
+/// assert_eq!(parts, [1, 4, 1]);
+///
+/// for part in [&text[..1], &text[1..5], &text[5..]] {
+///     assert!(std::str::from_utf8(part.as_bytes()).is_ok());
+/// }
+/// # Ok::<(), splitter::Error>(())
+/// ```
+pub fn text_parts<R: Read + Seek>(
+    mut reader: R,
+    len: u64,
+    split_size: u64,
+) -> Result<Vec<u64>, Error> {
+    /// A UTF-8 character is at most this long, so its start is at most this far away from any of its bytes, minus 1.
+    const MAX_CHAR_LEN: u64 = 4;
+
+    let mut parts = Vec::<u64>::new();
+    let mut part_start = 0;
+
+    while len - part_start > split_size {
+        let nominal_end = part_start + split_size;
+
+        // Only the bytes around the boundary are read, which covers the characters on both sides of it.
+        let window_start = nominal_end.saturating_sub(MAX_CHAR_LEN - 1).max(part_start);
+        let mut window = [0_u8; 2 * MAX_CHAR_LEN as usize - 1];
+        let window = &mut window[..(len - window_start).min(2 * MAX_CHAR_LEN - 1) as usize];
+        reader
+            .seek(SeekFrom::Start(window_start))
+            .and_then(|_| reader.read_exact(window))
+            .map_err(Error::io("Failed reading file."))?;
+
+        // A character starts anywhere but at continuation bytes, which are `0b10xxxxxx`.
+        let is_char_start = |position: u64| {
+            position >= len
+                || window[(position - window_start) as usize] & 0b1100_0000 != 0b1000_0000
         };
 
-        input.clear();
+        let end = (0..MAX_CHAR_LEN)
+            .map(|back| nominal_end - back)
+            .take_while(|end| *end > part_start)
+            .find(|end| is_char_start(*end))
+            .or_else(|| {
+                (1..MAX_CHAR_LEN)
+                    .map(|forward| nominal_end + forward)
+                    .find(|end| is_char_start(*end))
+            })
+            // This is not UTF-8, so there is no character to keep together.
+            .unwrap_or(nominal_end);
+
+        parts.push(end - part_start);
+        part_start = end;
+    }
+
+    if len > part_start {
+        parts.push(len - part_start);
     }
+
+    Ok(parts)
 }
 
-pub fn split(
-    stdin: &mut io::StdinLock,
-    stdout: &mut io::StdoutLock,
-    stderr: &mut io::StderrLock,
-    mut path_buf: PathBuf,
-) -> Result<Cow<'static, str>, Error> {
-    // NOTE: this is one of those cases where I would like to let the error run through the
-    //       logic of `From<io::Error>::from` first to be able to provide more information
-    //       if for instance the permission is denied. I haven't yet found a very idiomatic way to do that
-    //       so for now all errors of this operation will result in only this error message
-    let mut file = fs::File::open(&path_buf).map_err(|_| Error("Failed to open file.".into()))?;
+/// Divides the content of `reader` into parts of `lines_per_part` lines each,
+/// except for the last part which holds whatever remains. This is what `split -l` does.
+///
+/// This is only used with `--lines-per-part`. Lines end after a newline, so `\r\n` line endings are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let parts = split::line_count_parts(&b"a\r\nb\r\ncc\r\nd"[..], 2)?;
+///
+/// assert_eq!(parts, [6, 5]);
+/// # Ok::<(), splitter::Error>(())
+/// ```
+pub fn line_count_parts<R: BufRead>(
+    mut reader: R,
+    lines_per_part: usize,
+) -> Result<Vec<u64>, Error> {
+    let mut parts = Vec::<u64>::new();
 
-    let file_len = file.metadata()?.len();
+    let mut part_start = 0;
+    let mut line_count = 0;
+    let mut position = 0;
 
-    writeln!(stdout, "File length: {}", file_len)?;
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::io("Failed reading file.")(err)),
+        };
+        let read = chunk.len();
 
-    let split_size = get_split_size(stdin, stdout, stderr)?;
+        for (offset, _) in chunk.iter().enumerate().filter(|(_, byte)| **byte == b'\n') {
+            line_count += 1;
 
-    if file_len < split_size {
-        return Err(Error(
-            "File length is below split length. Nothing to split.".into(),
-        ));
+            if line_count == lines_per_part {
+                let line_end = position + offset as u64 + 1;
+                parts.push(line_end - part_start);
+                part_start = line_end;
+                line_count = 0;
+            }
+        }
+
+        reader.consume(read);
+        position += read as u64;
+    }
+
+    if position > part_start {
+        parts.push(position - part_start);
     }
 
-    let mut buffers = get_buffers(file_len, split_size);
+    Ok(parts)
+}
 
-    let buffers = &mut buffers
-        .iter_mut()
-        .map(|buffer| io::IoSliceMut::new(buffer))
-        .collect::<Vec<io::IoSliceMut>>();
+/// Makes sure that the records at the ends of the `parts` of the content of `reader`, which is JSON Lines, are valid JSON.
+///
+/// This is only used with `--jsonl`. Only the records at the ends are checked because those are the ones that show
+/// whether the parts really end between records. It still catches corruption early, before any part is written.
+/// Blank lines are not records and are skipped.
+fn check_jsonl_boundaries<R: BufRead>(mut reader: R, parts: &[u64]) -> Result<(), Error> {
+    let mut part_ends = parts.iter().scan(0, |end, part| {
+        *end += part;
+        Some(*end)
+    });
+    let mut part_end = part_ends.next();
+    let mut line = Vec::<u8>::new();
+    let mut line_number = 0;
+    let mut position = 0;
+    // The first record of a part is checked, as is the last one.
+    let mut is_first_record = true;
 
-    // This will read the file's content into a buffer until that buffer is full, then it goes on to the next buffer and so on until all buffers are filled.
-    // The buffer sizes are determined by `get_buffers` or more specifically `split_parts`.
-    // The buffer sizes are based on this `file`'s length so at the end there will be nothing left to read and all buffers will be perfectly filled.
-    file.read_vectored(buffers)
-        .map_err(|_| Error("Failed reading file.".into()))?;
+    while let Some(end) = part_end {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(Error::io("Failed reading file."))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+        position += read as u64;
 
-    let mut path_os_string = path_buf.clone().into_os_string();
-    path_os_string.push("-split");
+        let is_last_record = position >= end;
+        let is_blank = line.iter().all(u8::is_ascii_whitespace);
 
-    path_buf.set_file_name(path_os_string);
+        if (is_first_record || is_last_record) && !is_blank {
+            if let Err(err) = serde_json::from_slice::<serde::de::IgnoredAny>(&line) {
+                return Err(Error(
+                    format!(
+                        "Line {} is not valid JSON, so the file might be corrupted: {}.",
+                        line_number, err
+                    )
+                    .into(),
+                ));
+            }
+        }
 
-    fs::create_dir(&path_buf).map_err(|_| {
-        Error(
-            format!(
-                "Folder {} already exists. Please remove the previous split folder.",
-                path_buf.to_string_lossy()
-            )
-            .into(),
-        )
-    })?;
+        if !is_blank {
+            is_first_record = false;
+        }
+        if is_last_record {
+            part_end = part_ends.next();
+            is_first_record = true;
+        }
+    }
 
-    let mut open_options = fs::OpenOptions::new();
-    open_options.write(true).create_new(true);
+    Ok(())
+}
+
+/// Divides the content of `reader`, which is CSV, into parts of whole records
+/// and returns the header, which is the first record, along with the lengths of the parts.
+///
+/// This is only used with `--csv`. Every part but the first one gets a copy of the header,
+/// so the parts are filled with records until they would be longer than `split_size` together with the header.
+/// The first part already starts with the header. A record that is longer than that on its own becomes a part of its own.
+///
+/// Records end after a newline that is not inside a quoted field, so fields with newlines in them are never cut.
+///
+/// # Examples
+///
+/// ```
+/// # use splitter::split;
+/// let (header, parts) = split::csv_parts(&b"id,text\n1,a\n2,\"b\nc\"\n3,d\n"[..], 14)?;
+///
+/// assert_eq!(header, b"id,text\n");
+/// assert_eq!(parts, [12, 8, 4]);
+/// # Ok::<(), splitter::Error>(())
+/// ```
+pub fn csv_parts<R: BufRead>(mut reader: R, split_size: u64) -> Result<(Vec<u8>, Vec<u64>), Error> {
+    let mut parts = Vec::<u64>::new();
+
+    let mut header = Vec::<u8>::new();
+    let mut header_ended = false;
+    // How long the records of a part can be together, which is whatever the header leaves.
+    let mut records_size = split_size;
+    let mut quoted = false;
+
+    // Like in `line_parts`, these are positions in the content. The header is left out of the first part until the end.
+    let mut part_start = 0;
+    let mut last_record_end = 0;
+    let mut position = 0;
+
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::io("Failed reading file.")(err)),
+        };
+        let read = chunk.len();
+
+        for (offset, byte) in chunk.iter().enumerate() {
+            if !header_ended {
+                header.push(*byte);
+            }
+
+            match byte {
+                // An escaped quote inside a quoted field is written as two quotes, so it leaves the field quoted.
+                b'"' => quoted = !quoted,
+                b'\n' if !quoted => {
+                    let record_end = position + offset as u64 + 1;
+
+                    if !header_ended {
+                        header_ended = true;
+                        if record_end >= split_size {
+                            return Err(Error(
+                                format!(
+                                    "The CSV header is {} bytes long, so it does not leave room for any records in parts of {} bytes.",
+                                    record_end, split_size
+                                )
+                                .into(),
+                            ));
+                        }
+                        records_size = split_size - record_end;
+                        part_start = record_end;
+                    } else if record_end - part_start > records_size {
+                        if last_record_end > part_start {
+                            parts.push(last_record_end - part_start);
+                            part_start = last_record_end;
+                        }
 
-    for (index, buffer) in buffers.iter().enumerate() {
-        let path_os_string = crate::get_file_name(&path_buf)?;
-        let file_name = format!("{}-{}", path_os_string, index + 1);
-        let mut file = open_options
-            .open(path_buf.join(file_name))
-            .map_err(|_| Error("Failed to create output file.".into()))?;
-        file.write_all(buffer)
-            .map_err(|_| Error("Failed to write output.".into()))?;
+                        if record_end - part_start > records_size {
+                            parts.push(record_end - part_start);
+                            part_start = record_end;
+                        }
+                    }
+
+                    last_record_end = record_end;
+                }
+                _ => {}
+            }
+        }
+
+        reader.consume(read);
+        position += read as u64;
+    }
+
+    // The last record might not end with a newline. It is handled like any other record.
+    if header_ended {
+        if position - part_start > records_size && last_record_end > part_start {
+            parts.push(last_record_end - part_start);
+            part_start = last_record_end;
+        }
+        if position > part_start {
+            parts.push(position - part_start);
+        }
     }
 
-    Ok(format!("Successful split. Split folder: {}\n\nNote that altering the trailing numbers of the filenames may result in corruption when the files are joined.", path_buf.to_string_lossy()).into())
+    match parts.first_mut() {
+        Some(first_part) => *first_part += header.len() as u64,
+        None if !header.is_empty() => parts.push(header.len() as u64),
+        None => {}
+    }
+
+    Ok((header, parts))
 }
 
 /// Splits `parts` until all elements are below `split_size`.
 ///
+/// This is only used with `--halve`. See [`fixed_chunks`] for the default.
+///
+/// The halves are appended, so the lengths are not in any particular order.
+/// That does not matter for joining: the returned lengths are only the lengths of consecutive byte ranges of the file,
+/// so the part with the trailing number `n` always starts where the part `n - 1` ended,
+/// and joining in the order of the trailing numbers restores the file.
+///
 /// # Examples
-//
+///
 /// ```
+/// # use splitter::split;
 /// let parts = split::split_parts(10, 3);
 ///
 /// assert_eq!(parts, [2, 2, 1, 1, 2, 2]);
-/// ```  
+/// ```
 pub fn split_parts(initial_part: u64, split_size: u64) -> Vec<u64> {
+    // No part is ever below 1, so this would never end. See `parse_split_size` and `plan_split`.
+    debug_assert!(split_size > 1);
+
     // NOTE: the algorithm could be more efficient
 
     let mut parts = vec![initial_part];
@@ -151,15 +4136,498 @@ pub fn split_parts(initial_part: u64, split_size: u64) -> Vec<u64> {
     parts
 }
 
-/// Generates a vector of split integers (see [`split_parts`]) based on `file_len` and `split_size` and then
-/// a `Vec::<Vec<u8>>` is made out of it where the integers are replaced with a vector of `n` amount of zeroes.
-fn get_buffers(file_len: u64, split_size: u64) -> Vec<Vec<u8>> {
-    let parts = split_parts(file_len, split_size);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TempDir};
+    use std::fs;
+
+    /// Returns JSON Lines that are also valid CSV, so that every mode that splits between records can split them.
+    fn records() -> Vec<u8> {
+        (0..200)
+            .map(|number| format!("[{},\"{}\"]\n", number, "x".repeat(number % 13)))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    fn honors_buffer_size() {
+        let dir = TempDir::new();
+        let data = records();
+        let path = dir.write("file.txt", &data);
+
+        // The split options, the join options and whether a part is removed to be rebuilt.
+        let mut cases: Vec<(&[&str], &[&str], bool)> = vec![
+            (&["--size", "300"], &[], false),
+            (&["--size", "300", "--halve"], &[], false),
+            (&["--size", "300", "--lines"], &[], false),
+            (&["--size", "300", "--jsonl"], &[], false),
+            (&["--size", "300", "--csv"], &[], false),
+            (&["--size", "300", "--delimiter", "]"], &[], false),
+            (&["--lines-per-part", "30"], &[], false),
+            (&["--size", "300", "--compress=zstd"], &[], false),
+            (&["--size", "300", "--verify"], &[], false),
+            (
+                &["--size", "300", "--sfv", "--sha256sums"],
+                &["--check-sfv"],
+                false,
+            ),
+            (&["--size", "300", "--parity"], &[], true),
+        ];
+        if cfg!(feature = "redundancy") {
+            cases.push((&["--size", "300", "--redundancy", "2"], &[], true));
+        }
+
+        for (number, (split_args, join_args, remove_part)) in cases.into_iter().enumerate() {
+            let out = dir.path().join(number.to_string());
+            let out_arg = out.to_string_lossy().into_owned();
+            let args = [split_args, &["--buffer-size", "7", "--out", &out_arg]].concat();
+            test_util::split(&path, &args).unwrap();
+
+            let split_folder = out.join("file.txt-split");
+            if remove_part {
+                fs::remove_file(&test_util::files_in(&split_folder)[1]).unwrap();
+            }
+
+            let output = out.join("joined");
+            let output_arg = output.to_string_lossy().into_owned();
+            let args = [join_args, &["--buffer-size", "5", "--output", &output_arg]].concat();
+            test_util::join(test_util::files_in(&split_folder), &args).unwrap();
+            assert_eq!(fs::read(&output).unwrap(), data, "{:?}", split_args);
+        }
+    }
+
+    #[test]
+    fn splits_tiny_files() {
+        let dir = TempDir::new();
+        for &len in &[0, 1, 99, 100, 101] {
+            let path = dir.write(&format!("{}.bin", len), &test_util::data(len));
+            let result = test_util::split(&path, &["--size", "100"]);
+
+            match len {
+                0 => assert_eq!(result.err().unwrap().0, "The file is empty. Nothing to split."),
+                1 | 99 => assert_eq!(
+                    result.err().unwrap().0,
+                    format!("The file is only {} bytes long, which is less than the split size of 100 bytes. Nothing to split.", len)
+                ),
+                _ => assert_eq!(result.unwrap().part_lens.unwrap(), fixed_chunks(len as u64, 100)),
+            }
+        }
+    }
+
+    #[test]
+    fn halves_below_split_size() {
+        for &(file_len, split_size) in &[(10, 3), (10, 2), (1000, 7), (1 << 20, 1000)] {
+            let parts = split_parts(file_len, split_size);
+            assert!(parts.iter().all(|part| *part < split_size));
+            assert_eq!(parts.iter().sum::<u64>(), file_len);
+        }
+    }
+
+    #[test]
+    fn does_not_halve_to_split_size_1() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(10));
+
+        let err = test_util::split(&path, &["--size", "1", "--halve"])
+            .err()
+            .unwrap();
+        assert!(err
+            .0
+            .starts_with("--halve cannot be used with a split size of 1 byte"));
+        test_util::split(&path, &["--size", "2", "--halve"]).unwrap();
+    }
+
+    #[test]
+    fn balances_parts() {
+        let dir = TempDir::new();
+        for &(len, args) in &[
+            (9, ["--parts", "4"]),
+            (10, ["--parts", "4"]),
+            (12, ["--parts", "4"]),
+            (9, ["--size", "/4"]),
+            (1001, ["--size", "/10"]),
+        ] {
+            let path = dir.write(&format!("{}.bin", len), &test_util::data(len));
+            let outcome = test_util::split(&path, &args).unwrap();
+            let part_count = args[1].trim_start_matches('/').parse().unwrap();
+            assert_eq!(
+                outcome.part_lens.unwrap(),
+                balanced_parts(len as u64, part_count)
+            );
+
+            let split_folder = outcome.output.unwrap();
+            test_util::round_trip(&split_folder, &test_util::data(len));
+            fs::remove_dir_all(&split_folder).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trips_compressed_parts() {
+        // Noise that hardly compresses, so that parts fill up to the split size.
+        let mut state = 1_u32;
+        let noise = (0..100_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<u8>>();
+        let contents = [test_util::data(200_000), noise].concat();
+
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &contents);
+        for compression in ["--compress=gzip", "--compress=zstd"] {
+            let split_folder = test_util::split(&path, &[compression, "--size", "4096"])
+                .unwrap()
+                .output
+                .unwrap();
+            let parts = test_util::files_in(&split_folder)
+                .into_iter()
+                .filter(|path| path.to_string_lossy().contains("-split-"))
+                .collect::<Vec<PathBuf>>();
+            assert!(parts.len() > 1, "{}", compression);
+            for part in &parts {
+                assert!(fs::metadata(part).unwrap().len() <= 4096, "{}", compression);
+            }
+
+            test_util::round_trip(&split_folder, &contents);
+            fs::remove_dir_all(&split_folder).unwrap();
+        }
+    }
+
+    #[test]
+    fn parses_presets() {
+        for preset in PRESETS {
+            assert_eq!(parse_split_size(preset.name, 0), Ok(preset.split_size));
+            let name = preset.name.to_uppercase();
+            assert_eq!(parse_split_size(&name, 0), Ok(preset.split_size));
+            let options = test_util::options(&["--preset", preset.name]);
+            assert_eq!(options.split_size.as_deref(), Some(preset.name));
+        }
+    }
+
+    /// Reads at most 3 bytes at a time and is interrupted before every other read, like a pipe or a network drive might.
+    struct ShortReader<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for ShortReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+
+            let len = buf.len().min(self.data.len()).min(3);
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn copies_parts_from_short_reads() {
+        let data = test_util::data(100);
+        let mut reader = ShortReader {
+            data: &data,
+            interrupt: false,
+        };
+        let mut buffer = [0; 16];
+
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        copy_part(&mut reader, &mut first, 40, &mut buffer, |_| ()).unwrap();
+        copy_part(&mut reader, &mut second, 50, &mut buffer, |_| ()).unwrap();
+        assert_eq!(first, data[..40]);
+        assert_eq!(second, data[40..90]);
+
+        // Only 10 bytes are left.
+        let err = copy_part(&mut reader, &mut Vec::new(), 20, &mut buffer, |_| ()).unwrap_err();
+        assert!(err.0.starts_with("The file ended unexpectedly."));
+    }
+
+    /// Returns the most memory the process has used so far, in KiB.
+    #[cfg(target_os = "linux")]
+    fn max_rss() -> i64 {
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+        unsafe {
+            assert_eq!(libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()), 0);
+            usage.assume_init().ru_maxrss
+        }
+    }
+
+    /// The memory use of the process is only that of the split if no other tests run in it at the same time,
+    /// so the test runs itself again in a process of its own with this variable set.
+    #[cfg(target_os = "linux")]
+    const ALONE_VAR: &str = "SPLITTER_TEST_ALONE";
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn splits_without_reading_whole_file_into_memory() {
+        const LEN: u64 = 64 * 1024 * 1024;
+
+        if std::env::var_os(ALONE_VAR).is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "split::tests::splits_without_reading_whole_file_into_memory",
+                    "--exact",
+                    "--quiet",
+                ])
+                .env(ALONE_VAR, "1")
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stdout)
+            );
+            return;
+        }
+
+        let dir = TempDir::new();
+        let path = dir.path().join("sparse.bin");
+        // A sparse file takes no room on the disk until the parts are written.
+        fs::File::create(&path).unwrap().set_len(LEN).unwrap();
+
+        let before = max_rss();
+        let outcome = test_util::split(&path, &["--size", "20MiB"]).unwrap();
+        let after = max_rss();
+
+        assert_eq!(
+            outcome.part_lens.unwrap(),
+            fixed_chunks(LEN, 20 * 1024 * 1024)
+        );
+        assert!(
+            after - before < 32 * 1024,
+            "{} KiB more were used",
+            after - before
+        );
+    }
+
+    #[test]
+    fn pads_to_suffix_length() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(900));
+
+        for &(suffix_length, last_part) in
+            &[("1", "file.bin-split-9"), ("4", "file.bin-split-0009")]
+        {
+            let split_folder =
+                test_util::split(&path, &["--size", "100", "--suffix-length", suffix_length])
+                    .unwrap()
+                    .output
+                    .unwrap();
+            let manifest = Manifest::read(&split_folder).unwrap().unwrap();
+            assert_eq!(manifest.parts.last().unwrap().file_name, last_part);
+
+            test_util::round_trip(&split_folder, &test_util::data(900));
+            fs::remove_dir_all(&split_folder).unwrap();
+        }
+
+        let err = test_util::split(&path, &["--size", "90", "--suffix-length", "1"])
+            .err()
+            .unwrap();
+        assert_eq!(err.0, "A suffix length of 1 is too short for 10 parts.");
+    }
+
+    #[test]
+    fn splits_into_output_dir() {
+        let dir = TempDir::new();
+        fs::create_dir(dir.path().join("source")).unwrap();
+        let path = dir.write("source/file.bin", &test_util::data(1000));
+        // The folders in between do not exist yet.
+        let output_dir = dir.path().join("other").join("disk");
 
-    let mut buffers = Vec::<Vec<u8>>::new();
-    for part in &parts {
-        buffers.push(vec![0_u8; *part as usize]);
+        let split_folder = test_util::split(
+            &path,
+            &["--size", "300", "--out", output_dir.to_str().unwrap()],
+        )
+        .unwrap()
+        .output
+        .unwrap();
+        assert_eq!(split_folder, output_dir.join("file.bin-split"));
+        assert_eq!(test_util::files_in(&dir.path().join("source")), [path]);
+
+        test_util::round_trip(&split_folder, &test_util::data(1000));
+    }
+
+    #[test]
+    fn picks_next_free_split_folder() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+
+        let first = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+        let second = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+        assert_eq!(first, dir.path().join("file.bin-split"));
+        assert_eq!(second, dir.path().join("file.bin-split-2"));
+
+        // The parts are still named after the file, so they can be joined by their names alone.
+        fs::remove_file(second.join(crate::manifest::FILE_NAME)).unwrap();
+        let parts = test_util::files_in(&second);
+        assert!(parts.iter().all(|part| part
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("file.bin-split-")));
+        test_util::round_trip_parts(parts, &test_util::data(1000));
+    }
+
+    #[test]
+    fn replaces_split_folder_with_force() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+        assert_eq!(test_util::files_in(&split_folder).len(), 4 + 1);
+
+        // The stale parts are removed, not left next to the new ones.
+        let outcome = test_util::split(&path, &["--size", "500", "--force"]).unwrap();
+        assert_eq!(outcome.output.unwrap(), split_folder);
+        assert_eq!(test_util::files_in(&split_folder).len(), 2 + 1);
+
+        let notes = split_folder.join("notes.txt");
+        fs::write(&notes, "not a part").unwrap();
+        let err = test_util::split(&path, &["--size", "300", "--force"])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.0,
+            format!(
+                "Folder {} already exists and contains files that are not parts of file.bin. Refusing to remove it.",
+                split_folder.to_string_lossy()
+            )
+        );
+        assert!(notes.exists());
+        assert_eq!(test_util::files_in(&split_folder).len(), 2 + 1 + 1);
+    }
+
+    #[test]
+    fn refuses_to_split_parts() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300", "--headers"])
+            .unwrap()
+            .output
+            .unwrap();
+
+        // One part goes by its name, the other one by its header.
+        let named_part = test_util::files_in(&split_folder)[1].clone();
+        let renamed_part = dir.path().join("renamed.bin");
+        fs::copy(&test_util::files_in(&split_folder)[2], &renamed_part).unwrap();
+
+        for part in &[named_part, renamed_part] {
+            let err = test_util::split(part, &["--size", "100"]).err().unwrap();
+            assert_eq!(
+                err.0,
+                format!(
+                    "{} looks like a part of a split. To join it, select its split folder instead, or use --force-split to split it anyway.",
+                    part.to_string_lossy()
+                )
+            );
+
+            test_util::split(part, &["--size", "100", "--force-split"]).unwrap();
+        }
+    }
+
+    #[test]
+    fn shortens_long_file_names() {
+        let dir = TempDir::new();
+        let file_name = format!("{}.bin", "a".repeat(246));
+        let path = dir.write(&file_name, &test_util::data(1000));
+
+        let split_folder = test_util::split(&path, &["--size", "100"])
+            .unwrap()
+            .output
+            .unwrap();
+        let names = std::iter::once(split_folder.clone())
+            .chain(test_util::files_in(&split_folder))
+            .map(|path| path.file_name().unwrap().len())
+            .collect::<Vec<usize>>();
+        assert!(
+            names.iter().all(|len| *len <= MAX_FILE_NAME_LEN),
+            "{:?}",
+            names
+        );
+        let manifest = Manifest::read(&split_folder).unwrap().unwrap();
+        assert_eq!(manifest.original_file_name(), file_name);
+
+        test_util::round_trip(&split_folder, &test_util::data(1000));
+    }
+
+    #[test]
+    fn resume_rewrites_damaged_parts() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+        let parts = test_util::files_in(&split_folder);
+        let originals = parts
+            .iter()
+            .map(|part| fs::read(part).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+
+        // One part was cut short, and another one has its full length but was not written completely, as after a power loss.
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&parts[1])
+            .unwrap()
+            .set_len(100)
+            .unwrap();
+        let mut damaged = originals[2].clone();
+        damaged[150..].iter_mut().for_each(|byte| *byte = 0);
+        fs::write(&parts[2], &damaged).unwrap();
+
+        test_util::split(&path, &["--size", "300", "--resume"]).unwrap();
+        for (part, original) in parts.iter().zip(&originals) {
+            assert_eq!(&fs::read(part).unwrap(), original, "{:?}", part);
+        }
     }
 
-    buffers
+    #[test]
+    fn keeps_partial_folder_with_resume() {
+        let dir = TempDir::new();
+        let path = dir.write("file.bin", &test_util::data(1000));
+        let split_folder = test_util::split(&path, &["--size", "300"])
+            .unwrap()
+            .output
+            .unwrap();
+
+        // The split is taken to have been interrupted, and a folder in the way of a part makes resuming it fail.
+        let temporary_folder = get_temporary_split_folder(&split_folder);
+        fs::rename(&split_folder, &temporary_folder).unwrap();
+        let blocked_part = test_util::files_in(&temporary_folder)[1].clone();
+        fs::remove_file(&blocked_part).unwrap();
+        fs::create_dir(&blocked_part).unwrap();
+
+        let err = test_util::split(&path, &["--size", "300", "--resume"])
+            .err()
+            .unwrap();
+        assert!(
+            err.0
+                .ends_with("Run the split again with --resume to continue it."),
+            "{}",
+            err
+        );
+        assert_eq!(test_util::files_in(&temporary_folder).len(), 4 + 1);
+
+        fs::remove_dir(&blocked_part).unwrap();
+        test_util::split(&path, &["--size", "300", "--resume"]).unwrap();
+        assert!(!temporary_folder.exists());
+
+        test_util::split(&path, &["--size", "500", "--force"]).unwrap();
+        assert_eq!(test_util::files_in(&split_folder).len(), 2 + 1);
+        assert!(!get_replaced_split_folder(&split_folder).exists());
+    }
 }
@@ -0,0 +1,101 @@
+//! Helpers for the tests of the other modules.
+
+use crate::{args::Options, join, outcome::Outcome, split, Error};
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A folder of its own for a test to write into, which is removed again when it is dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> TempDir {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = env::temp_dir().join(format!(
+            "splitter-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes a file named `name` into the folder and returns its path.
+    pub fn write(&self, name: &str, contents: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns `len` bytes that do not repeat in short cycles, so that parts in the wrong order or at the wrong offset are noticed.
+pub fn data(len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|index| (index * 7 + index / 251) as u8)
+        .collect()
+}
+
+/// Parses `args` like the command line, with `--quiet` added so that the tests print nothing.
+pub fn options(args: &[&str]) -> Options {
+    Options::parse(args.iter().chain(&["--quiet"]).map(OsString::from)).unwrap()
+}
+
+/// Splits the file at `path` with the options in `args`.
+pub fn split(path: &Path, args: &[&str]) -> Result<Outcome, Error> {
+    let (stdin, stdout, stderr) = (io::stdin(), io::stdout(), io::stderr());
+    split::split(
+        &mut stdin.lock(),
+        &mut stdout.lock(),
+        &mut stderr.lock(),
+        path.to_path_buf(),
+        &options(args),
+    )
+}
+
+/// Joins the files at `paths` with the options in `args`.
+pub fn join(paths: Vec<PathBuf>, args: &[&str]) -> Result<Outcome, Error> {
+    let stdout = io::stdout();
+    join::join(&mut stdout.lock(), paths, &options(args))
+}
+
+/// Joins the parts in `split_folder` into a file next to it, checks that it is `expected` and removes it again.
+/// Returns the outcome of the join.
+pub fn round_trip(split_folder: &Path, expected: &[u8]) -> Outcome {
+    round_trip_parts(files_in(split_folder), expected)
+}
+
+/// Like [`round_trip`], but joins the `parts` in the order they are given.
+pub fn round_trip_parts(parts: Vec<PathBuf>, expected: &[u8]) -> Outcome {
+    let output = parts[0].parent().unwrap().with_file_name("joined");
+    let outcome = join(parts, &["--output", output.to_str().unwrap()]).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), expected);
+    fs::remove_file(&output).unwrap();
+    outcome
+}
+
+/// Returns the paths of all files in `dir`, sorted by name.
+pub fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    paths
+}
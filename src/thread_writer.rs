@@ -0,0 +1,140 @@
+use crate::buffer_pool::BufferPool;
+use std::{
+    fs,
+    io::{self, Write},
+    sync::mpsc::{self, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+/// How many chunks can be queued up for the thread before the writer has to wait.
+/// This bounds the memory used for chunks that were passed on but not written yet.
+const QUEUE_LEN: usize = 16;
+
+enum Message {
+    Data(Vec<u8>),
+    /// Asks the thread to say once everything before this was written.
+    Flush(SyncSender<()>),
+}
+
+/// Writes to a file on a thread of its own, so that several files can be written at the same time.
+///
+/// What is written is only queued up. If writing it to the file fails, the error is returned by a later write or by [`Write::flush`],
+/// which waits until everything was written.
+pub struct ThreadWriter {
+    sender: Option<SyncSender<Message>>,
+    handle: Option<JoinHandle<io::Result<()>>>,
+    /// What is written is copied into these, as it is only borrowed.
+    buffers: BufferPool,
+}
+
+impl ThreadWriter {
+    pub fn new(file: fs::File) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_LEN);
+        let buffers = BufferPool::new();
+        let recycler = buffers.recycler();
+        let handle = thread::spawn(move || {
+            let mut file = file;
+            for message in receiver {
+                match message {
+                    Message::Data(data) => {
+                        file.write_all(&data)?;
+                        let _ = recycler.send(data);
+                    }
+                    Message::Flush(done) => {
+                        file.flush()?;
+                        let _ = done.send(());
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        ThreadWriter {
+            sender: Some(sender),
+            handle: Some(handle),
+            buffers,
+        }
+    }
+
+    fn send(&mut self, message: Message) -> io::Result<()> {
+        match &self.sender {
+            Some(sender) if sender.send(message).is_ok() => Ok(()),
+            // The thread only stops early if writing failed, and that is the error to report.
+            _ => Err(self.stop().err().unwrap_or_else(stopped)),
+        }
+    }
+
+    /// Stops the thread once it has written everything that was queued up and returns the error it stopped with, if any.
+    fn stop(&mut self) -> io::Result<()> {
+        self.sender = None;
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("The writer thread panicked"))),
+            None => Err(stopped()),
+        }
+    }
+}
+
+/// The error for writes after the thread already stopped, which was reported before.
+fn stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Writing already failed")
+}
+
+impl Write for ThreadWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = self.buffers.copy(buf);
+        self.send(Message::Data(data))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let (done_sender, done) = mpsc::sync_channel(1);
+        self.send(Message::Flush(done_sender))?;
+        match done.recv() {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.stop().err().unwrap_or_else(stopped)),
+        }
+    }
+}
+
+impl Drop for ThreadWriter {
+    fn drop(&mut self) {
+        // Errors are reported by `flush`, which has to be called before dropping the writer, like for a `BufWriter`.
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn writes_in_order() {
+        let dir = TempDir::new();
+        let path = dir.path().join("part");
+        let mut writer = ThreadWriter::new(fs::File::create(&path).unwrap());
+        for chunk in crate::test_util::data(10_000).chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), crate::test_util::data(10_000));
+    }
+
+    #[test]
+    fn returns_the_original_error() {
+        let dir = TempDir::new();
+        let path = dir.write("part", b"");
+        // A file that was only opened for reading cannot be written to.
+        let mut writer = ThreadWriter::new(fs::File::open(&path).unwrap());
+
+        let err = writer
+            .write_all(b"data")
+            .and_then(|()| writer.flush())
+            .unwrap_err();
+        assert_ne!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert!(err.raw_os_error().is_some(), "{:?}", err);
+    }
+}
@@ -0,0 +1,451 @@
+//! Writing a file into a spanned ZIP archive, given with `--zip-volumes`, so that it can be put back together
+//! with any zip tool that supports spanned archives instead of splitter.
+//!
+//! The archive has a single entry that is stored without compression. Its volumes are called
+//! `<name>.z01`, `<name>.z02` and so on, except for the last one, which is `<name>.zip`.
+//! The first volume starts with the spanning signature and no record is cut between two volumes,
+//! only the content of the file is, as the [ZIP specification](https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT) requires.
+//! Offsets in the records are relative to the start of the volume they point into.
+//!
+//! Entries of files of 4 GiB or more use the ZIP64 extensions.
+
+use crate::Error;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The smallest volume size, which is also the smallest one Info-ZIP allows.
+/// Every record has to fit into a single volume.
+pub const MIN_VOLUME_SIZE: u64 = 64 * 1024;
+
+/// The signature the first volume of a spanned archive starts with.
+const SPANNING_SIGNATURE: u32 = 0x08074b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const ZIP64_END_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const END_SIGNATURE: u32 = 0x06054b50;
+
+/// The header ID of the extra field with the sizes of ZIP64 entries.
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+/// The header ID of the extra field with the modification time as a Unix timestamp.
+const TIMESTAMP_EXTRA_ID: u16 = 0x5455;
+
+/// The general purpose flag that marks the filename as UTF-8.
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// The value of size, offset and count fields whose actual value is in a ZIP64 record instead.
+const ZIP64_MARKER: u32 = u32::MAX;
+
+/// The length of the end of central directory record, without a comment.
+const END_LEN: u64 = 22;
+/// The length of the ZIP64 end of central directory record and its locator together.
+const ZIP64_END_LEN: u64 = 56 + 20;
+
+/// Returns the filename of volume `number`, starting at 1, of the `volume_count` volumes of the archive of the file called `file_name`.
+///
+/// ```
+/// # use splitter::zip::volume_file_name;
+/// assert_eq!(volume_file_name("video.mkv", 1, 3), "video.mkv.z01");
+/// assert_eq!(volume_file_name("video.mkv", 2, 3), "video.mkv.z02");
+/// assert_eq!(volume_file_name("video.mkv", 3, 3), "video.mkv.zip");
+/// ```
+pub fn volume_file_name(file_name: &str, number: usize, volume_count: usize) -> String {
+    if number == volume_count {
+        format!("{}.zip", file_name)
+    } else {
+        format!("{}.z{:02}", file_name, number)
+    }
+}
+
+/// The file that is put into the archive.
+pub struct Entry<'a> {
+    pub file_name: &'a str,
+    pub len: u64,
+    /// When the file was last modified, in seconds since the Unix epoch.
+    pub modified_at: Option<u64>,
+}
+
+impl Entry<'_> {
+    /// Returns whether the entry needs the ZIP64 extensions because its size does not fit into the regular fields.
+    fn is_zip64(&self) -> bool {
+        self.len >= ZIP64_MARKER as u64
+    }
+
+    /// Returns the fields of the headers that the local header and the central header have in common, from the version on.
+    fn common_fields(&self, crc32: u32, extra_len: usize) -> Vec<u8> {
+        let (time, date) = to_dos_date_time(self.modified_at.unwrap_or(0));
+        let flags = if self.file_name.is_ascii() {
+            0
+        } else {
+            UTF8_FLAG
+        };
+        let len = if self.is_zip64() {
+            ZIP64_MARKER
+        } else {
+            self.len as u32
+        };
+
+        let mut fields = Vec::new();
+        // Version 4.5 is needed for the ZIP64 extensions, 1.0 is enough for stored entries otherwise.
+        put_u16(&mut fields, if self.is_zip64() { 45 } else { 10 });
+        put_u16(&mut fields, flags);
+        // The method 0 means that the entry is stored without compression.
+        put_u16(&mut fields, 0);
+        put_u16(&mut fields, time);
+        put_u16(&mut fields, date);
+        put_u32(&mut fields, crc32);
+        // As the entry is stored, its compressed size is its size.
+        put_u32(&mut fields, len);
+        put_u32(&mut fields, len);
+        put_u16(&mut fields, self.file_name.len() as u16);
+        put_u16(&mut fields, extra_len as u16);
+        fields
+    }
+
+    /// Returns the extra fields, which are the same in the local header and the central header.
+    fn extra_fields(&self) -> Vec<u8> {
+        let mut extra = Vec::new();
+
+        if self.is_zip64() {
+            put_u16(&mut extra, ZIP64_EXTRA_ID);
+            put_u16(&mut extra, 16);
+            put_u64(&mut extra, self.len);
+            put_u64(&mut extra, self.len);
+        }
+
+        // The DOS time is in local time, which is not known, so the exact time is added as well.
+        if let Some(modified_at) = self
+            .modified_at
+            .filter(|modified_at| *modified_at <= u32::MAX as u64)
+        {
+            put_u16(&mut extra, TIMESTAMP_EXTRA_ID);
+            put_u16(&mut extra, 5);
+            // Only the modification time is given.
+            extra.push(1);
+            put_u32(&mut extra, modified_at as u32);
+        }
+
+        extra
+    }
+
+    fn local_header(&self, crc32: u32) -> Vec<u8> {
+        let extra = self.extra_fields();
+
+        let mut header = Vec::new();
+        put_u32(&mut header, LOCAL_HEADER_SIGNATURE);
+        header.extend_from_slice(&self.common_fields(crc32, extra.len()));
+        header.extend_from_slice(self.file_name.as_bytes());
+        header.extend_from_slice(&extra);
+        header
+    }
+
+    /// Returns the central header of the entry, whose local header is at `offset` on the first volume.
+    fn central_header(&self, crc32: u32, offset: u64) -> Vec<u8> {
+        let extra = self.extra_fields();
+
+        let mut header = Vec::new();
+        put_u32(&mut header, CENTRAL_HEADER_SIGNATURE);
+        // The version the archive was made with, with the upper byte being 0 for MS-DOS attributes.
+        put_u16(&mut header, if self.is_zip64() { 45 } else { 10 });
+        header.extend_from_slice(&self.common_fields(crc32, extra.len()));
+        // The length of the comment.
+        put_u16(&mut header, 0);
+        // The number of the volume the local header is on.
+        put_u16(&mut header, 0);
+        // The internal and external file attributes.
+        put_u16(&mut header, 0);
+        put_u32(&mut header, 0);
+        put_u32(&mut header, offset as u32);
+        header.extend_from_slice(self.file_name.as_bytes());
+        header.extend_from_slice(&extra);
+        header
+    }
+
+    /// Returns the end records, which are on the last volume `volume` at `offset`, for the central header
+    /// of `central_len` bytes on volume `central_volume` at `central_offset`.
+    /// With `zip64`, the ZIP64 end records come first.
+    fn end_records(
+        &self,
+        zip64: bool,
+        volume: usize,
+        offset: u64,
+        central_volume: usize,
+        central_offset: u64,
+        central_len: u64,
+    ) -> Vec<u8> {
+        let entries_on_volume = (central_volume == volume) as u64;
+
+        let mut end = Vec::new();
+        if zip64 {
+            put_u32(&mut end, ZIP64_END_SIGNATURE);
+            // The length of the rest of the record.
+            put_u64(&mut end, 44);
+            put_u16(&mut end, 45);
+            put_u16(&mut end, 45);
+            put_u32(&mut end, volume as u32);
+            put_u32(&mut end, central_volume as u32);
+            put_u64(&mut end, entries_on_volume);
+            put_u64(&mut end, 1);
+            put_u64(&mut end, central_len);
+            put_u64(&mut end, central_offset);
+
+            put_u32(&mut end, ZIP64_LOCATOR_SIGNATURE);
+            put_u32(&mut end, volume as u32);
+            put_u64(&mut end, offset);
+            put_u32(&mut end, volume as u32 + 1);
+        }
+        put_u32(&mut end, END_SIGNATURE);
+        put_u16(&mut end, volume.min(u16::MAX as usize) as u16);
+        put_u16(&mut end, central_volume.min(u16::MAX as usize) as u16);
+        put_u16(&mut end, entries_on_volume as u16);
+        put_u16(&mut end, 1);
+        put_u32(&mut end, central_len as u32);
+        put_u32(&mut end, central_offset.min(ZIP64_MARKER as u64) as u32);
+        // The length of the comment.
+        put_u16(&mut end, 0);
+        end
+    }
+
+    /// Returns how many volumes of `volume_size` bytes the archive of this entry has.
+    pub fn volume_count(&self, volume_size: u64) -> usize {
+        let mut position = Position::new(volume_size);
+        position.place(SPANNING_SIGNATURE_LEN);
+        position.place(self.local_header(0).len() as u64);
+        let mut remaining = self.len;
+        while remaining > 0 {
+            remaining -= position.advance(remaining);
+        }
+        let central_offset = position.place(self.central_header(0, 0).len() as u64);
+        position.place(end_len(needs_zip64_end(self, &position, central_offset)));
+        position.volume + 1
+    }
+}
+
+/// The length of the spanning signature.
+const SPANNING_SIGNATURE_LEN: u64 = 4;
+
+/// Returns whether the end records need the ZIP64 extensions. They are put after the central header of the `entry`,
+/// which is at `central_offset`, with the `position` being right after it.
+/// As they may not fit onto the current volume anymore, it is assumed that they go onto the next one.
+fn needs_zip64_end(entry: &Entry, position: &Position, central_offset: u64) -> bool {
+    entry.is_zip64()
+        || position.volume + 1 >= u16::MAX as usize
+        || central_offset >= ZIP64_MARKER as u64
+}
+
+/// Returns the length of the end records, with the ZIP64 ones if `zip64` is true.
+fn end_len(zip64: bool) -> u64 {
+    if zip64 {
+        ZIP64_END_LEN + END_LEN
+    } else {
+        END_LEN
+    }
+}
+
+/// Writes the archive of an [`Entry`] into its volumes. What is written to it is the content of the file,
+/// which is cut wherever a volume is full. The volumes are created as they are needed.
+pub struct Writer<'a> {
+    entry: &'a Entry<'a>,
+    crc32: u32,
+    volume_paths: &'a [PathBuf],
+    open_options: &'a fs::OpenOptions,
+    position: Position,
+    /// The volume that is currently written to, along with its index.
+    volume: Option<(usize, fs::File)>,
+}
+
+impl<'a> Writer<'a> {
+    /// Starts the archive of the `entry`, whose CRC32 checksum is `crc32`, in the volumes at `volume_paths`,
+    /// which are as many as [`Entry::volume_count`] returns for `volume_size`.
+    /// The volumes are created with the `open_options`.
+    pub fn new(
+        entry: &'a Entry,
+        crc32: u32,
+        volume_paths: &'a [PathBuf],
+        volume_size: u64,
+        open_options: &'a fs::OpenOptions,
+    ) -> Result<Self, Error> {
+        let mut writer = Writer {
+            entry,
+            crc32,
+            volume_paths,
+            open_options,
+            position: Position::new(volume_size),
+            volume: None,
+        };
+
+        writer.write_record(&SPANNING_SIGNATURE.to_le_bytes())?;
+        writer.write_record(&entry.local_header(crc32))?;
+
+        Ok(writer)
+    }
+
+    /// Returns the volume that is currently written to, creating it if it was not created yet.
+    fn volume(&mut self) -> Result<&mut fs::File, Error> {
+        let index = self.position.volume;
+        if !matches!(self.volume, Some((current, _)) if current == index) {
+            let path = &self.volume_paths[index];
+            let file = self.open_options.open(path).map_err(Error::io(format!(
+                "Failed to create output file {}.",
+                path.to_string_lossy()
+            )))?;
+            self.volume = Some((index, file));
+        }
+
+        Ok(self.volume.as_mut().map(|(_, file)| file).unwrap())
+    }
+
+    /// Writes a record, which has to be on a single volume, and returns its offset in that volume.
+    fn write_record(&mut self, record: &[u8]) -> Result<u64, Error> {
+        let offset = self.position.place(record.len() as u64);
+        self.volume()?
+            .write_all(record)
+            .map_err(Error::io("Failed to write output."))?;
+        Ok(offset)
+    }
+
+    /// Writes the central header and the end records after the content of the file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        let central_header = self
+            .entry
+            .central_header(self.crc32, SPANNING_SIGNATURE_LEN);
+        let central_offset = self.write_record(&central_header)?;
+        let central_volume = self.position.volume;
+
+        let zip64 = needs_zip64_end(self.entry, &self.position, central_offset);
+        // The content of the end records depends on where they go, which is only known once they are placed.
+        let mut end_position = self.position.clone();
+        let end_offset = end_position.place(end_len(zip64));
+        let end = self.entry.end_records(
+            zip64,
+            end_position.volume,
+            end_offset,
+            central_volume,
+            central_offset,
+            central_header.len() as u64,
+        );
+        self.write_record(&end)?;
+
+        if let Some((_, volume)) = self.volume {
+            volume
+                .sync_all()
+                .map_err(Error::io("Failed to write output."))?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for Writer<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.position.advance(buf.len() as u64) as usize;
+        self.volume()
+            .map_err(|err| io::Error::other(err.0))?
+            .write_all(&buf[..len])?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.volume {
+            Some((_, volume)) => volume.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where in the archive the next byte goes.
+#[derive(Clone)]
+struct Position {
+    volume_size: u64,
+    /// The index of the volume, starting at 0.
+    volume: usize,
+    /// The offset in the volume.
+    offset: u64,
+}
+
+impl Position {
+    fn new(volume_size: u64) -> Self {
+        Position {
+            volume_size,
+            volume: 0,
+            offset: 0,
+        }
+    }
+
+    /// Makes room for a record of `len` bytes, which has to be on a single volume,
+    /// and returns its offset in the volume it starts on, which is the current one afterwards.
+    fn place(&mut self, len: u64) -> u64 {
+        if self.offset + len > self.volume_size {
+            self.volume += 1;
+            self.offset = 0;
+        }
+        let offset = self.offset;
+        self.offset += len;
+        offset
+    }
+
+    /// Makes room for up to `len` bytes of content, going on to the next volume if the current one is full,
+    /// and returns how many of them fit onto the volume, which is the current one afterwards.
+    fn advance(&mut self, len: u64) -> u64 {
+        if self.offset == self.volume_size {
+            self.volume += 1;
+            self.offset = 0;
+        }
+        let len = len.min(self.volume_size - self.offset);
+        self.offset += len;
+        len
+    }
+}
+
+/// Converts `timestamp`, in seconds since the Unix epoch, to the time and the date in the format of MS-DOS,
+/// which cannot go back further than 1980.
+fn to_dos_date_time(timestamp: u64) -> (u16, u16) {
+    let days = timestamp / (24 * 60 * 60);
+    let seconds = timestamp % (24 * 60 * 60);
+
+    // The civil date of the day, following http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    if !(1980..=2107).contains(&year) {
+        // The earliest date there is, 1980-01-01 at midnight.
+        return (0, (1 << 5) | 1);
+    }
+
+    let time = ((seconds / 3600) << 11) | (((seconds / 60) % 60) << 5) | ((seconds % 60) / 2);
+    let date = ((year - 1980) << 9) | (month << 5) | day;
+    (time as u16, date as u16)
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Returns the paths of the `volume_count` volumes of the archive of the file called `file_name` in `folder`.
+pub fn volume_paths(folder: &Path, file_name: &str, volume_count: usize) -> Vec<PathBuf> {
+    (1..=volume_count)
+        .map(|number| folder.join(volume_file_name(file_name, number, volume_count)))
+        .collect()
+}